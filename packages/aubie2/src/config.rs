@@ -0,0 +1,603 @@
+//! Robot hardware descriptions.
+//!
+//! A [`RobotConfig`] lists which ports each piece of hardware is plugged into,
+//! so that every robot can share the same setup code and only differ in
+//! constants.
+
+use std::{cell::RefCell, io, path::Path, rc::Rc};
+
+use evian::{
+    drivetrain::{Drivetrain, model::Differential},
+    math::Angle,
+    tracking::{
+        Gyro, RotarySensor,
+        wheeled::{TrackingWheel, WheeledTracking},
+    },
+};
+use log::{info, warn};
+use vexide::{
+    adi::AdiPort,
+    display::Display,
+    math::Direction,
+    peripherals::{DynamicPeripherals, Peripherals},
+    prelude::{AdiDigitalOut, Controller, Gearset, InertialSensor, Motor},
+    smart::SmartPort,
+};
+
+use crate::{
+    hardware::{
+        calibration::{ImuCalibration, ImuCalibrationConfig, ImuCalibrationError, calibrate_imus},
+        encoder::{Amt102V, CheckedEncoder},
+        health::{
+            AdiPortKind, ExpectedAdiPort, ExpectedDevice, HealthReport, SmartDeviceKind,
+            check_adi_ports, check_devices,
+        },
+        imu::FusedImu,
+        odometry::{
+            OdometryCalibration, OdometryCalibrationConfig, OdometryCalibrationError,
+            OdometryReading, OdometrySetup, calibrate_odometry,
+        },
+        pneumatics::{AirTank, Cylinder, Piston},
+    },
+    subsystems::intake::{Intake, IntakeVoltages, JamConfig},
+};
+
+/// A motor and the smart port it's plugged into.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct MotorConfig {
+    pub port: u8,
+    pub gearset: Gearset,
+    pub direction: Direction,
+}
+
+impl MotorConfig {
+    pub const fn new(port: u8, gearset: Gearset, direction: Direction) -> Self {
+        Self {
+            port,
+            gearset,
+            direction,
+        }
+    }
+
+    fn build(&self, ports: &mut Ports) -> Motor {
+        Motor::new(ports.smart(self.port), self.gearset, self.direction)
+    }
+}
+
+/// A piston and the ADI port (`'a'` through `'h'`) its valve is plugged into.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PistonConfig {
+    /// Name shown when reporting problems with the piston's port.
+    pub name: &'static str,
+    pub port: char,
+    pub inverted: bool,
+    pub cylinder: Cylinder,
+
+    /// Number of cylinders plumbed to the valve.
+    pub cylinders: usize,
+}
+
+impl PistonConfig {
+    /// A single 50 mm cylinder with non-inverted wiring.
+    pub const fn new(name: &'static str, port: char) -> Self {
+        Self {
+            name,
+            port,
+            inverted: false,
+            cylinder: Cylinder::VEX_50MM,
+            cylinders: 1,
+        }
+    }
+
+    pub const fn inverted(mut self) -> Self {
+        self.inverted = true;
+        self
+    }
+
+    pub const fn with_cylinders(mut self, cylinder: Cylinder, count: usize) -> Self {
+        self.cylinder = cylinder;
+        self.cylinders = count;
+        self
+    }
+
+    fn build(&self, ports: &mut Ports, tank: &AirTank) -> Piston {
+        let piston = Piston::new(AdiDigitalOut::new(ports.adi(self.port)))
+            .with_cylinders(self.cylinder, self.cylinders)
+            .with_tank(tank.clone());
+
+        if self.inverted {
+            piston.inverted()
+        } else {
+            piston
+        }
+    }
+}
+
+/// Drive motors, which also act as the robot's forward tracking wheels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrivetrainConfig<const N: usize> {
+    pub left: [MotorConfig; N],
+    pub right: [MotorConfig; N],
+
+    /// Drive wheel diameter in inches.
+    pub wheel_diameter: f64,
+
+    /// Distance between the left and right wheels in inches.
+    pub track_width: f64,
+
+    /// Ratio of wheel rotations to motor rotations, if the drive is geared.
+    pub gearing: Option<f64>,
+}
+
+/// An IMU and the factor its rotation is corrected by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImuConfig {
+    pub port: u8,
+
+    /// Multiplier applied to the IMU's rotation, found by spinning the robot a
+    /// known number of turns and dividing the true rotation by the measured one.
+    pub scale: f64,
+}
+
+impl ImuConfig {
+    /// An IMU with no scale correction.
+    pub const fn new(port: u8) -> Self {
+        Self { port, scale: 1.0 }
+    }
+
+    pub const fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+/// An unpowered tracking wheel on an AMT102-V encoder.
+///
+/// A forward wheel falls back to the drive motors on its side if its encoder
+/// fails, and a sideways wheel stops contributing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackingWheelConfig {
+    /// ADI ports (`'a'` through `'h'`) of the encoder's two channels.
+    pub top_port: char,
+    pub bottom_port: char,
+    pub direction: Direction,
+
+    /// Wheel diameter in inches.
+    pub diameter: f64,
+
+    /// Distance from the tracking center in inches.
+    pub offset: f64,
+}
+
+impl TrackingWheelConfig {
+    fn build(&self, ports: &mut Ports) -> CheckedEncoder<Amt102V> {
+        CheckedEncoder::new(
+            Amt102V::new(
+                ports.adi(self.top_port),
+                ports.adi(self.bottom_port),
+                self.direction,
+            ),
+            self.diameter,
+        )
+    }
+}
+
+/// Motors for each intake stage, and the intake's pneumatics.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntakeConfig {
+    pub front_bottom: &'static [MotorConfig],
+    pub back_bottom: &'static [MotorConfig],
+    pub back_top: &'static [MotorConfig],
+    pub front_top: &'static [MotorConfig],
+    pub voltages: IntakeVoltages,
+    pub hood: Option<PistonConfig>,
+    pub jam_detection: Option<JamConfig>,
+}
+
+/// Every port on a robot.
+///
+/// `N` is the number of motors on each side of the drivetrain and `P` is the
+/// number of standalone pistons.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RobotConfig<const N: usize, const P: usize> {
+    pub drivetrain: DrivetrainConfig<N>,
+
+    /// IMUs whose headings are fused into the robot's heading.
+    pub imus: &'static [ImuConfig],
+
+    /// Tracking wheel parallel to the drive wheels, used in place of the drive
+    /// encoders. Heading still comes from the IMUs, so this is ignored if none of
+    /// them calibrate.
+    pub forward_wheel: Option<TrackingWheelConfig>,
+    pub sideways_wheel: Option<TrackingWheelConfig>,
+    pub intake: IntakeConfig,
+
+    /// Pistons that aren't part of a subsystem, returned in the same order by
+    /// [`RobotConfig::build`].
+    pub pistons: [PistonConfig; P],
+
+    /// Number of air tanks feeding the pistons.
+    pub air_tanks: usize,
+}
+
+/// Hardware built from a [`RobotConfig`].
+pub struct RobotHardware<const P: usize> {
+    pub controller: Controller,
+    pub display: Display,
+    pub drivetrain: Drivetrain<Differential, WheeledTracking>,
+    pub intake: Intake,
+    pub pistons: [Piston; P],
+    pub air: AirTank,
+
+    /// Each IMU's calibration, in the order they were configured. IMUs that
+    /// failed to calibrate are left out of the heading, and if every one failed
+    /// the drivetrain tracks heading from its drive encoders instead.
+    pub imus: Vec<Result<ImuCalibration, ImuCalibrationError>>,
+}
+
+/// Hands out ports by number, panicking if one is used twice.
+struct Ports(DynamicPeripherals);
+
+impl Ports {
+    fn smart(&mut self, port: u8) -> SmartPort {
+        self.0
+            .take_smart_port(port)
+            .unwrap_or_else(|| panic!("Smart port {port} is used twice or doesn't exist."))
+    }
+
+    fn adi(&mut self, port: char) -> AdiPort {
+        let index = (port.to_ascii_lowercase() as u8).wrapping_sub(b'a') + 1;
+
+        self.0
+            .take_adi_port(index)
+            .unwrap_or_else(|| panic!("ADI port {port} is used twice or doesn't exist."))
+    }
+}
+
+/// Builds odometry from forward wheels and an optional sideways wheel.
+fn tracking<T: RotarySensor + 'static, const F: usize>(
+    forward: [TrackingWheel<T>; F],
+    sideways: Option<TrackingWheel<CheckedEncoder<Amt102V>>>,
+    heading: Angle,
+    gyro: Option<FusedImu>,
+) -> WheeledTracking {
+    match sideways {
+        Some(sideways) => WheeledTracking::new((0.0, 0.0), heading, forward, [sideways], gyro),
+        None => WheeledTracking::forward_only((0.0, 0.0), heading, forward, gyro),
+    }
+}
+
+impl<const N: usize, const P: usize> RobotConfig<N, P> {
+    /// Returns every smart device and ADI port this config claims.
+    pub fn expected_ports(&self) -> (Vec<ExpectedDevice>, Vec<ExpectedAdiPort>) {
+        let mut devices = Vec::new();
+        let mut adi_ports = Vec::new();
+
+        let mut motors = |name: &'static str, configs: &[MotorConfig]| {
+            devices.extend(configs.iter().map(|motor| ExpectedDevice {
+                name,
+                port: motor.port,
+                kind: SmartDeviceKind::Motor,
+            }));
+        };
+        motors("Left drive", &self.drivetrain.left);
+        motors("Right drive", &self.drivetrain.right);
+        motors("Intake FB", self.intake.front_bottom);
+        motors("Intake BB", self.intake.back_bottom);
+        motors("Intake BT", self.intake.back_top);
+        motors("Intake FT", self.intake.front_top);
+
+        devices.extend(self.imus.iter().map(|imu| ExpectedDevice {
+            name: "IMU",
+            port: imu.port,
+            kind: SmartDeviceKind::Imu,
+        }));
+
+        let wheels = [
+            ("Forward wheel", self.forward_wheel),
+            ("Sideways wheel", self.sideways_wheel),
+        ];
+        for (name, wheel) in wheels {
+            let Some(wheel) = wheel else {
+                continue;
+            };
+
+            for port in [wheel.top_port, wheel.bottom_port] {
+                adi_ports.push(ExpectedAdiPort {
+                    name,
+                    port,
+                    kind: AdiPortKind::Encoder,
+                });
+            }
+        }
+
+        adi_ports.extend(
+            self.intake
+                .hood
+                .iter()
+                .chain(self.pistons.iter())
+                .map(|piston| ExpectedAdiPort {
+                    name: piston.name,
+                    port: piston.port,
+                    kind: AdiPortKind::DigitalOut,
+                }),
+        );
+
+        (devices, adi_ports)
+    }
+
+    /// Checks that every configured smart port has the expected device plugged in.
+    ///
+    /// This doesn't need the ports to be claimed, so it can be called before
+    /// [`RobotConfig::build`] spends several seconds calibrating IMUs.
+    pub fn check_devices(&self) -> HealthReport {
+        check_devices(&self.expected_ports().0)
+    }
+
+    /// Checks that every configured ADI port is set up as expected.
+    ///
+    /// ADI ports are only configured once [`RobotConfig::build`] has claimed
+    /// them, so this should be called afterwards.
+    pub fn check_adi_ports(&self) -> HealthReport {
+        check_adi_ports(&self.expected_ports().1)
+    }
+
+    /// Calibrates every IMU, returning the ones that succeeded fused together.
+    async fn build_imus(
+        &self,
+        controller: &mut Controller,
+        display: &mut Display,
+        ports: &mut Ports,
+    ) -> (
+        Option<FusedImu>,
+        Vec<Result<ImuCalibration, ImuCalibrationError>>,
+    ) {
+        let mut imus: Vec<InertialSensor> = self
+            .imus
+            .iter()
+            .map(|imu| InertialSensor::new(ports.smart(imu.port)))
+            .collect();
+        let calibrations = calibrate_imus(
+            controller,
+            display,
+            &mut imus,
+            ImuCalibrationConfig::default(),
+        )
+        .await;
+
+        let calibrated: Vec<_> = imus
+            .into_iter()
+            .zip(self.imus)
+            .zip(&calibrations)
+            .filter(|(_, calibration)| calibration.is_ok())
+            .map(|((imu, config), _)| (imu, config.scale))
+            .collect();
+
+        (
+            (!calibrated.is_empty()).then(|| FusedImu::new(calibrated)),
+            calibrations,
+        )
+    }
+
+    /// Replaces hand-measured odometry constants with calibrated ones.
+    ///
+    /// Tracking wheels are only updated if they're configured and were
+    /// calibrated.
+    pub fn with_odometry(mut self, calibration: &OdometryCalibration) -> Self {
+        self.drivetrain.wheel_diameter = calibration.wheel_diameter;
+        self.drivetrain.track_width = calibration.track_width;
+
+        for (wheel, calibration) in [
+            (&mut self.forward_wheel, calibration.forward_wheel),
+            (&mut self.sideways_wheel, calibration.sideways_wheel),
+        ] {
+            if let (Some(wheel), Some(calibration)) = (wheel, calibration) {
+                wheel.diameter = calibration.diameter;
+                wheel.offset = calibration.offset;
+            }
+        }
+
+        self
+    }
+
+    /// Loads odometry constants saved by [`RobotConfig::calibrate_odometry`],
+    /// keeping the config's own if there's no saved calibration or it isn't
+    /// [valid](OdometryCalibration::is_valid).
+    pub fn with_saved_odometry(self, path: impl AsRef<Path>) -> Self {
+        match OdometryCalibration::load(&path) {
+            Ok(calibration) => {
+                info!(
+                    "Loaded odometry calibration from {}.",
+                    path.as_ref().display()
+                );
+                self.with_odometry(&calibration)
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => self,
+            Err(error) => {
+                warn!("Couldn't load odometry calibration: {error}");
+                self
+            }
+        }
+    }
+
+    /// Claims the drivetrain, IMUs and tracking wheels and runs
+    /// [`calibrate_odometry`] on them.
+    ///
+    /// This is used in place of [`RobotConfig::build`], usually when
+    /// [`calibration_requested`](crate::hardware::odometry::calibration_requested)
+    /// at boot. With the default `config.save_path`,
+    /// [`RobotConfig::with_saved_odometry`] picks up the results on the next run.
+    pub async fn calibrate_odometry(
+        &self,
+        peripherals: Peripherals,
+        config: OdometryCalibrationConfig,
+    ) -> Result<OdometryCalibration, OdometryCalibrationError> {
+        let mut peripherals = DynamicPeripherals::new(peripherals);
+        let mut controller = peripherals.take_primary_controller().unwrap();
+        let mut display = peripherals.take_display().unwrap();
+        let mut ports = Ports(peripherals);
+
+        let (gyro, _) = self
+            .build_imus(&mut controller, &mut display, &mut ports)
+            .await;
+        let gyro = gyro.ok_or(OdometryCalibrationError::Sensor)?;
+
+        let drivetrain = &self.drivetrain;
+        let left = Rc::new(RefCell::new(
+            drivetrain.left.map(|motor| motor.build(&mut ports)),
+        ));
+        let right = Rc::new(RefCell::new(
+            drivetrain.right.map(|motor| motor.build(&mut ports)),
+        ));
+        let forward_wheel = self.forward_wheel.map(|wheel| wheel.build(&mut ports));
+        let sideways_wheel = self.sideways_wheel.map(|wheel| wheel.build(&mut ports));
+
+        let read = || {
+            let wheel = |wheel: &Option<CheckedEncoder<Amt102V>>| match wheel {
+                Some(wheel) => wheel.position().ok().map(Some),
+                None => Some(None),
+            };
+
+            Some(OdometryReading {
+                left: left.position().ok()?,
+                right: right.position().ok()?,
+                forward_wheel: wheel(&forward_wheel)?,
+                sideways_wheel: wheel(&sideways_wheel)?,
+                heading: gyro.heading().ok()?,
+            })
+        };
+
+        calibrate_odometry(
+            &mut controller,
+            &mut Differential::from_shared(left.clone(), right.clone()),
+            read,
+            OdometrySetup {
+                gearing: drivetrain.gearing,
+                sideways_wheel_diameter: self.sideways_wheel.map(|wheel| wheel.diameter),
+            },
+            config,
+        )
+        .await
+    }
+
+    /// Claims every configured port and builds the robot's hardware.
+    ///
+    /// IMUs are calibrated before the drivetrain is created, with progress shown
+    /// on the controller and display. If every calibration fails, the drivetrain
+    /// tracks heading from its drive encoders instead.
+    pub async fn build(&self, peripherals: Peripherals) -> RobotHardware<P> {
+        let mut peripherals = DynamicPeripherals::new(peripherals);
+        let mut controller = peripherals.take_primary_controller().unwrap();
+        let mut display = peripherals.take_display().unwrap();
+        let mut ports = Ports(peripherals);
+
+        let (gyro, calibrations) = self
+            .build_imus(&mut controller, &mut display, &mut ports)
+            .await;
+
+        let drivetrain = &self.drivetrain;
+        let left = Rc::new(RefCell::new(
+            drivetrain.left.map(|motor| motor.build(&mut ports)),
+        ));
+        let right = Rc::new(RefCell::new(
+            drivetrain.right.map(|motor| motor.build(&mut ports)),
+        ));
+
+        // With a working IMU, heading comes from it rather than the difference
+        // between the drive wheels, so they're treated as sitting on the
+        // tracking center's axis.
+        let offset = if gyro.is_some() {
+            0.0
+        } else {
+            warn!("Falling back to encoder-only heading.");
+            drivetrain.track_width / 2.0
+        };
+        let forward = [
+            TrackingWheel::new(
+                left.clone(),
+                drivetrain.wheel_diameter,
+                -offset,
+                drivetrain.gearing,
+            ),
+            TrackingWheel::new(
+                right.clone(),
+                drivetrain.wheel_diameter,
+                offset,
+                drivetrain.gearing,
+            ),
+        ];
+        let heading = Angle::from_degrees(90.0);
+
+        let sideways = self.sideways_wheel.map(|wheel| {
+            TrackingWheel::new(wheel.build(&mut ports), wheel.diameter, wheel.offset, None)
+        });
+        // Claimed even if it goes unused so the health check still sees it.
+        let forward_wheel = self
+            .forward_wheel
+            .map(|wheel| (wheel, wheel.build(&mut ports)));
+
+        let tracking = match (forward_wheel, gyro) {
+            (Some((wheel, encoder)), Some(gyro)) => {
+                // Of the drive sides, the one nearest the wheel moves most like it.
+                let side = if wheel.offset < 0.0 { &left } else { &right };
+                let encoder = encoder.with_fallback(
+                    side.clone(),
+                    drivetrain.wheel_diameter,
+                    drivetrain.gearing,
+                );
+
+                tracking(
+                    [TrackingWheel::new(
+                        encoder,
+                        wheel.diameter,
+                        wheel.offset,
+                        None,
+                    )],
+                    sideways,
+                    heading,
+                    Some(gyro),
+                )
+            }
+            (forward_wheel, gyro) => {
+                if forward_wheel.is_some() {
+                    warn!("Forward tracking wheel needs an IMU, using drive encoders instead.");
+                }
+
+                tracking(forward, sideways, heading, gyro)
+            }
+        };
+
+        let air = AirTank::vex(self.air_tanks);
+
+        let config = &self.intake;
+        let stage = |motors: &[MotorConfig], ports: &mut Ports| {
+            motors
+                .iter()
+                .map(|motor| motor.build(ports))
+                .collect::<Vec<_>>()
+        };
+        let mut intake = Intake::new(
+            stage(config.front_bottom, &mut ports),
+            stage(config.back_bottom, &mut ports),
+            stage(config.back_top, &mut ports),
+            stage(config.front_top, &mut ports),
+        )
+        .with_voltages(config.voltages);
+        if let Some(hood) = &config.hood {
+            intake = intake.with_hood(hood.build(&mut ports, &air));
+        }
+        if let Some(jam_config) = config.jam_detection {
+            intake = intake.with_jam_detection(jam_config);
+        }
+
+        RobotHardware {
+            controller,
+            display,
+            drivetrain: Drivetrain::new(Differential::from_shared(left, right), tracking),
+            intake,
+            pistons: self.pistons.map(|piston| piston.build(&mut ports, &air)),
+            air,
+            imus: calibrations,
+        }
+    }
+}
@@ -1,37 +1,219 @@
-use std::time::Instant;
+use std::{
+    pin::pin,
+    time::{Duration, Instant},
+};
 
-use log::{error, info};
+use evian::{math::Angle, tracking::Gyro};
+use futures::future::{Either, join_all, select};
+use log::{error, info, warn};
 use vexide::{
     color::Color,
-    display::{Display, Alignment, Font, FontFamily, FontSize, Text},
     controller::Controller,
-    smart::imu::InertialSensor,
+    display::{Alignment, Display, Font, FontFamily, FontSize, Text},
+    smart::imu::{InertialError, InertialSensor},
+    time::sleep,
 };
 
+/// Retry, timeout and drift thresholds for [`calibrate_imu`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImuCalibrationConfig {
+    /// Number of times calibration is attempted before giving up.
+    pub attempts: usize,
+
+    /// Longest a single calibration attempt may take.
+    pub timeout: Duration,
+
+    /// How long the robot is held still after calibrating to measure drift.
+    pub drift_window: Duration,
+
+    /// Largest acceptable stationary drift in degrees per second.
+    pub max_drift_rate: f64,
+}
+
+impl Default for ImuCalibrationConfig {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            timeout: Duration::from_secs(4),
+            drift_window: Duration::from_millis(500),
+            max_drift_rate: 0.1,
+        }
+    }
+}
+
+/// A successful IMU calibration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImuCalibration {
+    /// Time spent calibrating, including failed attempts.
+    pub elapsed: Duration,
+    pub attempts: usize,
+
+    /// Measured stationary drift in degrees per second.
+    pub drift_rate: f64,
+}
+
+/// Why [`calibrate_imu`] gave up.
+#[derive(Debug)]
+pub enum ImuCalibrationError {
+    /// The last attempt failed with an error from the sensor.
+    Sensor(InertialError),
+
+    /// The last attempt took longer than the configured timeout.
+    TimedOut,
+
+    /// The last attempt calibrated, but the heading drifted too much while
+    /// stationary (in degrees per second).
+    Drifting(f64),
+}
+
+/// Calibrates the IMU, retrying failed, slow or drifting calibrations.
+///
+/// Progress is shown on the controller and the result on the display. On error,
+/// the robot should either refuse to run autonomous or fall back to heading
+/// from its drive encoders.
 pub async fn calibrate_imu(
     controller: &mut Controller,
     display: &mut Display,
     imu: &mut InertialSensor,
-) {
+    config: ImuCalibrationConfig,
+) -> Result<ImuCalibration, ImuCalibrationError> {
     info!("Calibrating IMU");
     _ = controller.try_set_text("Calibrating...", 1, 1);
-    let imu_calibration_start = Instant::now();
 
-    if imu.calibrate().await.is_err() {
-        error!("Calibration fail!");
+    let result = calibrate(0, imu, config).await;
+    report(controller, display, std::slice::from_ref(&result));
+
+    result
+}
+
+/// Calibrates several IMUs at once, retrying each independently.
+///
+/// Results are returned in the same order as `imus`.
+pub async fn calibrate_imus(
+    controller: &mut Controller,
+    display: &mut Display,
+    imus: &mut [InertialSensor],
+    config: ImuCalibrationConfig,
+) -> Vec<Result<ImuCalibration, ImuCalibrationError>> {
+    info!("Calibrating {} IMUs", imus.len());
+    _ = controller.try_set_text("Calibrating...", 1, 1);
+
+    let results = join_all(
+        imus.iter_mut()
+            .enumerate()
+            .map(|(index, imu)| calibrate(index, imu, config)),
+    )
+    .await;
+    report(controller, display, &results);
+
+    results
+}
+
+/// Shows the outcome of a calibration on the controller and display.
+fn report(
+    controller: &mut Controller,
+    display: &mut Display,
+    results: &[Result<ImuCalibration, ImuCalibrationError>],
+) {
+    let failed = results.iter().filter(|result| result.is_err()).count();
+
+    if failed > 0 {
         _ = controller.try_set_text("Calibration fail!    ", 1, 1);
+        draw_status(display, "IMU FAILED", Color::new(255, 0, 0));
         return;
     }
 
+    let elapsed = results
+        .iter()
+        .filter_map(|result| result.as_ref().ok())
+        .map(|calibration| calibration.elapsed)
+        .max()
+        .unwrap_or_default();
+
+    _ = controller.try_set_text(format!("{:?}    ", elapsed), 1, 1);
+    draw_status(
+        display,
+        &format!("{:?}", elapsed),
+        Color::new(255, 255, 255),
+    );
+}
+
+/// Calibrates a single IMU, logging each attempt.
+async fn calibrate(
+    index: usize,
+    imu: &mut InertialSensor,
+    config: ImuCalibrationConfig,
+) -> Result<ImuCalibration, ImuCalibrationError> {
+    let imu_calibration_start = Instant::now();
+    let mut attempts = 0;
+
+    let result = loop {
+        attempts += 1;
+
+        match attempt_calibration(imu, &config).await {
+            Ok(drift_rate) => break Ok(drift_rate),
+            Err(error) if attempts < config.attempts => {
+                warn!("IMU {index} calibration attempt {attempts} failed: {error:?}");
+            }
+            Err(error) => break Err(error),
+        }
+    };
+
     let imu_calibration_elapsed = imu_calibration_start.elapsed();
 
-    info!("Calibration completed in {:?}.", imu_calibration_elapsed);
+    let drift_rate = match result {
+        Ok(drift_rate) => drift_rate,
+        Err(error) => {
+            error!("Calibration fail! (IMU {index}: {error:?})");
+            return Err(error);
+        }
+    };
 
-    _ = controller.try_set_text(format!("{:?}    ", imu_calibration_elapsed), 1, 1);
+    info!(
+        "IMU {index} calibration completed in {:?} (drift {:.3}°/s).",
+        imu_calibration_elapsed, drift_rate
+    );
+
+    Ok(ImuCalibration {
+        elapsed: imu_calibration_elapsed,
+        attempts,
+        drift_rate,
+    })
+}
+
+/// Runs a single calibration, returning the measured drift rate.
+async fn attempt_calibration(
+    imu: &mut InertialSensor,
+    config: &ImuCalibrationConfig,
+) -> Result<f64, ImuCalibrationError> {
+    {
+        let calibration = pin!(imu.calibrate());
+        let timeout = pin!(sleep(config.timeout));
+
+        match select(calibration, timeout).await {
+            Either::Left((result, _)) => result.map_err(ImuCalibrationError::Sensor)?,
+            Either::Right(_) => return Err(ImuCalibrationError::TimedOut),
+        }
+    }
 
+    let start = Gyro::heading(&*imu).map_err(ImuCalibrationError::Sensor)?;
+    sleep(config.drift_window).await;
+    let end = Gyro::heading(&*imu).map_err(ImuCalibrationError::Sensor)?;
+
+    let drift: Angle = (end - start).wrapped_half();
+    let drift_rate = drift.as_degrees().abs() / config.drift_window.as_secs_f64();
+
+    if drift_rate > config.max_drift_rate {
+        return Err(ImuCalibrationError::Drifting(drift_rate));
+    }
+
+    Ok(drift_rate)
+}
+
+fn draw_status(display: &mut Display, status: &str, color: Color) {
     display.draw_text(
         &Text::from_string_aligned(
-            format!("{:?}", imu_calibration_elapsed),
+            status.to_string(),
             Font::new(FontSize::LARGE, FontFamily::Monospace),
             [
                 Display::HORIZONTAL_RESOLUTION / 2,
@@ -40,7 +222,7 @@ pub async fn calibrate_imu(
             Alignment::Center,
             Alignment::Center,
         ),
-        Color::new(255, 255, 255),
+        color,
         None,
     );
 }
@@ -1,4 +1,11 @@
+use std::{
+    cell::Cell,
+    convert::Infallible,
+    time::{Duration, Instant},
+};
+
 use evian::tracking::RotarySensor;
+use log::warn;
 use vexide::{
     adi::{AdiPort, encoder::AdiEncoder},
     math::{Angle, Direction},
@@ -31,3 +38,379 @@ impl<const TPR: u32> RotarySensor for CustomEncoder<TPR> {
             })
     }
 }
+
+/// Why a [`CheckedEncoder`] stopped trusting its encoder.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EncoderFault {
+    /// The encoder's port returned an error.
+    Disconnected,
+
+    /// The encoder didn't move while the fallback travelled this many inches.
+    Stalled(f64),
+
+    /// The encoder reported an impossible speed, in inches per second.
+    TooFast(f64),
+}
+
+/// A [`RotarySensor`] that never moves.
+///
+/// Used as the fallback of a sideways tracking wheel, which has no motor
+/// encoder to stand in for it, so the robot is assumed not to slide once the
+/// wheel fails.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct Stationary;
+
+impl RotarySensor for Stationary {
+    type Error = Infallible;
+
+    fn position(&self) -> Result<Angle, Self::Error> {
+        Ok(Angle::from_radians(0.0))
+    }
+}
+
+/// Shortest time that an encoder's speed is measured over.
+///
+/// ADI encoders only update every 10 ms, so over shorter windows a reading can
+/// jump by a whole update's worth of travel at once.
+const SPEED_WINDOW: Duration = Duration::from_millis(10);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CheckState {
+    /// Encoder position last read.
+    last_position: Option<Angle>,
+
+    /// Encoder position and time that speed is measured from, at least
+    /// [`SPEED_WINDOW`] before the next speed check.
+    speed_reference: Option<(Angle, Instant)>,
+
+    /// Fallback travel in inches when the encoder last moved.
+    last_moved_at: Option<f64>,
+
+    /// Position last returned, in inches of wheel travel.
+    last_travel: f64,
+
+    /// Difference between the reported travel and the fallback's travel, set
+    /// on the first fallback reading after the encoder fails.
+    offset: Option<f64>,
+    fault: Option<EncoderFault>,
+}
+
+/// A tracking wheel encoder that switches to a fallback sensor when it fails.
+///
+/// The encoder is considered failed if its port errors, if it reports a speed
+/// faster than the robot can drive, or if it stops counting while the fallback
+/// (usually the drive motors on the same side) keeps moving. Once failed, the
+/// fallback's travel is reported instead for the rest of the program, picking
+/// up from where the encoder left off.
+pub struct CheckedEncoder<E, F = Stationary> {
+    encoder: E,
+    fallback: F,
+
+    /// Tracking wheel diameter in inches.
+    diameter: f64,
+
+    /// Inches travelled per radian of the fallback.
+    fallback_scale: f64,
+
+    max_speed: f64,
+    max_stall_distance: f64,
+    state: Cell<CheckState>,
+}
+
+impl<E: RotarySensor> CheckedEncoder<E> {
+    /// Checks an encoder on a tracking wheel of the given diameter in inches.
+    ///
+    /// Without a fallback, a failed wheel stops moving.
+    pub fn new(encoder: E, diameter: f64) -> Self {
+        Self {
+            encoder,
+            fallback: Stationary,
+            diameter,
+            fallback_scale: 0.0,
+            max_speed: 120.0,
+            max_stall_distance: 24.0,
+            state: Cell::new(CheckState {
+                last_position: None,
+                speed_reference: None,
+                last_moved_at: None,
+                last_travel: 0.0,
+                offset: None,
+                fault: None,
+            }),
+        }
+    }
+}
+
+impl<E: RotarySensor, F: RotarySensor> CheckedEncoder<E, F> {
+    /// Modifies the sensor used once the encoder fails, given the diameter of
+    /// its wheel and its gearing in the same terms as a `TrackingWheel`.
+    pub fn with_fallback<G: RotarySensor>(
+        self,
+        fallback: G,
+        diameter: f64,
+        gearing: Option<f64>,
+    ) -> CheckedEncoder<E, G> {
+        CheckedEncoder {
+            encoder: self.encoder,
+            fallback,
+            diameter: self.diameter,
+            fallback_scale: gearing.unwrap_or(1.0) * diameter / 2.0,
+            max_speed: self.max_speed,
+            max_stall_distance: self.max_stall_distance,
+            state: self.state,
+        }
+    }
+
+    /// Modifies the fastest plausible wheel speed in inches per second.
+    pub fn with_max_speed(mut self, max_speed: f64) -> Self {
+        self.max_speed = max_speed;
+        self
+    }
+
+    /// Modifies how far the fallback may travel while the encoder reads no
+    /// movement before the encoder is assumed to be unplugged.
+    ///
+    /// This should be long enough that pushing against a wall with the drive
+    /// wheels slipping doesn't trip it.
+    pub fn with_max_stall_distance(mut self, max_stall_distance: f64) -> Self {
+        self.max_stall_distance = max_stall_distance;
+        self
+    }
+
+    /// Returns why the encoder was abandoned, if it has been.
+    pub fn fault(&self) -> Option<EncoderFault> {
+        self.state.get().fault
+    }
+
+    /// Reads the encoder, returning its travel in inches or why it can't be
+    /// trusted.
+    fn check(
+        &self,
+        state: &mut CheckState,
+        fallback: Option<f64>,
+        now: Instant,
+    ) -> Result<f64, EncoderFault> {
+        let position = self
+            .encoder
+            .position()
+            .map_err(|_| EncoderFault::Disconnected)?;
+        let travel = position.as_radians() * self.diameter / 2.0;
+
+        match state.speed_reference {
+            Some((reference, since)) => {
+                let elapsed = now.duration_since(since);
+
+                if elapsed >= SPEED_WINDOW {
+                    let distance = (position - reference).as_radians() * self.diameter / 2.0;
+                    let speed = distance.abs() / elapsed.as_secs_f64();
+
+                    if speed > self.max_speed {
+                        return Err(EncoderFault::TooFast(speed));
+                    }
+
+                    state.speed_reference = Some((position, now));
+                }
+            }
+            None => state.speed_reference = Some((position, now)),
+        }
+
+        if state
+            .last_position
+            .is_some_and(|last_position| last_position != position)
+        {
+            state.last_moved_at = fallback;
+        }
+
+        match (fallback, state.last_moved_at) {
+            (Some(fallback), Some(last_moved_at)) => {
+                let stalled = (fallback - last_moved_at).abs();
+                if stalled > self.max_stall_distance {
+                    return Err(EncoderFault::Stalled(stalled));
+                }
+            }
+            (Some(_), None) => state.last_moved_at = fallback,
+            _ => {}
+        }
+
+        state.last_position = Some(position);
+        Ok(travel)
+    }
+
+    /// Reads the encoder at `now`, or the fallback once the encoder has failed.
+    fn position_at(&self, now: Instant) -> Result<Angle, F::Error> {
+        let mut state = self.state.get();
+        let fallback = self.fallback.position();
+
+        if state.fault.is_none() {
+            let fallback = fallback
+                .as_ref()
+                .ok()
+                .map(|position| position.as_radians() * self.fallback_scale);
+
+            match self.check(&mut state, fallback, now) {
+                Ok(travel) => {
+                    state.last_travel = travel;
+                    self.state.set(state);
+                    return Ok(Angle::from_radians(travel * 2.0 / self.diameter));
+                }
+                Err(fault) => {
+                    warn!("Tracking wheel encoder failed ({fault:?}), switching to fallback.");
+                    state.fault = Some(fault);
+                }
+            }
+        }
+
+        let result = fallback.map(|position| {
+            let fallback = position.as_radians() * self.fallback_scale;
+            // Continue from the last good reading rather than jumping to
+            // wherever the fallback happens to be.
+            let offset = *state.offset.get_or_insert(state.last_travel - fallback);
+
+            Angle::from_radians((fallback + offset) * 2.0 / self.diameter)
+        });
+        self.state.set(state);
+
+        result
+    }
+}
+
+impl<E: RotarySensor, F: RotarySensor> RotarySensor for CheckedEncoder<E, F> {
+    type Error = F::Error;
+
+    fn position(&self) -> Result<Angle, Self::Error> {
+        self.position_at(Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+
+    /// An encoder whose reading, in radians, is set by the test. `None` reads as
+    /// unplugged.
+    #[derive(Clone, Default)]
+    struct FakeEncoder(Rc<Cell<Option<f64>>>);
+
+    impl FakeEncoder {
+        fn set(&self, position: Option<f64>) {
+            self.0.set(position);
+        }
+    }
+
+    impl RotarySensor for FakeEncoder {
+        type Error = ();
+
+        fn position(&self) -> Result<Angle, Self::Error> {
+            self.0.get().map(Angle::from_radians).ok_or(())
+        }
+    }
+
+    /// A checked encoder on a 2" wheel, so that radians read as inches, with a
+    /// fallback that also reads in inches.
+    fn checked() -> (
+        CheckedEncoder<FakeEncoder, FakeEncoder>,
+        FakeEncoder,
+        FakeEncoder,
+    ) {
+        let (encoder, fallback) = (FakeEncoder::default(), FakeEncoder::default());
+        encoder.set(Some(0.0));
+        fallback.set(Some(0.0));
+
+        let checked =
+            CheckedEncoder::new(encoder.clone(), 2.0).with_fallback(fallback.clone(), 2.0, None);
+        (checked, encoder, fallback)
+    }
+
+    fn inches(checked: &CheckedEncoder<FakeEncoder, FakeEncoder>, now: Instant) -> f64 {
+        checked.position_at(now).unwrap().as_radians()
+    }
+
+    #[test]
+    fn healthy_encoders_are_trusted() {
+        let (checked, encoder, fallback) = checked();
+        let start = Instant::now();
+
+        for step in 0..100 {
+            let travel = f64::from(step) * 0.5;
+            encoder.set(Some(travel));
+            fallback.set(Some(travel * 1.1));
+
+            let now = start + Duration::from_millis(10 * u64::from(step));
+            assert_eq!(inches(&checked, now), travel);
+        }
+
+        assert_eq!(checked.fault(), None);
+    }
+
+    #[test]
+    fn disconnected_encoders_switch_to_the_fallback() {
+        let (checked, encoder, fallback) = checked();
+        let start = Instant::now();
+
+        encoder.set(Some(5.0));
+        fallback.set(Some(4.0));
+        assert_eq!(inches(&checked, start), 5.0);
+
+        // The fallback picks up from the encoder's last reading.
+        encoder.set(None);
+        fallback.set(Some(6.0));
+        assert_eq!(inches(&checked, start + SPEED_WINDOW), 5.0);
+        assert_eq!(checked.fault(), Some(EncoderFault::Disconnected));
+
+        fallback.set(Some(8.0));
+        assert_eq!(inches(&checked, start + SPEED_WINDOW * 2), 7.0);
+    }
+
+    #[test]
+    fn stuck_encoders_switch_to_the_fallback() {
+        let (checked, encoder, fallback) = checked();
+        let start = Instant::now();
+        encoder.set(Some(3.0));
+
+        for step in 0..=30 {
+            fallback.set(Some(f64::from(step)));
+            _ = checked.position_at(start + Duration::from_millis(10 * u64::from(step)));
+        }
+
+        // The stall is caught once the fallback has moved 25", and the 5" it
+        // moved after that carry on from the encoder's last reading.
+        assert_eq!(checked.fault(), Some(EncoderFault::Stalled(25.0)));
+        assert_eq!(inches(&checked, start + Duration::from_secs(1)), 8.0);
+    }
+
+    #[test]
+    fn speed_is_measured_over_the_adi_update_period() {
+        let (checked, encoder, _) = checked();
+        let start = Instant::now();
+
+        // A whole update's travel arriving 1 ms after the last read isn't a fault.
+        encoder.set(Some(0.0));
+        _ = checked.position_at(start);
+        encoder.set(Some(0.5));
+        _ = checked.position_at(start + Duration::from_millis(1));
+        _ = checked.position_at(start + SPEED_WINDOW);
+        assert_eq!(checked.fault(), None);
+
+        // 10" in 10 ms is.
+        encoder.set(Some(10.5));
+        _ = checked.position_at(start + SPEED_WINDOW * 2);
+        assert!(matches!(checked.fault(), Some(EncoderFault::TooFast(speed)) if speed > 900.0));
+    }
+
+    #[test]
+    fn faults_are_permanent() {
+        let (checked, encoder, fallback) = checked();
+        let start = Instant::now();
+
+        encoder.set(None);
+        _ = checked.position_at(start);
+        assert_eq!(checked.fault(), Some(EncoderFault::Disconnected));
+
+        encoder.set(Some(100.0));
+        fallback.set(Some(2.0));
+        assert_eq!(inches(&checked, start + SPEED_WINDOW), 2.0);
+        assert_eq!(checked.fault(), Some(EncoderFault::Disconnected));
+    }
+}
@@ -0,0 +1,309 @@
+//! Startup port health check.
+//!
+//! Compares what's actually plugged into each port against what the robot
+//! expects, so that swapped cables and dead devices are caught before a match
+//! rather than showing up as a strange autonomous.
+
+use log::{error, info};
+use vex_sdk::{
+    V5_AdiPortConfiguration, V5_DeviceType, vexDeviceAdiPortConfigGet, vexDeviceGetByIndex,
+    vexDeviceGetStatus,
+};
+use vexide::{
+    color::Color,
+    controller::Controller,
+    display::{Display, Font, FontFamily, FontSize, Text},
+};
+
+/// Index of the brain's built-in ADI ports among the smart devices.
+const INTERNAL_ADI_INDEX: u32 = 21;
+
+/// Number of device slots reported by the SDK.
+const DEVICE_SLOTS: usize = 32;
+
+/// A kind of smart device.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SmartDeviceKind {
+    Motor,
+    Imu,
+    Optical,
+    Distance,
+    Rotation,
+}
+
+impl SmartDeviceKind {
+    fn device_type(self) -> V5_DeviceType {
+        match self {
+            Self::Motor => V5_DeviceType::kDeviceTypeMotorSensor,
+            Self::Imu => V5_DeviceType::kDeviceTypeImuSensor,
+            Self::Optical => V5_DeviceType::kDeviceTypeOpticalSensor,
+            Self::Distance => V5_DeviceType::kDeviceTypeDistanceSensor,
+            Self::Rotation => V5_DeviceType::kDeviceTypeAbsEncSensor,
+        }
+    }
+
+    fn from_device_type(device_type: V5_DeviceType) -> Option<Self> {
+        [
+            Self::Motor,
+            Self::Imu,
+            Self::Optical,
+            Self::Distance,
+            Self::Rotation,
+        ]
+        .into_iter()
+        .find(|kind| kind.device_type() == device_type)
+    }
+}
+
+/// How an ADI port is expected to be configured.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AdiPortKind {
+    DigitalOut,
+    DigitalIn,
+    AnalogIn,
+
+    /// Either channel of a quadrature encoder.
+    Encoder,
+}
+
+impl AdiPortKind {
+    fn configuration(self) -> V5_AdiPortConfiguration {
+        match self {
+            Self::DigitalOut => V5_AdiPortConfiguration::kAdiPortTypeDigitalOut,
+            Self::DigitalIn => V5_AdiPortConfiguration::kAdiPortTypeDigitalIn,
+            Self::AnalogIn => V5_AdiPortConfiguration::kAdiPortTypeAnalogIn,
+            Self::Encoder => V5_AdiPortConfiguration::kAdiPortTypeQuadEncoder,
+        }
+    }
+}
+
+/// A smart device the robot expects to find on a port (1-21).
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ExpectedDevice {
+    pub name: &'static str,
+    pub port: u8,
+    pub kind: SmartDeviceKind,
+}
+
+/// An ADI port (`'a'` through `'h'`) the robot expects to be configured.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ExpectedAdiPort {
+    pub name: &'static str,
+    pub port: char,
+    pub kind: AdiPortKind,
+}
+
+/// A problem found by [`check_devices`] or [`check_adi_ports`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PortIssue {
+    /// Nothing is plugged into the port.
+    Missing(ExpectedDevice),
+
+    /// A different device is plugged into the port, or `None` if the SDK
+    /// reported a device this module doesn't know about.
+    Mismatched {
+        expected: ExpectedDevice,
+        found: Option<SmartDeviceKind>,
+    },
+
+    /// The ADI port isn't configured the way the robot expects.
+    AdiMisconfigured(ExpectedAdiPort),
+}
+
+impl PortIssue {
+    /// Returns a short description that fits on one line of the controller.
+    pub fn summary(&self) -> String {
+        match self {
+            Self::Missing(expected) => format!("{} P{} missing", expected.name, expected.port),
+            Self::Mismatched { expected, .. } => {
+                format!("{} P{} wrong", expected.name, expected.port)
+            }
+            Self::AdiMisconfigured(expected) => format!(
+                "{} {} wrong",
+                expected.name,
+                expected.port.to_ascii_uppercase()
+            ),
+        }
+    }
+}
+
+/// The result of a port check.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct HealthReport {
+    pub issues: Vec<PortIssue>,
+    pub checked: usize,
+}
+
+impl HealthReport {
+    pub fn is_healthy(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Adds the issues and checked ports from another report to this one.
+    pub fn merge(&mut self, other: HealthReport) {
+        self.issues.extend(other.issues);
+        self.checked += other.checked;
+    }
+
+    /// Logs every issue found.
+    pub fn log(&self) {
+        if self.is_healthy() {
+            info!("All {} ports healthy.", self.checked);
+            return;
+        }
+
+        for issue in &self.issues {
+            match issue {
+                PortIssue::Missing(expected) => error!(
+                    "{} ({:?}) not found on port {}.",
+                    expected.name, expected.kind, expected.port
+                ),
+                PortIssue::Mismatched { expected, found } => error!(
+                    "{} expected a {:?} on port {}, found {}.",
+                    expected.name,
+                    expected.kind,
+                    expected.port,
+                    found.map_or_else(
+                        || "an unknown device".to_string(),
+                        |kind| format!("{kind:?}")
+                    )
+                ),
+                PortIssue::AdiMisconfigured(expected) => error!(
+                    "{} on ADI port {} isn't configured as {:?}.",
+                    expected.name,
+                    expected.port.to_ascii_uppercase(),
+                    expected.kind
+                ),
+            }
+        }
+
+        error!(
+            "{} of {} ports failed the health check.",
+            self.issues.len(),
+            self.checked
+        );
+    }
+
+    /// Lists issues on the brain's display.
+    pub fn draw(&self, display: &mut Display) {
+        const LINE_HEIGHT: i16 = 20;
+
+        display.erase(Color::new(0, 0, 0));
+
+        let (title, color) = if self.is_healthy() {
+            ("All ports healthy".to_string(), Color::new(0, 255, 0))
+        } else {
+            (
+                format!("{} port issues", self.issues.len()),
+                Color::new(255, 0, 0),
+            )
+        };
+
+        let font = Font::new(FontSize::MEDIUM, FontFamily::Monospace);
+        display.draw_text(&Text::new(&title, font, [8, 8]), color, None);
+
+        for (line, issue) in self.issues.iter().enumerate() {
+            display.draw_text(
+                &Text::new(
+                    &issue.summary(),
+                    font,
+                    [8, 8 + LINE_HEIGHT * (line as i16 + 1)],
+                ),
+                Color::new(255, 255, 255),
+                None,
+            );
+        }
+    }
+
+    /// Shows a summary of the report on the controller screen.
+    pub async fn show(&self, controller: &mut Controller) {
+        if self.is_healthy() {
+            _ = controller.set_text("Ports OK        ", 1, 1).await;
+            return;
+        }
+
+        _ = controller
+            .set_text(format!("{} port issues    ", self.issues.len()), 1, 1)
+            .await;
+
+        for (line, issue) in self.issues.iter().take(2).enumerate() {
+            _ = controller
+                .set_text(format!("{:<16.16}", issue.summary()), line as u8 + 2, 1)
+                .await;
+        }
+    }
+
+    /// Logs the report and shows it on the display and controller.
+    pub async fn report(&self, controller: &mut Controller, display: &mut Display) {
+        self.log();
+        self.draw(display);
+        self.show(controller).await;
+    }
+}
+
+/// Checks every expected device and ADI port against what's plugged in.
+pub fn check_ports(devices: &[ExpectedDevice], adi_ports: &[ExpectedAdiPort]) -> HealthReport {
+    let mut report = check_devices(devices);
+    report.merge(check_adi_ports(adi_ports));
+    report
+}
+
+/// Checks that the expected smart devices are plugged in.
+///
+/// Smart devices are detected whether or not their ports have been claimed, so
+/// this can be called before anything is built.
+pub fn check_devices(devices: &[ExpectedDevice]) -> HealthReport {
+    let mut types = [V5_DeviceType::kDeviceTypeNoSensor; DEVICE_SLOTS];
+    // SAFETY: `types` has room for every device slot the SDK reports.
+    unsafe {
+        vexDeviceGetStatus(types.as_mut_ptr());
+    }
+
+    let mut report = HealthReport::default();
+
+    for expected in devices {
+        report.checked += 1;
+
+        let Some(&found) = types.get(usize::from(expected.port).wrapping_sub(1)) else {
+            report.issues.push(PortIssue::Missing(*expected));
+            continue;
+        };
+
+        if found == V5_DeviceType::kDeviceTypeNoSensor {
+            report.issues.push(PortIssue::Missing(*expected));
+        } else if found != expected.kind.device_type() {
+            report.issues.push(PortIssue::Mismatched {
+                expected: *expected,
+                found: SmartDeviceKind::from_device_type(found),
+            });
+        }
+    }
+
+    report
+}
+
+/// Checks that the expected ADI ports are configured.
+///
+/// ADI ports are only configured once they've been claimed, so this should be
+/// called after building the devices on them.
+pub fn check_adi_ports(adi_ports: &[ExpectedAdiPort]) -> HealthReport {
+    let mut report = HealthReport::default();
+
+    // SAFETY: The internal ADI device always exists.
+    let adi = unsafe { vexDeviceGetByIndex(INTERNAL_ADI_INDEX) };
+
+    for expected in adi_ports {
+        report.checked += 1;
+
+        let index = (expected.port.to_ascii_lowercase() as u32).wrapping_sub('a' as u32);
+        // SAFETY: `adi` is a valid device handle and out-of-range ports are
+        // rejected before calling into the SDK.
+        let configuration = (index < 8).then(|| unsafe { vexDeviceAdiPortConfigGet(adi, index) });
+
+        if configuration != Some(expected.kind.configuration()) {
+            report.issues.push(PortIssue::AdiMisconfigured(*expected));
+        }
+    }
+
+    report
+}
@@ -0,0 +1,195 @@
+//! Heading fusion across several IMUs.
+
+use std::cell::Cell;
+
+use evian::{math::Angle, tracking::Gyro};
+use log::warn;
+use vexide::smart::imu::{InertialError, InertialSensor};
+
+/// Per-sensor unwrapping state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SensorState {
+    /// Raw heading at the previous reading, or `None` if the sensor was
+    /// disconnected.
+    last_raw: Option<Angle>,
+
+    /// Scale-corrected, unwrapped heading.
+    heading: Angle,
+}
+
+/// An IMU and the factor its rotation is multiplied by.
+struct FusedSensor {
+    imu: InertialSensor,
+    scale: f64,
+    state: Cell<SensorState>,
+}
+
+/// A heading source that combines several IMUs.
+///
+/// Each IMU's rotation is corrected by a scale factor (measured by spinning the
+/// robot a known number of turns), then sensors that disagree with the rest by
+/// more than `max_disagreement` are rejected before the remaining headings are
+/// averaged. A sensor that disconnects is rebased onto the fused heading when it
+/// comes back.
+///
+/// Headings are unwrapped between readings, so this must be polled faster than
+/// the robot can turn half a revolution, which odometry always does.
+pub struct FusedImu {
+    sensors: Vec<FusedSensor>,
+    max_disagreement: Angle,
+    last_heading: Cell<Option<Angle>>,
+}
+
+impl FusedImu {
+    /// Creates a fused heading from IMUs and their scale factors.
+    pub fn new(imus: impl IntoIterator<Item = (InertialSensor, f64)>) -> Self {
+        Self {
+            sensors: imus
+                .into_iter()
+                .map(|(imu, scale)| FusedSensor {
+                    imu,
+                    scale,
+                    state: Cell::new(SensorState {
+                        last_raw: None,
+                        heading: Angle::from_radians(0.0),
+                    }),
+                })
+                .collect(),
+            max_disagreement: Angle::from_degrees(3.0),
+            last_heading: Cell::new(None),
+        }
+    }
+
+    /// Modifies how far a sensor may drift from the others before it is ignored.
+    pub fn with_max_disagreement(mut self, max_disagreement: Angle) -> Self {
+        self.max_disagreement = max_disagreement;
+        self
+    }
+
+    /// Returns the number of sensors being fused.
+    pub fn len(&self) -> usize {
+        self.sensors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sensors.is_empty()
+    }
+
+    /// Reads every sensor, returning their corrected headings or the last error.
+    fn read(&self) -> Result<Vec<Angle>, InertialError> {
+        let mut headings = Vec::with_capacity(self.sensors.len());
+        let mut last_error = None;
+
+        for (index, sensor) in self.sensors.iter().enumerate() {
+            let mut state = sensor.state.get();
+
+            match Gyro::heading(&sensor.imu) {
+                Ok(raw) => {
+                    match state.last_raw {
+                        Some(last_raw) => {
+                            state.heading += Angle::from_radians(
+                                (raw - last_raw).wrapped_half().as_radians() * sensor.scale,
+                            );
+                        }
+                        None => {
+                            // Start (or restart) from the fused heading so a
+                            // reconnected sensor doesn't pull the average back to
+                            // where it was when it dropped out.
+                            state.heading = self.last_heading.get().unwrap_or(raw);
+                        }
+                    }
+                    state.last_raw = Some(raw);
+                    headings.push(state.heading);
+                }
+                Err(error) => {
+                    if state.last_raw.is_some() {
+                        warn!("IMU {index} disconnected, ignoring it until it returns.");
+                    }
+                    state.last_raw = None;
+                    last_error = Some(error);
+                }
+            }
+
+            sensor.state.set(state);
+        }
+
+        match last_error {
+            Some(error) if headings.is_empty() => Err(error),
+            _ => Ok(headings),
+        }
+    }
+
+    /// Averages the headings that agree with the most other sensors.
+    fn fuse(&self, headings: &[Angle]) -> Angle {
+        let difference = |a: Angle, b: Angle| (a - b).wrapped_half().as_radians().abs();
+        let agreement = |heading: Angle| {
+            headings
+                .iter()
+                .filter(|other| difference(**other, heading) <= self.max_disagreement.as_radians())
+                .count()
+        };
+        let best = headings
+            .iter()
+            .map(|heading| agreement(*heading))
+            .max()
+            .unwrap_or_default();
+
+        // With only two sensors that disagree, trust whichever is closer to the
+        // previous estimate.
+        let reference = headings
+            .iter()
+            .copied()
+            .filter(|heading| agreement(*heading) == best)
+            .min_by(|a, b| {
+                let last = self.last_heading.get().unwrap_or(*a);
+                difference(*a, last).total_cmp(&difference(*b, last))
+            })
+            .unwrap_or(headings[0]);
+
+        let inliers: Vec<f64> = headings
+            .iter()
+            .map(|heading| (*heading - reference).wrapped_half().as_radians())
+            .filter(|error| error.abs() <= self.max_disagreement.as_radians())
+            .collect();
+
+        reference + Angle::from_radians(inliers.iter().sum::<f64>() / inliers.len() as f64)
+    }
+}
+
+impl Gyro for FusedImu {
+    type Error = InertialError;
+
+    fn heading(&self) -> Result<Angle, Self::Error> {
+        let headings = self.read()?;
+        if headings.is_empty() {
+            // No sensors were given, so there's nothing to fuse.
+            return Ok(self.last_heading.get().unwrap_or(Angle::from_radians(0.0)));
+        }
+
+        let heading = self.fuse(&headings);
+
+        self.last_heading.set(Some(heading));
+        Ok(heading)
+    }
+
+    fn angular_velocity(&self) -> Result<f64, Self::Error> {
+        let mut total = 0.0;
+        let mut count = 0;
+        let mut last_error = None;
+
+        for sensor in &self.sensors {
+            match Gyro::angular_velocity(&sensor.imu) {
+                Ok(velocity) => {
+                    total += velocity * sensor.scale;
+                    count += 1;
+                }
+                Err(error) => last_error = Some(error),
+            }
+        }
+
+        match last_error {
+            Some(error) if count == 0 => Err(error),
+            _ => Ok(total / count.max(1) as f64),
+        }
+    }
+}
@@ -1,2 +1,6 @@
 pub mod calibration;
 pub mod encoder;
+pub mod pneumatics;
+pub mod health;
+pub mod imu;
+pub mod odometry;
@@ -0,0 +1,533 @@
+//! Odometry auto-calibration.
+//!
+//! Measures the constants [`WheeledTracking`](evian::tracking::wheeled::WheeledTracking)
+//! depends on rather than trusting a ruler: wheel diameters come from pushing the
+//! robot a known distance, and track width and tracking wheel offsets come from
+//! spinning it in place against the IMU.
+
+use std::{
+    f64::consts::TAU,
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use evian::{drivetrain::model::Tank, math::Angle};
+use log::{info, warn};
+use vexide::{controller::Controller, time::sleep};
+
+/// How far and how fast [`calibrate_odometry`] moves the robot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OdometryCalibrationConfig {
+    /// Distance the robot is pushed in a straight line, in inches.
+    pub distance: f64,
+
+    /// Number of full turns the robot spins in place.
+    pub turns: f64,
+
+    /// Drive power used while spinning, from 0 to 1.
+    pub turn_power: f64,
+
+    /// Longest the spin may take before giving up.
+    pub timeout: Duration,
+
+    /// Where to save the results, if anywhere.
+    ///
+    /// Defaults to [`OdometryCalibration::DEFAULT_PATH`], so the results are
+    /// picked up by [`RobotConfig::with_saved_odometry`] on the next run.
+    ///
+    /// [`RobotConfig::with_saved_odometry`]: crate::config::RobotConfig::with_saved_odometry
+    pub save_path: Option<&'static str>,
+}
+
+impl Default for OdometryCalibrationConfig {
+    fn default() -> Self {
+        Self {
+            distance: 48.0,
+            turns: 5.0,
+            turn_power: 0.4,
+            timeout: Duration::from_secs(30),
+            save_path: Some(OdometryCalibration::DEFAULT_PATH),
+        }
+    }
+}
+
+/// Sensor readings taken at the start and end of each calibration step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OdometryReading {
+    /// Average drive motor position on each side.
+    pub left: Angle,
+    pub right: Angle,
+
+    /// Tracking wheel encoder positions, if the robot has them.
+    pub forward_wheel: Option<Angle>,
+    pub sideways_wheel: Option<Angle>,
+
+    /// IMU heading.
+    pub heading: Angle,
+}
+
+/// Gearing and tracking wheel sizes that can't be measured by the routine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OdometrySetup {
+    /// Ratio of wheel rotations to motor rotations, if the drive is geared.
+    pub gearing: Option<f64>,
+
+    /// Diameter of the sideways tracking wheel in inches, which doesn't turn
+    /// when the robot is pushed forward.
+    pub sideways_wheel_diameter: Option<f64>,
+}
+
+/// A tracking wheel's measured size and position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WheelCalibration {
+    /// Effective diameter in inches.
+    pub diameter: f64,
+
+    /// Distance from the tracking center in inches.
+    pub offset: f64,
+}
+
+/// Measured odometry constants.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OdometryCalibration {
+    /// Effective drive wheel diameter in inches.
+    pub wheel_diameter: f64,
+
+    /// Effective distance between the left and right wheels in inches.
+    pub track_width: f64,
+    pub forward_wheel: Option<WheelCalibration>,
+    pub sideways_wheel: Option<WheelCalibration>,
+}
+
+/// Why [`calibrate_odometry`] gave up.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum OdometryCalibrationError {
+    /// A sensor stopped reporting partway through.
+    Sensor,
+
+    /// The robot didn't finish spinning before the timeout.
+    TimedOut,
+
+    /// The drive encoders didn't register any movement.
+    NoMovement,
+}
+
+impl OdometryCalibration {
+    /// Suggested file name for saving results on the SD card.
+    pub const DEFAULT_PATH: &str = "odometry.txt";
+
+    /// Logs the results in a form that can be pasted into a robot's config.
+    pub fn log(&self) {
+        info!("wheel_diameter: {:.3}", self.wheel_diameter);
+        info!("track_width: {:.3}", self.track_width);
+
+        for (name, wheel) in [
+            ("forward_wheel", self.forward_wheel),
+            ("sideways_wheel", self.sideways_wheel),
+        ] {
+            if let Some(wheel) = wheel {
+                info!(
+                    "{name}: diameter {:.3}, offset {:.3}",
+                    wheel.diameter, wheel.offset
+                );
+            }
+        }
+    }
+
+    /// Writes the results as `key=value` lines, e.g. to the SD card.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = File::create(path)?;
+
+        writeln!(file, "wheel_diameter={}", self.wheel_diameter)?;
+        writeln!(file, "track_width={}", self.track_width)?;
+        for (name, wheel) in [
+            ("forward_wheel", self.forward_wheel),
+            ("sideways_wheel", self.sideways_wheel),
+        ] {
+            if let Some(wheel) = wheel {
+                writeln!(file, "{name}_diameter={}", wheel.diameter)?;
+                writeln!(file, "{name}_offset={}", wheel.offset)?;
+            }
+        }
+
+        file.flush()
+    }
+
+    /// Returns `true` if every diameter and the track width are finite and
+    /// positive, and every offset is finite.
+    pub fn is_valid(&self) -> bool {
+        let positive = |value: f64| value.is_finite() && value > 0.0;
+
+        positive(self.wheel_diameter)
+            && positive(self.track_width)
+            && [self.forward_wheel, self.sideways_wheel]
+                .iter()
+                .flatten()
+                .all(|wheel| positive(wheel.diameter) && wheel.offset.is_finite())
+    }
+
+    /// Reads results written by [`OdometryCalibration::save`].
+    ///
+    /// Results that aren't [valid](OdometryCalibration::is_valid), e.g. from a
+    /// calibration where a wheel never moved, are rejected.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let value = |key: &str| {
+            contents.lines().find_map(|line| {
+                let (name, value) = line.split_once('=')?;
+                (name.trim() == key).then(|| value.trim().parse::<f64>().ok())?
+            })
+        };
+        let wheel = |name: &str| {
+            Some(WheelCalibration {
+                diameter: value(&format!("{name}_diameter"))?,
+                offset: value(&format!("{name}_offset"))?,
+            })
+        };
+        let invalid = |key| io::Error::new(io::ErrorKind::InvalidData, format!("missing {key}"));
+
+        let calibration = Self {
+            wheel_diameter: value("wheel_diameter").ok_or_else(|| invalid("wheel_diameter"))?,
+            track_width: value("track_width").ok_or_else(|| invalid("track_width"))?,
+            forward_wheel: wheel("forward_wheel"),
+            sideways_wheel: wheel("sideways_wheel"),
+        };
+
+        if !calibration.is_valid() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("implausible calibration {calibration:?}"),
+            ));
+        }
+
+        Ok(calibration)
+    }
+}
+
+/// Returns `true` if Y is held on `controller`.
+///
+/// Robots check this as the program starts to run odometry calibration in
+/// place of their usual program.
+pub fn calibration_requested(controller: &Controller) -> bool {
+    controller
+        .state()
+        .is_ok_and(|state| state.button_y.is_pressed())
+}
+
+/// Waits for the A button, showing `prompt` on the controller.
+async fn wait_for_a(controller: &mut Controller, prompt: &str) {
+    info!("{prompt}");
+    _ = controller.set_text(format!("{prompt:<16}"), 1, 1).await;
+
+    loop {
+        if controller
+            .state()
+            .is_ok_and(|state| state.button_a.is_now_pressed())
+        {
+            return;
+        }
+
+        sleep(Controller::UPDATE_INTERVAL).await;
+    }
+}
+
+/// Returns the drive and forward tracking wheel diameters from readings taken
+/// before and after pushing the robot `distance` inches.
+fn push_diameters(
+    start: &OdometryReading,
+    end: &OdometryReading,
+    distance: f64,
+    setup: &OdometrySetup,
+) -> Result<(f64, Option<f64>), OdometryCalibrationError> {
+    let drive = ((end.left - start.left).as_radians() + (end.right - start.right).as_radians())
+        / 2.0
+        * setup.gearing.unwrap_or(1.0);
+    if drive.abs() < f64::EPSILON {
+        return Err(OdometryCalibrationError::NoMovement);
+    }
+
+    // A wheel turning `angle` radians travels `angle * diameter / 2` inches.
+    let wheel_diameter = 2.0 * distance / drive.abs();
+    let forward_wheel_diameter = end
+        .forward_wheel
+        .zip(start.forward_wheel)
+        .map(|(end, start)| 2.0 * distance / (end - start).as_radians().abs());
+
+    Ok((wheel_diameter, forward_wheel_diameter))
+}
+
+/// Returns the track width and tracking wheel offsets from readings taken
+/// before and after spinning the robot `rotation` radians in place, as measured
+/// by the IMU.
+fn spin_calibration(
+    start: &OdometryReading,
+    end: &OdometryReading,
+    rotation: f64,
+    (wheel_diameter, forward_wheel_diameter): (f64, Option<f64>),
+    setup: &OdometrySetup,
+) -> Result<OdometryCalibration, OdometryCalibrationError> {
+    let gearing = setup.gearing.unwrap_or(1.0);
+    let travel =
+        |start: Angle, end: Angle, diameter: f64| (end - start).as_radians() * diameter / 2.0;
+    let left = travel(start.left, end.left, wheel_diameter * gearing);
+    let right = travel(start.right, end.right, wheel_diameter * gearing);
+
+    if (right - left).abs() < f64::EPSILON || rotation.abs() < f64::EPSILON {
+        return Err(OdometryCalibrationError::NoMovement);
+    }
+
+    // The IMU gives how far the robot turned, and the drive sides which way, so
+    // an IMU mounted upside down doesn't flip every offset.
+    let rotation = rotation.abs().copysign(right - left);
+    let track_width = (right - left) / rotation;
+
+    let forward_wheel = forward_wheel_diameter
+        .zip(start.forward_wheel.zip(end.forward_wheel))
+        .map(|(diameter, (start, end))| WheelCalibration {
+            diameter,
+            offset: travel(start, end, diameter) / rotation,
+        });
+    let sideways_wheel = setup
+        .sideways_wheel_diameter
+        .zip(start.sideways_wheel.zip(end.sideways_wheel))
+        .map(|(diameter, (start, end))| WheelCalibration {
+            diameter,
+            offset: travel(start, end, diameter) / rotation,
+        });
+
+    Ok(OdometryCalibration {
+        wheel_diameter,
+        track_width,
+        forward_wheel,
+        sideways_wheel,
+    })
+}
+
+/// Measures wheel diameters, track width and tracking wheel offsets.
+///
+/// The driver is first prompted on the controller to push the robot
+/// `config.distance` inches in a straight line (e.g. two tiles) with the drive
+/// unpowered, which gives the wheel diameters. The robot then spins in place
+/// for `config.turns` turns, and the distance each wheel travelled is compared
+/// against the IMU's rotation.
+///
+/// `read` returns the current sensor readings, or `None` if one is unavailable.
+/// Results are logged and, if `config.save_path` is set, saved.
+pub async fn calibrate_odometry<M: Tank>(
+    controller: &mut Controller,
+    model: &mut M,
+    mut read: impl FnMut() -> Option<OdometryReading>,
+    setup: OdometrySetup,
+    config: OdometryCalibrationConfig,
+) -> Result<OdometryCalibration, OdometryCalibrationError> {
+    _ = model.drive_tank(0.0, 0.0);
+
+    // Straight push
+    wait_for_a(controller, "Line up, press A").await;
+    let start = read().ok_or(OdometryCalibrationError::Sensor)?;
+    wait_for_a(controller, &format!("Push {}in, A", config.distance)).await;
+    let end = read().ok_or(OdometryCalibrationError::Sensor)?;
+
+    let diameters = push_diameters(&start, &end, config.distance, &setup)?;
+
+    let turned = (end.heading - start.heading).wrapped_half().as_degrees();
+    if turned.abs() > 2.0 {
+        warn!("Robot turned {turned:.1}° while being pushed, results may be off.");
+    }
+
+    // Spin
+    wait_for_a(controller, "Clear, press A").await;
+    _ = controller.set_text("Spinning...     ", 1, 1).await;
+
+    let start = read().ok_or(OdometryCalibrationError::Sensor)?;
+    let spin_start = Instant::now();
+    let mut rotation = 0.0;
+    let mut last_heading = start.heading;
+
+    _ = model.drive_tank(-config.turn_power, config.turn_power);
+    let result = loop {
+        sleep(Duration::from_millis(10)).await;
+
+        let Some(reading) = read() else {
+            break Err(OdometryCalibrationError::Sensor);
+        };
+        rotation += (reading.heading - last_heading).wrapped_half().as_radians();
+        last_heading = reading.heading;
+
+        if rotation.abs() >= config.turns * TAU {
+            break Ok(());
+        }
+        if spin_start.elapsed() > config.timeout {
+            break Err(OdometryCalibrationError::TimedOut);
+        }
+    };
+    _ = model.drive_tank(0.0, 0.0);
+    result?;
+
+    // Let the robot coast to a stop before the final reading.
+    sleep(Duration::from_millis(500)).await;
+    let end = read().ok_or(OdometryCalibrationError::Sensor)?;
+    rotation += (end.heading - last_heading).wrapped_half().as_radians();
+
+    let calibration = spin_calibration(&start, &end, rotation, diameters, &setup)?;
+    let track_width = calibration.track_width;
+
+    calibration.log();
+    _ = controller
+        .set_text(format!("TW {track_width:<13.3}"), 1, 1)
+        .await;
+
+    if let Some(path) = config.save_path {
+        match calibration.save(path) {
+            Ok(()) => info!("Saved odometry calibration to {path}."),
+            Err(error) => warn!("Couldn't save odometry calibration to {path}: {error}"),
+        }
+    }
+
+    Ok(calibration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETUP: OdometrySetup = OdometrySetup {
+        gearing: Some(0.75),
+        sideways_wheel_diameter: Some(2.0),
+    };
+
+    const WHEEL_DIAMETER: f64 = 3.25;
+    const FORWARD_WHEEL: WheelCalibration = WheelCalibration {
+        diameter: 2.75,
+        offset: -1.5,
+    };
+    const SIDEWAYS_OFFSET: f64 = 3.0;
+    const TRACK_WIDTH: f64 = 11.0;
+
+    /// Readings from a robot with the constants above after each side of the
+    /// drive, the forward wheel and the sideways wheel travel the given inches.
+    fn reading(left: f64, right: f64, forward: f64, sideways: f64) -> OdometryReading {
+        let motor = |travel: f64| Angle::from_radians(travel * 2.0 / (WHEEL_DIAMETER * 0.75));
+
+        OdometryReading {
+            left: motor(left),
+            right: motor(right),
+            forward_wheel: Some(Angle::from_radians(forward * 2.0 / FORWARD_WHEEL.diameter)),
+            sideways_wheel: Some(Angle::from_radians(sideways * 2.0 / 2.0)),
+            heading: Angle::from_radians(0.0),
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn pushing_measures_wheel_diameters() {
+        let start = reading(0.0, 0.0, 0.0, 0.0);
+        let end = reading(48.0, 48.0, 48.0, 0.0);
+
+        let (wheel, forward_wheel) = push_diameters(&start, &end, 48.0, &SETUP).unwrap();
+        assert_close(wheel, WHEEL_DIAMETER);
+        assert_close(forward_wheel.unwrap(), FORWARD_WHEEL.diameter);
+    }
+
+    #[test]
+    fn pushing_backwards_measures_the_same_diameters() {
+        let start = reading(48.0, 48.0, 48.0, 0.0);
+        let end = reading(0.0, 0.0, 0.0, 0.0);
+
+        let (wheel, forward_wheel) = push_diameters(&start, &end, 48.0, &SETUP).unwrap();
+        assert_close(wheel, WHEEL_DIAMETER);
+        assert_close(forward_wheel.unwrap(), FORWARD_WHEEL.diameter);
+    }
+
+    #[test]
+    fn spinning_measures_track_width_and_offsets() {
+        let rotation = 5.0 * TAU;
+        let start = reading(0.0, 0.0, 0.0, 0.0);
+        let end = reading(
+            -TRACK_WIDTH / 2.0 * rotation,
+            TRACK_WIDTH / 2.0 * rotation,
+            FORWARD_WHEEL.offset * rotation,
+            SIDEWAYS_OFFSET * rotation,
+        );
+        let diameters = (WHEEL_DIAMETER, Some(FORWARD_WHEEL.diameter));
+
+        // An upside-down IMU measures the same rotation the other way.
+        for rotation in [rotation, -rotation] {
+            let calibration = spin_calibration(&start, &end, rotation, diameters, &SETUP).unwrap();
+
+            assert_close(calibration.wheel_diameter, WHEEL_DIAMETER);
+            assert_close(calibration.track_width, TRACK_WIDTH);
+
+            let forward_wheel = calibration.forward_wheel.unwrap();
+            assert_close(forward_wheel.diameter, FORWARD_WHEEL.diameter);
+            assert_close(forward_wheel.offset, FORWARD_WHEEL.offset);
+
+            let sideways_wheel = calibration.sideways_wheel.unwrap();
+            assert_close(sideways_wheel.diameter, 2.0);
+            assert_close(sideways_wheel.offset, SIDEWAYS_OFFSET);
+        }
+    }
+
+    #[test]
+    fn still_robots_fail_to_calibrate() {
+        let still = reading(0.0, 0.0, 0.0, 0.0);
+
+        assert_eq!(
+            push_diameters(&still, &still, 48.0, &SETUP),
+            Err(OdometryCalibrationError::NoMovement)
+        );
+        assert_eq!(
+            spin_calibration(&still, &still, TAU, (WHEEL_DIAMETER, None), &SETUP),
+            Err(OdometryCalibrationError::NoMovement)
+        );
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("aubie2-{}-{name}", std::process::id()))
+    }
+
+    #[test]
+    fn saved_calibrations_load_unchanged() {
+        let path = temp_path("round-trip.txt");
+        let calibration = OdometryCalibration {
+            wheel_diameter: 3.251_234_567,
+            track_width: 11.0 / 3.0,
+            forward_wheel: Some(FORWARD_WHEEL),
+            sideways_wheel: None,
+        };
+
+        calibration.save(&path).unwrap();
+        let loaded = OdometryCalibration::load(&path);
+        _ = fs::remove_file(&path);
+
+        assert_eq!(loaded.unwrap(), calibration);
+    }
+
+    #[test]
+    fn implausible_calibrations_are_rejected() {
+        let path = temp_path("implausible.txt");
+
+        for contents in [
+            "wheel_diameter=NaN\ntrack_width=11\n",
+            "wheel_diameter=3.25\ntrack_width=inf\n",
+            "wheel_diameter=0\ntrack_width=11\n",
+            "wheel_diameter=3.25\ntrack_width=-11\n",
+            "wheel_diameter=3.25\ntrack_width=11\nforward_wheel_diameter=-2\nforward_wheel_offset=1\n",
+            "wheel_diameter=3.25\n",
+        ] {
+            fs::write(&path, contents).unwrap();
+            let error = OdometryCalibration::load(&path).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{contents}");
+        }
+
+        _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,479 @@
+//! Pneumatic pistons with state and air usage tracking.
+//!
+//! Air usage is estimated with Boyle's law: every stroke lets a cylinder's
+//! volume of atmospheric air equalize with the tank. The estimate ignores leaks
+//! and tubing volume, so treat it as an upper bound on remaining pressure.
+
+use std::{cell::RefCell, rc::Rc};
+
+use log::warn;
+use vexide::{adi::digital::LogicLevel, prelude::AdiDigitalOut, smart::PortError};
+
+/// Atmospheric pressure in PSI.
+const ATMOSPHERIC_PRESSURE: f64 = 14.7;
+
+/// The bore and stroke of a pneumatic cylinder, in inches.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cylinder {
+    pub bore: f64,
+    pub stroke: f64,
+}
+
+impl Cylinder {
+    /// 10 mm bore, 25 mm stroke.
+    pub const VEX_25MM: Self = Self::new(0.394, 0.984);
+
+    /// 10 mm bore, 50 mm stroke.
+    pub const VEX_50MM: Self = Self::new(0.394, 1.969);
+
+    /// 10 mm bore, 75 mm stroke.
+    pub const VEX_75MM: Self = Self::new(0.394, 2.953);
+
+    pub const fn new(bore: f64, stroke: f64) -> Self {
+        Self { bore, stroke }
+    }
+
+    /// Returns the swept volume of the cylinder in cubic inches.
+    pub fn volume(&self) -> f64 {
+        core::f64::consts::PI * (self.bore / 2.0).powi(2) * self.stroke
+    }
+}
+
+#[derive(Debug)]
+struct TankState {
+    volume: f64,
+    pressure: f64,
+    min_pressure: f64,
+    warned: bool,
+}
+
+/// An estimate of the pressure left in the robot's air tanks.
+///
+/// Cloning an `AirTank` shares the same estimate, so every piston fed by the
+/// same tanks should be given a clone.
+#[derive(Debug, Clone)]
+pub struct AirTank {
+    state: Rc<RefCell<TankState>>,
+}
+
+impl AirTank {
+    /// Approximate volume of a single VEX air tank in cubic inches.
+    pub const VEX_TANK_VOLUME: f64 = 12.2;
+
+    /// Largest gauge pressure allowed by the game manual in PSI.
+    pub const MAX_PRESSURE: f64 = 100.0;
+
+    /// Creates tanks with a total volume in cubic inches, filled to a gauge
+    /// pressure in PSI.
+    pub fn new(volume: f64, pressure: f64) -> Self {
+        Self {
+            state: Rc::new(RefCell::new(TankState {
+                volume,
+                pressure,
+                min_pressure: 40.0,
+                warned: false,
+            })),
+        }
+    }
+
+    /// Creates `count` VEX air tanks filled to [`AirTank::MAX_PRESSURE`].
+    pub fn vex(count: usize) -> Self {
+        Self::new(count as f64 * Self::VEX_TANK_VOLUME, Self::MAX_PRESSURE)
+    }
+
+    /// Modifies the gauge pressure in PSI below which pistons are considered
+    /// too weak to actuate reliably.
+    pub fn with_min_pressure(self, min_pressure: f64) -> Self {
+        self.state.borrow_mut().min_pressure = min_pressure;
+        self
+    }
+
+    /// Returns the estimated gauge pressure in PSI.
+    pub fn pressure(&self) -> f64 {
+        self.state.borrow().pressure
+    }
+
+    pub fn min_pressure(&self) -> f64 {
+        self.state.borrow().min_pressure
+    }
+
+    /// Resets the estimate after the tanks have been refilled.
+    pub fn set_pressure(&mut self, pressure: f64) {
+        let mut state = self.state.borrow_mut();
+        state.pressure = pressure;
+        state.warned = false;
+    }
+
+    /// Returns `true` if the estimated pressure is below the minimum.
+    pub fn is_low(&self) -> bool {
+        let state = self.state.borrow();
+        state.pressure < state.min_pressure
+    }
+
+    /// Returns the gauge pressure left after filling `volume` cubic inches of
+    /// atmospheric air from a given gauge pressure.
+    fn pressure_after(&self, pressure: f64, volume: f64) -> f64 {
+        let tank_volume = self.state.borrow().volume;
+        let absolute =
+            (pressure + ATMOSPHERIC_PRESSURE) * tank_volume + ATMOSPHERIC_PRESSURE * volume;
+
+        // Rounding can leave an empty tank fractionally below atmospheric.
+        (absolute / (tank_volume + volume) - ATMOSPHERIC_PRESSURE).max(0.0)
+    }
+
+    /// Returns how many more strokes of a given volume can be made before the
+    /// pressure drops below the minimum.
+    pub fn remaining_strokes(&self, volume: f64) -> usize {
+        let min_pressure = self.min_pressure();
+        let mut pressure = self.pressure();
+        let mut strokes = 0;
+
+        // Pressure only approaches atmospheric, so it never drops below a
+        // minimum of 0 PSI.
+        if volume <= 0.0 || min_pressure <= 0.0 {
+            return usize::MAX;
+        }
+
+        loop {
+            pressure = self.pressure_after(pressure, volume);
+            if pressure < min_pressure {
+                break;
+            }
+            strokes += 1;
+        }
+
+        strokes
+    }
+
+    /// Records a stroke of a given volume.
+    fn consume(&self, volume: f64) {
+        let pressure = self.pressure_after(self.pressure(), volume);
+        let mut state = self.state.borrow_mut();
+        state.pressure = pressure;
+
+        if state.pressure < state.min_pressure && !state.warned {
+            state.warned = true;
+            warn!(
+                "Estimated air pressure is {:.0} PSI, below the {:.0} PSI minimum.",
+                state.pressure, state.min_pressure
+            );
+        }
+    }
+}
+
+/// Air usage bookkeeping shared by both piston types.
+#[derive(Debug)]
+struct Usage {
+    cylinder: Cylinder,
+
+    /// Number of cylinders plumbed to the same valve.
+    count: usize,
+    tank: Option<AirTank>,
+    actuations: usize,
+}
+
+impl Usage {
+    fn new() -> Self {
+        Self {
+            cylinder: Cylinder::VEX_50MM,
+            count: 1,
+            tank: None,
+            actuations: 0,
+        }
+    }
+
+    fn volume(&self) -> f64 {
+        self.cylinder.volume() * self.count as f64
+    }
+
+    fn stroke(&mut self) {
+        self.actuations += 1;
+        if let Some(tank) = &self.tank {
+            tank.consume(self.volume());
+        }
+    }
+
+    fn remaining_strokes(&self) -> Option<usize> {
+        Some(self.tank.as_ref()?.remaining_strokes(self.volume()))
+    }
+}
+
+/// A single-acting piston driven by one solenoid valve.
+///
+/// Air is only used when extending, since the piston retracts by venting.
+pub struct Piston {
+    out: AdiDigitalOut,
+    inverted: bool,
+    extended: bool,
+    usage: Usage,
+}
+
+impl Piston {
+    /// Creates a retracted piston.
+    pub fn new(out: AdiDigitalOut) -> Self {
+        Self {
+            out,
+            inverted: false,
+            extended: false,
+            usage: Usage::new(),
+        }
+    }
+
+    /// Inverts the valve's wiring so that a low signal extends the piston.
+    ///
+    /// The piston is retracted when this is called.
+    pub fn inverted(mut self) -> Self {
+        self.inverted = true;
+        _ = self.write();
+        self
+    }
+
+    /// Modifies the cylinders driven by this piston's valve, for air usage
+    /// estimates.
+    pub fn with_cylinders(mut self, cylinder: Cylinder, count: usize) -> Self {
+        self.usage.cylinder = cylinder;
+        self.usage.count = count;
+        self
+    }
+
+    /// Tracks air usage against a shared tank.
+    pub fn with_tank(mut self, tank: AirTank) -> Self {
+        self.usage.tank = Some(tank);
+        self
+    }
+
+    fn write(&mut self) -> Result<(), PortError> {
+        self.out.set_level(if self.extended != self.inverted {
+            LogicLevel::High
+        } else {
+            LogicLevel::Low
+        })
+    }
+
+    pub fn set_extended(&mut self, extended: bool) -> Result<(), PortError> {
+        if extended && !self.extended {
+            self.usage.stroke();
+        }
+
+        self.extended = extended;
+        self.write()
+    }
+
+    pub fn extend(&mut self) -> Result<(), PortError> {
+        self.set_extended(true)
+    }
+
+    pub fn retract(&mut self) -> Result<(), PortError> {
+        self.set_extended(false)
+    }
+
+    pub fn toggle(&mut self) -> Result<(), PortError> {
+        self.set_extended(!self.extended)
+    }
+
+    pub fn is_extended(&self) -> bool {
+        self.extended
+    }
+
+    /// Returns the number of times the piston has extended.
+    pub fn actuations(&self) -> usize {
+        self.usage.actuations
+    }
+
+    /// Returns how many more times the piston can extend before its tank runs
+    /// low, or `None` if it isn't tracking a tank.
+    pub fn remaining_actuations(&self) -> Option<usize> {
+        self.usage.remaining_strokes()
+    }
+
+    /// Returns `false` and logs a warning if the tank can't supply `actuations`
+    /// more extensions.
+    pub fn has_air_for(&self, actuations: usize) -> bool {
+        match self.remaining_actuations() {
+            Some(remaining) if remaining < actuations => {
+                warn!("Only {remaining} of {actuations} piston actuations left in the tank.");
+                false
+            }
+            _ => true,
+        }
+    }
+}
+
+/// A double-acting piston driven by separate extend and retract valves.
+///
+/// Air is used on every stroke in either direction.
+pub struct DoubleActingPiston {
+    extend: AdiDigitalOut,
+    retract: AdiDigitalOut,
+    inverted: bool,
+    extended: bool,
+    usage: Usage,
+}
+
+impl DoubleActingPiston {
+    /// Creates a retracted piston.
+    pub fn new(extend: AdiDigitalOut, retract: AdiDigitalOut) -> Self {
+        let mut piston = Self {
+            extend,
+            retract,
+            inverted: false,
+            extended: false,
+            usage: Usage::new(),
+        };
+        _ = piston.write();
+        piston
+    }
+
+    /// Inverts the valves' wiring so that a low signal opens them.
+    ///
+    /// The piston is retracted when this is called.
+    pub fn inverted(mut self) -> Self {
+        self.inverted = true;
+        _ = self.write();
+        self
+    }
+
+    /// Modifies the cylinders driven by this piston's valves, for air usage
+    /// estimates.
+    pub fn with_cylinders(mut self, cylinder: Cylinder, count: usize) -> Self {
+        self.usage.cylinder = cylinder;
+        self.usage.count = count;
+        self
+    }
+
+    /// Tracks air usage against a shared tank.
+    pub fn with_tank(mut self, tank: AirTank) -> Self {
+        self.usage.tank = Some(tank);
+        self
+    }
+
+    fn write(&mut self) -> Result<(), PortError> {
+        let level = |open: bool| {
+            if open != self.inverted {
+                LogicLevel::High
+            } else {
+                LogicLevel::Low
+            }
+        };
+        let (extend, retract) = (level(self.extended), level(!self.extended));
+
+        let mut rtn = self.extend.set_level(extend);
+        if let Err(error) = self.retract.set_level(retract) {
+            rtn = Err(error);
+        }
+
+        rtn
+    }
+
+    pub fn set_extended(&mut self, extended: bool) -> Result<(), PortError> {
+        if extended != self.extended {
+            self.usage.stroke();
+        }
+
+        self.extended = extended;
+        self.write()
+    }
+
+    pub fn extend(&mut self) -> Result<(), PortError> {
+        self.set_extended(true)
+    }
+
+    pub fn retract(&mut self) -> Result<(), PortError> {
+        self.set_extended(false)
+    }
+
+    pub fn toggle(&mut self) -> Result<(), PortError> {
+        self.set_extended(!self.extended)
+    }
+
+    pub fn is_extended(&self) -> bool {
+        self.extended
+    }
+
+    /// Returns the number of strokes the piston has made in either direction.
+    pub fn actuations(&self) -> usize {
+        self.usage.actuations
+    }
+
+    /// Returns how many more strokes the piston can make before its tank runs
+    /// low, or `None` if it isn't tracking a tank.
+    pub fn remaining_actuations(&self) -> Option<usize> {
+        self.usage.remaining_strokes()
+    }
+
+    /// Returns `false` and logs a warning if the tank can't supply `actuations`
+    /// more strokes.
+    pub fn has_air_for(&self, actuations: usize) -> bool {
+        match self.remaining_actuations() {
+            Some(remaining) if remaining < actuations => {
+                warn!("Only {remaining} of {actuations} piston strokes left in the tank.");
+                false
+            }
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VOLUME: f64 = 1.0;
+
+    #[test]
+    fn strokes_scale_pressure_by_tank_volume() {
+        // Gauge pressure drops by a factor of V / (V + v) per stroke.
+        let tank = AirTank::new(12.0, 100.0);
+        tank.consume(VOLUME);
+        assert!((tank.pressure() - 100.0 * 12.0 / 13.0).abs() < 1e-9);
+
+        tank.consume(VOLUME);
+        assert!((tank.pressure() - 100.0 * (12.0f64 / 13.0).powi(2)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn remaining_strokes_stop_at_min_pressure() {
+        let tank = AirTank::new(12.0, 100.0).with_min_pressure(40.0);
+        let strokes = tank.remaining_strokes(VOLUME);
+
+        // 100 * (12 / 13)^n >= 40 for n up to 11.
+        assert_eq!(strokes, 11);
+
+        for _ in 0..strokes {
+            tank.consume(VOLUME);
+        }
+        assert!(!tank.is_low());
+        assert_eq!(tank.remaining_strokes(VOLUME), 0);
+
+        tank.consume(VOLUME);
+        assert!(tank.is_low());
+    }
+
+    #[test]
+    fn remaining_strokes_are_unbounded_without_a_minimum() {
+        let tank = AirTank::new(12.0, 100.0).with_min_pressure(0.0);
+        assert_eq!(tank.remaining_strokes(VOLUME), usize::MAX);
+        assert_eq!(AirTank::vex(1).remaining_strokes(0.0), usize::MAX);
+    }
+
+    #[test]
+    fn consume_never_drops_below_atmospheric() {
+        let tank = AirTank::new(1.0, 0.0);
+        for _ in 0..100 {
+            tank.consume(10.0);
+            assert!(tank.pressure() >= 0.0);
+        }
+        assert_eq!(tank.pressure(), 0.0);
+    }
+
+    #[test]
+    fn refilling_resets_the_estimate() {
+        let mut tank = AirTank::new(1.0, 50.0);
+        tank.consume(10.0);
+        assert!(tank.is_low());
+
+        tank.set_pressure(AirTank::MAX_PRESSURE);
+        assert_eq!(tank.pressure(), AirTank::MAX_PRESSURE);
+        assert!(!tank.is_low());
+    }
+}
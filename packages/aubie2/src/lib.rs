@@ -3,4 +3,10 @@ pub mod subsystems;
 pub mod logger;
 pub mod hardware;
 pub mod motion;
-pub mod image;
\ No newline at end of file
+pub mod image;
+pub mod route;
+#[cfg(all(any(test, feature = "sim"), not(target_os = "vexos")))]
+pub mod sim;
+pub mod time;
+pub mod localization;
+pub mod config;
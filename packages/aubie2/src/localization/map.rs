@@ -0,0 +1,185 @@
+//! Field geometry seen by distance sensors.
+
+use evian::math::{Angle, Vec2};
+
+use crate::localization::FieldWalls;
+
+/// A straight obstacle on the field, such as the side of a goal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub start: Vec2<f64>,
+    pub end: Vec2<f64>,
+}
+
+impl Segment {
+    pub const fn new(start: Vec2<f64>, end: Vec2<f64>) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns the distance along a ray to this segment, if the ray hits it.
+    pub fn raycast(&self, origin: Vec2<f64>, direction: Angle) -> Option<f64> {
+        let (dx, dy) = (direction.cos(), direction.sin());
+        let (ex, ey) = (self.end.x - self.start.x, self.end.y - self.start.y);
+
+        let denominator = dx * ey - dy * ex;
+        if denominator.abs() < f64::EPSILON {
+            return None;
+        }
+
+        let (wx, wy) = (self.start.x - origin.x, self.start.y - origin.y);
+        let distance = (wx * ey - wy * ex) / denominator;
+        let along = (wx * dy - wy * dx) / denominator;
+
+        (distance >= 0.0 && (0.0..=1.0).contains(&along)).then_some(distance)
+    }
+}
+
+/// Everything on the field that a distance sensor can see.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldMap {
+    pub walls: FieldWalls,
+    pub obstacles: Vec<Segment>,
+}
+
+impl FieldMap {
+    /// Creates a map containing only the field perimeter.
+    pub const fn empty(walls: FieldWalls) -> Self {
+        Self {
+            walls,
+            obstacles: Vec::new(),
+        }
+    }
+
+    /// The VEX V5RC Push Back field, with the red alliance wall at -x.
+    ///
+    /// The long goals are modelled as solid 4" wide bars running parallel to the
+    /// x-axis. The center goals are raised above the height of a typical distance
+    /// sensor mount and are left out.
+    pub fn push_back() -> Self {
+        const LONG_GOAL_HALF_LENGTH: f64 = 24.4;
+        const LONG_GOAL_HALF_WIDTH: f64 = 2.0;
+        const LONG_GOAL_Y: f64 = 47.0;
+
+        let mut obstacles = Vec::new();
+        for y in [-LONG_GOAL_Y, LONG_GOAL_Y] {
+            let corners = [
+                Vec2::new(-LONG_GOAL_HALF_LENGTH, y - LONG_GOAL_HALF_WIDTH),
+                Vec2::new(LONG_GOAL_HALF_LENGTH, y - LONG_GOAL_HALF_WIDTH),
+                Vec2::new(LONG_GOAL_HALF_LENGTH, y + LONG_GOAL_HALF_WIDTH),
+                Vec2::new(-LONG_GOAL_HALF_LENGTH, y + LONG_GOAL_HALF_WIDTH),
+            ];
+
+            for i in 0..corners.len() {
+                obstacles.push(Segment::new(corners[i], corners[(i + 1) % corners.len()]));
+            }
+        }
+
+        Self {
+            walls: FieldWalls::STANDARD,
+            obstacles,
+        }
+    }
+
+    /// Returns the distance along a ray to the nearest wall or obstacle.
+    pub fn raycast(&self, origin: Vec2<f64>, direction: Angle) -> Option<f64> {
+        let wall = self.walls.raycast(origin, direction)?.1;
+
+        Some(
+            self.obstacles
+                .iter()
+                .filter_map(|obstacle| obstacle.raycast(origin, direction))
+                .fold(wall, f64::min),
+        )
+    }
+
+    /// Returns `true` if a point is inside the field perimeter.
+    pub fn contains(&self, point: Vec2<f64>) -> bool {
+        point.x >= self.walls.min.x
+            && point.x <= self.walls.max.x
+            && point.y >= self.walls.min.y
+            && point.y <= self.walls.max.y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    fn segment() -> Segment {
+        Segment::new(Vec2::new(10.0, -5.0), Vec2::new(10.0, 5.0))
+    }
+
+    #[test]
+    fn segment_raycast_hits_head_on() {
+        let distance = segment()
+            .raycast(Vec2::new(0.0, 0.0), Angle::from_degrees(0.0))
+            .unwrap();
+
+        assert_near(distance, 10.0);
+    }
+
+    #[test]
+    fn segment_raycast_hits_at_an_angle() {
+        let distance = segment()
+            .raycast(Vec2::new(0.0, 0.0), Angle::from_degrees(20.0))
+            .unwrap();
+
+        assert_near(distance, 10.0 / Angle::from_degrees(20.0).cos());
+    }
+
+    #[test]
+    fn segment_raycast_misses() {
+        let segment = segment();
+        let origin = Vec2::new(0.0, 0.0);
+
+        // Behind the ray, past the end of the segment, and parallel to it.
+        assert_eq!(segment.raycast(origin, Angle::from_degrees(180.0)), None);
+        assert_eq!(segment.raycast(origin, Angle::from_degrees(45.0)), None);
+        assert_eq!(segment.raycast(origin, Angle::from_degrees(90.0)), None);
+    }
+
+    #[test]
+    fn field_map_prefers_nearer_obstacle() {
+        let map = FieldMap::push_back();
+
+        // The near face of the long goal is 45" away along +y.
+        assert_near(
+            map.raycast(Vec2::new(0.0, 0.0), Angle::from_degrees(90.0))
+                .unwrap(),
+            45.0,
+        );
+
+        // Nothing but the wall along +x.
+        assert_near(
+            map.raycast(Vec2::new(0.0, 0.0), Angle::from_degrees(0.0))
+                .unwrap(),
+            70.2,
+        );
+
+        // Past the end of the goal the ray reaches the wall.
+        assert_near(
+            map.raycast(Vec2::new(30.0, 0.0), Angle::from_degrees(90.0))
+                .unwrap(),
+            70.2,
+        );
+    }
+
+    #[test]
+    fn field_map_raycast_from_outside_misses() {
+        let map = FieldMap::empty(FieldWalls::STANDARD);
+
+        assert_eq!(
+            map.raycast(Vec2::new(80.0, 0.0), Angle::from_degrees(180.0)),
+            None
+        );
+        assert!(!map.contains(Vec2::new(80.0, 0.0)));
+        assert!(map.contains(Vec2::new(70.2, -70.2)));
+    }
+}
@@ -0,0 +1,283 @@
+//! Absolute position corrections from external sensors.
+
+use evian::{
+    math::{Angle, Vec2},
+    tracking::wheeled::WheeledTracking,
+};
+
+pub mod map;
+pub mod monte_carlo;
+pub mod particle;
+pub mod wall;
+
+/// A sensor mounted on the robot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SensorMount<S> {
+    pub sensor: S,
+
+    /// Position of the sensor relative to the center of the robot (x forwards,
+    /// y left) in inches.
+    pub offset: Vec2<f64>,
+
+    /// Direction the sensor faces relative to the front of the robot.
+    pub angle: Angle,
+}
+
+impl<S> SensorMount<S> {
+    pub fn new(sensor: S, offset: impl Into<Vec2<f64>>, angle: Angle) -> Self {
+        Self {
+            sensor,
+            offset: offset.into(),
+            angle,
+        }
+    }
+
+    /// Returns the position of the sensor on the field given the robot's pose.
+    pub fn origin(&self, position: Vec2<f64>, heading: Angle) -> Vec2<f64> {
+        let (sin, cos) = (heading.sin(), heading.cos());
+
+        position
+            + Vec2::new(
+                self.offset.x * cos - self.offset.y * sin,
+                self.offset.x * sin + self.offset.y * cos,
+            )
+    }
+
+    /// Returns the direction the sensor faces on the field given the robot's heading.
+    pub fn direction(&self, heading: Angle) -> Angle {
+        heading + self.angle
+    }
+}
+
+/// One side of the field perimeter.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Wall {
+    /// The wall at the minimum x-coordinate.
+    Left,
+
+    /// The wall at the maximum x-coordinate.
+    Right,
+
+    /// The wall at the minimum y-coordinate.
+    Bottom,
+
+    /// The wall at the maximum y-coordinate.
+    Top,
+}
+
+impl Wall {
+    /// Returns the angle between a ray travelling in `direction` and the
+    /// outward-facing normal of this wall (pointing out of the field), from 0°
+    /// (head-on) to 180°.
+    pub fn incidence(self, direction: Angle) -> Angle {
+        let normal = match self {
+            Self::Left => Angle::HALF_TURN,
+            Self::Right => Angle::from_radians(0.0),
+            Self::Bottom => Angle::from_degrees(270.0),
+            Self::Top => Angle::from_degrees(90.0),
+        };
+
+        Angle::from_radians((direction - normal).wrapped_half().as_radians().abs())
+    }
+}
+
+/// Axis-aligned rectangular field perimeter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldWalls {
+    /// Corner of the field with the smallest coordinates.
+    pub min: Vec2<f64>,
+
+    /// Corner of the field with the largest coordinates.
+    pub max: Vec2<f64>,
+}
+
+impl FieldWalls {
+    /// A standard 12' field centered on the origin, measured to the inside of
+    /// the perimeter.
+    pub const STANDARD: Self = Self::centered(70.2);
+
+    /// Creates a square field centered on the origin.
+    pub const fn centered(half_width: f64) -> Self {
+        Self {
+            min: Vec2::new(-half_width, -half_width),
+            max: Vec2::new(half_width, half_width),
+        }
+    }
+
+    /// Returns the coordinate of a wall along the axis it is perpendicular to.
+    pub const fn coordinate(&self, wall: Wall) -> f64 {
+        match wall {
+            Wall::Left => self.min.x,
+            Wall::Right => self.max.x,
+            Wall::Bottom => self.min.y,
+            Wall::Top => self.max.y,
+        }
+    }
+
+    /// Returns the first wall hit by a ray and the distance to it.
+    ///
+    /// Returns `None` if the ray starts outside the field.
+    pub fn raycast(&self, origin: Vec2<f64>, direction: Angle) -> Option<(Wall, f64)> {
+        if origin.x < self.min.x
+            || origin.x > self.max.x
+            || origin.y < self.min.y
+            || origin.y > self.max.y
+        {
+            return None;
+        }
+
+        let (dx, dy) = (direction.cos(), direction.sin());
+        let mut hit = None;
+
+        let mut consider = |wall: Wall, distance: f64| {
+            if hit.is_none_or(|(_, closest)| distance < closest) {
+                hit = Some((wall, distance));
+            }
+        };
+
+        if dx > 0.0 {
+            consider(Wall::Right, (self.max.x - origin.x) / dx);
+        } else if dx < 0.0 {
+            consider(Wall::Left, (self.min.x - origin.x) / dx);
+        }
+        if dy > 0.0 {
+            consider(Wall::Top, (self.max.y - origin.y) / dy);
+        } else if dy < 0.0 {
+            consider(Wall::Bottom, (self.min.y - origin.y) / dy);
+        }
+
+        hit
+    }
+}
+
+/// Tracking whose position can be overwritten.
+pub trait SetPosition {
+    fn set_position(&mut self, position: Vec2<f64>);
+}
+
+impl SetPosition for WheeledTracking {
+    fn set_position(&mut self, position: Vec2<f64>) {
+        WheeledTracking::set_position(self, position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn raycast_hits_wall_ahead() {
+        let walls = FieldWalls::STANDARD;
+
+        for (degrees, expected) in [
+            (0.0, Wall::Right),
+            (90.0, Wall::Top),
+            (180.0, Wall::Left),
+            (270.0, Wall::Bottom),
+        ] {
+            let (wall, distance) = walls
+                .raycast(Vec2::new(0.0, 0.0), Angle::from_degrees(degrees))
+                .unwrap();
+            assert_eq!(wall, expected);
+            assert_near(distance, 70.2);
+        }
+    }
+
+    #[test]
+    fn raycast_picks_nearest_wall_at_an_angle() {
+        let walls = FieldWalls::STANDARD;
+
+        let (wall, distance) = walls
+            .raycast(Vec2::new(60.0, 0.0), Angle::from_degrees(45.0))
+            .unwrap();
+        assert_eq!(wall, Wall::Right);
+        assert_near(distance, 10.2 * core::f64::consts::SQRT_2);
+
+        let (wall, distance) = walls
+            .raycast(Vec2::new(0.0, -60.0), Angle::from_degrees(200.0))
+            .unwrap();
+        assert_eq!(wall, Wall::Bottom);
+        assert_near(distance, 10.2 / Angle::from_degrees(20.0).sin());
+    }
+
+    #[test]
+    fn raycast_from_outside_field_misses() {
+        let walls = FieldWalls::centered(10.0);
+
+        assert_eq!(
+            walls.raycast(Vec2::new(11.0, 0.0), Angle::from_degrees(180.0)),
+            None
+        );
+        assert_eq!(
+            walls.raycast(Vec2::new(0.0, -10.5), Angle::from_degrees(90.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn incidence_is_zero_head_on() {
+        for (wall, degrees) in [
+            (Wall::Right, 0.0),
+            (Wall::Top, 90.0),
+            (Wall::Left, 180.0),
+            (Wall::Bottom, 270.0),
+        ] {
+            assert_near(
+                wall.incidence(Angle::from_degrees(degrees)).as_degrees(),
+                0.0,
+            );
+        }
+    }
+
+    #[test]
+    fn incidence_is_symmetric_about_the_normal() {
+        assert_near(
+            Wall::Right
+                .incidence(Angle::from_degrees(30.0))
+                .as_degrees(),
+            30.0,
+        );
+        assert_near(
+            Wall::Right
+                .incidence(Angle::from_degrees(-30.0))
+                .as_degrees(),
+            30.0,
+        );
+        assert_near(
+            Wall::Top.incidence(Angle::from_degrees(135.0)).as_degrees(),
+            45.0,
+        );
+    }
+
+    #[test]
+    fn incidence_is_half_turn_facing_away() {
+        assert_near(
+            Wall::Left.incidence(Angle::from_degrees(0.0)).as_degrees(),
+            180.0,
+        );
+        assert_near(
+            Wall::Bottom
+                .incidence(Angle::from_degrees(90.0))
+                .as_degrees(),
+            180.0,
+        );
+    }
+
+    #[test]
+    fn sensor_mount_rotates_with_robot() {
+        let mount = SensorMount::new((), (2.0, 1.0), Angle::from_degrees(90.0));
+        let heading = Angle::from_degrees(90.0);
+
+        let origin = mount.origin(Vec2::new(10.0, 10.0), heading);
+        assert_near(origin.x, 9.0);
+        assert_near(origin.y, 12.0);
+        assert_near(mount.direction(heading).as_degrees(), 180.0);
+    }
+}
@@ -0,0 +1,160 @@
+//! Particle filter tracking.
+
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use evian::{
+    math::{Angle, Vec2},
+    tracking::{TracksForwardTravel, TracksHeading, TracksPosition, TracksVelocity},
+};
+use vexide::{
+    task::{Task, spawn},
+    time::sleep,
+};
+
+use crate::{
+    localization::{
+        SensorMount, SetPosition,
+        particle::{Beam, ParticleFilter},
+    },
+    motion::distance_sensor::ObjectDistance,
+};
+
+struct State<T> {
+    tracking: T,
+    filter: ParticleFilter,
+    last_odometry: Vec2<f64>,
+    estimate: Vec2<f64>,
+}
+
+/// Tracking that corrects another tracking's position with a [`ParticleFilter`].
+///
+/// Odometry displacement and heading are taken from the wrapped tracking (usually
+/// [`WheeledTracking`](evian::tracking::wheeled::WheeledTracking) with an IMU),
+/// and distance sensor readings are matched against the filter's field map in a
+/// background task. Heading, velocity and forward travel are passed through
+/// unchanged, so any motion that accepts the wrapped tracking accepts this too.
+pub struct MonteCarloTracking<T> {
+    _task: Task<()>,
+    state: Rc<RefCell<State<T>>>,
+}
+
+impl<T: TracksPosition + TracksHeading + 'static> MonteCarloTracking<T> {
+    /// Time between filter updates.
+    pub const UPDATE_INTERVAL: Duration = Duration::from_millis(20);
+
+    /// Starts filtering `tracking` with readings from the given sensors.
+    ///
+    /// The filter's particles should already be distributed around the robot's
+    /// starting position.
+    pub fn new<S: ObjectDistance + 'static>(
+        tracking: T,
+        sensors: Vec<SensorMount<S>>,
+        filter: ParticleFilter,
+    ) -> Self {
+        let last_odometry = tracking.position();
+        let estimate = filter.estimate();
+        let state = Rc::new(RefCell::new(State {
+            tracking,
+            filter,
+            last_odometry,
+            estimate,
+        }));
+
+        Self {
+            _task: spawn(Self::task(state.clone(), sensors)),
+            state,
+        }
+    }
+
+    async fn task<S: ObjectDistance>(state: Rc<RefCell<State<T>>>, sensors: Vec<SensorMount<S>>) {
+        let mut beams = Vec::with_capacity(sensors.len());
+
+        loop {
+            {
+                let mut state = state.borrow_mut();
+                let state = &mut *state;
+
+                let odometry = state.tracking.position();
+                let heading = state.tracking.heading();
+
+                state.filter.predict(odometry - state.last_odometry);
+                state.last_odometry = odometry;
+
+                beams.clear();
+                beams.extend(sensors.iter().filter_map(|mount| {
+                    Some(Beam {
+                        offset: mount.offset,
+                        angle: mount.angle,
+                        distance: mount.sensor.object_distance()?,
+                    })
+                }));
+
+                state.filter.update(heading, &beams);
+                state.estimate = state.filter.estimate();
+            }
+
+            sleep(Self::UPDATE_INTERVAL).await;
+        }
+    }
+
+    /// Redistributes the filter's particles around a known position.
+    pub fn set_position(&mut self, position: impl Into<Vec2<f64>>, spread: f64) {
+        let mut state = self.state.borrow_mut();
+        let position = position.into();
+
+        state.last_odometry = state.tracking.position();
+        state.filter.reset(position, spread);
+        state.estimate = position;
+    }
+
+    /// Returns the wrapped tracking's uncorrected position.
+    pub fn odometry_position(&self) -> Vec2<f64> {
+        self.state.borrow().tracking.position()
+    }
+
+    /// Returns the standard deviation of the filter's particles in inches.
+    ///
+    /// Larger values mean the filter is less certain of its estimate.
+    pub fn spread(&self) -> f64 {
+        self.state.borrow().filter.spread()
+    }
+
+    /// Runs a closure with mutable access to the wrapped tracking.
+    pub fn with_tracking<R>(&mut self, f: impl FnOnce(&mut T) -> R) -> R {
+        f(&mut self.state.borrow_mut().tracking)
+    }
+}
+
+impl<T: TracksPosition + TracksHeading + 'static> SetPosition for MonteCarloTracking<T> {
+    fn set_position(&mut self, position: Vec2<f64>) {
+        MonteCarloTracking::set_position(self, position, 0.5);
+    }
+}
+
+impl<T> TracksPosition for MonteCarloTracking<T> {
+    fn position(&self) -> Vec2<f64> {
+        self.state.borrow().estimate
+    }
+}
+
+impl<T: TracksHeading> TracksHeading for MonteCarloTracking<T> {
+    fn heading(&self) -> Angle {
+        self.state.borrow().tracking.heading()
+    }
+}
+
+impl<T: TracksVelocity> TracksVelocity for MonteCarloTracking<T> {
+    fn linear_velocity(&self) -> f64 {
+        self.state.borrow().tracking.linear_velocity()
+    }
+
+    fn angular_velocity(&self) -> f64 {
+        self.state.borrow().tracking.angular_velocity()
+    }
+}
+
+impl<T: TracksForwardTravel> TracksForwardTravel for MonteCarloTracking<T> {
+    fn forward_travel(&self) -> f64 {
+        self.state.borrow().tracking.forward_travel()
+    }
+}
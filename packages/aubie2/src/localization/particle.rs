@@ -0,0 +1,475 @@
+//! Monte Carlo localization.
+//!
+//! This module only contains the filter's math and has no dependency on robot
+//! hardware, so it can be driven on the host with synthetic odometry and sensor
+//! readings.
+
+use core::f64::consts::TAU;
+
+use evian::math::{Angle, Vec2};
+
+use crate::localization::map::FieldMap;
+
+/// Tuning parameters for a [`ParticleFilter`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleFilterConfig {
+    /// Number of particles. Must be nonzero.
+    pub particle_count: usize,
+
+    /// Standard deviation of odometry error per inch travelled.
+    pub odometry_noise: f64,
+
+    /// Standard deviation of a distance reading at zero range, in inches.
+    pub sensor_noise: f64,
+
+    /// Additional standard deviation of a distance reading per inch of range.
+    pub sensor_noise_per_inch: f64,
+
+    /// Probability that a reading is unrelated to the map (e.g. it hit a game
+    /// element or another robot).
+    pub outlier_probability: f64,
+
+    /// Particles are resampled once the effective sample size drops below this
+    /// fraction of the particle count.
+    pub resample_threshold: f64,
+}
+
+impl Default for ParticleFilterConfig {
+    fn default() -> Self {
+        Self {
+            particle_count: 300,
+            odometry_noise: 0.05,
+            sensor_noise: 0.6,
+            sensor_noise_per_inch: 0.05,
+            outlier_probability: 0.1,
+            resample_threshold: 0.5,
+        }
+    }
+}
+
+/// A single distance reading.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Beam {
+    /// Position of the sensor relative to the center of the robot (x forwards,
+    /// y left) in inches.
+    pub offset: Vec2<f64>,
+
+    /// Direction the sensor faces relative to the front of the robot.
+    pub angle: Angle,
+
+    /// Measured distance in inches.
+    pub distance: f64,
+}
+
+/// A hypothesis of the robot's position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Particle {
+    pub position: Vec2<f64>,
+    pub weight: f64,
+}
+
+/// Small deterministic random number generator (xorshift64*).
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a uniformly distributed number in `[0, 1)`.
+    fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a normally distributed number with the given standard deviation.
+    fn gaussian(&mut self, std_dev: f64) -> f64 {
+        let u1 = self.uniform().max(f64::MIN_POSITIVE);
+        let u2 = self.uniform();
+
+        std_dev * (-2.0 * u1.ln()).sqrt() * (TAU * u2).cos()
+    }
+}
+
+/// Estimates the robot's position from odometry and distance readings.
+///
+/// Heading is assumed to be known (from an IMU), so particles only track
+/// position.
+#[derive(Debug, Clone)]
+pub struct ParticleFilter {
+    pub config: ParticleFilterConfig,
+    pub map: FieldMap,
+    particles: Vec<Particle>,
+    rng: Rng,
+}
+
+impl ParticleFilter {
+    /// Creates a filter with particles spread normally around a starting position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `config.particle_count` is zero.
+    pub fn new(
+        config: ParticleFilterConfig,
+        map: FieldMap,
+        position: impl Into<Vec2<f64>>,
+        spread: f64,
+        seed: u64,
+    ) -> Self {
+        assert!(
+            config.particle_count > 0,
+            "A particle filter needs at least one particle."
+        );
+
+        let mut filter = Self {
+            config,
+            map,
+            particles: Vec::with_capacity(config.particle_count),
+            rng: Rng::new(seed),
+        };
+        filter.reset(position, spread);
+        filter
+    }
+
+    /// Redistributes every particle normally around a position.
+    pub fn reset(&mut self, position: impl Into<Vec2<f64>>, spread: f64) {
+        let position = position.into();
+        let weight = 1.0 / self.config.particle_count as f64;
+
+        self.particles.clear();
+        for _ in 0..self.config.particle_count {
+            self.particles.push(Particle {
+                position: Vec2::new(
+                    position.x + self.rng.gaussian(spread),
+                    position.y + self.rng.gaussian(spread),
+                ),
+                weight,
+            });
+        }
+    }
+
+    /// Returns the current particles.
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Moves every particle by an odometry displacement in field coordinates.
+    pub fn predict(&mut self, displacement: Vec2<f64>) {
+        let std_dev = self.config.odometry_noise * displacement.x.hypot(displacement.y);
+        if std_dev == 0.0 {
+            return;
+        }
+
+        for particle in &mut self.particles {
+            particle.position += Vec2::new(
+                displacement.x + self.rng.gaussian(std_dev),
+                displacement.y + self.rng.gaussian(std_dev),
+            );
+        }
+    }
+
+    /// Weighs particles by how well they explain a set of distance readings,
+    /// resampling if the weights have degenerated.
+    pub fn update(&mut self, heading: Angle, beams: &[Beam]) {
+        if beams.is_empty() {
+            return;
+        }
+
+        let (sin, cos) = (heading.sin(), heading.cos());
+        let config = self.config;
+
+        for particle in &mut self.particles {
+            if !self.map.contains(particle.position) {
+                particle.weight = 0.0;
+                continue;
+            }
+
+            for beam in beams {
+                let origin = particle.position
+                    + Vec2::new(
+                        beam.offset.x * cos - beam.offset.y * sin,
+                        beam.offset.x * sin + beam.offset.y * cos,
+                    );
+
+                let likelihood = match self.map.raycast(origin, heading + beam.angle) {
+                    Some(expected) => {
+                        let std_dev = config.sensor_noise + config.sensor_noise_per_inch * expected;
+                        let z = (beam.distance - expected) / std_dev;
+
+                        (1.0 - config.outlier_probability) * (-0.5 * z * z).exp()
+                            + config.outlier_probability
+                    }
+                    None => config.outlier_probability,
+                };
+
+                particle.weight *= likelihood;
+            }
+        }
+
+        let total: f64 = self.particles.iter().map(|particle| particle.weight).sum();
+        if total <= 0.0 || !total.is_finite() {
+            // Every particle was ruled out, so the readings can't be trusted.
+            // The weights are meaningless now, so restart from where the
+            // particles are rather than from the weighted estimate.
+            let mean = self.mean();
+            self.reset(mean, 2.0);
+            return;
+        }
+
+        for particle in &mut self.particles {
+            particle.weight /= total;
+        }
+
+        if self.effective_sample_size() < config.resample_threshold * self.particles.len() as f64 {
+            self.resample();
+        }
+    }
+
+    /// Returns the weighted mean of every particle.
+    ///
+    /// Falls back to the unweighted mean if no particle has any weight.
+    pub fn estimate(&self) -> Vec2<f64> {
+        let (mut x, mut y, mut total) = (0.0, 0.0, 0.0);
+        for particle in &self.particles {
+            x += particle.position.x * particle.weight;
+            y += particle.position.y * particle.weight;
+            total += particle.weight;
+        }
+
+        if total > 0.0 && total.is_finite() {
+            Vec2::new(x / total, y / total)
+        } else {
+            self.mean()
+        }
+    }
+
+    /// Returns the unweighted mean of every particle.
+    fn mean(&self) -> Vec2<f64> {
+        let count = self.particles.len().max(1) as f64;
+        let (x, y) = self.particles.iter().fold((0.0, 0.0), |(x, y), particle| {
+            (x + particle.position.x, y + particle.position.y)
+        });
+
+        Vec2::new(x / count, y / count)
+    }
+
+    /// Returns the weighted standard deviation of particle positions in inches.
+    pub fn spread(&self) -> f64 {
+        let estimate = self.estimate();
+        let (mut variance, mut total) = (0.0, 0.0);
+        for particle in &self.particles {
+            let error = particle.position - estimate;
+            variance += (error.x * error.x + error.y * error.y) * particle.weight;
+            total += particle.weight;
+        }
+
+        if total > 0.0 {
+            (variance / total).sqrt()
+        } else {
+            0.0
+        }
+    }
+
+    fn effective_sample_size(&self) -> f64 {
+        1.0 / self
+            .particles
+            .iter()
+            .map(|particle| particle.weight * particle.weight)
+            .sum::<f64>()
+    }
+
+    /// Low-variance (systematic) resampling.
+    fn resample(&mut self) {
+        let Some(first) = self.particles.first() else {
+            return;
+        };
+
+        let count = self.particles.len();
+        let step = 1.0 / count as f64;
+        let mut cumulative = first.weight;
+        let mut target = self.rng.uniform() * step;
+        let mut index = 0;
+
+        let mut resampled = Vec::with_capacity(count);
+        for _ in 0..count {
+            while target > cumulative && index < count - 1 {
+                index += 1;
+                cumulative += self.particles[index].weight;
+            }
+
+            resampled.push(Particle {
+                position: self.particles[index].position,
+                weight: step,
+            });
+            target += step;
+        }
+
+        self.particles = resampled;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::localization::FieldWalls;
+
+    fn filter(position: (f64, f64), spread: f64) -> ParticleFilter {
+        ParticleFilter::new(
+            ParticleFilterConfig::default(),
+            FieldMap::empty(FieldWalls::STANDARD),
+            position,
+            spread,
+            1,
+        )
+    }
+
+    fn beam(degrees: f64, distance: f64) -> Beam {
+        Beam {
+            offset: Vec2::new(0.0, 0.0),
+            angle: Angle::from_degrees(degrees),
+            distance,
+        }
+    }
+
+    fn distance(a: Vec2<f64>, b: Vec2<f64>) -> f64 {
+        (a.x - b.x).hypot(a.y - b.y)
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_zero_particles() {
+        ParticleFilter::new(
+            ParticleFilterConfig {
+                particle_count: 0,
+                ..Default::default()
+            },
+            FieldMap::empty(FieldWalls::STANDARD),
+            (0.0, 0.0),
+            1.0,
+            1,
+        );
+    }
+
+    #[test]
+    fn predict_moves_particles_by_displacement() {
+        let mut filter = filter((0.0, 0.0), 0.0);
+
+        filter.predict(Vec2::new(10.0, 5.0));
+
+        assert!(distance(filter.estimate(), Vec2::new(10.0, 5.0)) < 0.2);
+        assert!(filter.spread() > 0.0);
+    }
+
+    #[test]
+    fn predict_without_displacement_adds_no_noise() {
+        let mut filter = filter((12.0, -4.0), 0.0);
+
+        filter.predict(Vec2::new(0.0, 0.0));
+
+        assert!(distance(filter.estimate(), Vec2::new(12.0, -4.0)) < 1e-9);
+        assert!(filter.spread() < 1e-9);
+    }
+
+    #[test]
+    fn update_converges_on_readings() {
+        let mut filter = filter((0.0, 0.0), 6.0);
+        let initial_spread = filter.spread();
+
+        // Readings taken from (5, -3) facing +x.
+        let beams = [
+            beam(0.0, 65.2),
+            beam(90.0, 73.2),
+            beam(180.0, 75.2),
+            beam(270.0, 67.2),
+        ];
+        for _ in 0..5 {
+            filter.update(Angle::from_degrees(0.0), &beams);
+        }
+
+        assert!(distance(filter.estimate(), Vec2::new(5.0, -3.0)) < 2.0);
+        assert!(filter.spread() < initial_spread);
+    }
+
+    #[test]
+    fn update_without_beams_keeps_weights() {
+        let mut filter = filter((0.0, 0.0), 6.0);
+        let particles = filter.particles().to_vec();
+
+        filter.update(Angle::from_degrees(0.0), &[]);
+
+        assert_eq!(filter.particles(), particles);
+    }
+
+    #[test]
+    fn update_ruling_out_every_particle_keeps_position() {
+        let mut filter = ParticleFilter::new(
+            ParticleFilterConfig {
+                sensor_noise: 0.01,
+                sensor_noise_per_inch: 0.0,
+                outlier_probability: 0.0,
+                ..Default::default()
+            },
+            FieldMap::empty(FieldWalls::STANDARD),
+            (30.0, 30.0),
+            1.0,
+            1,
+        );
+
+        // Nothing near (30, 30) is 5" from the wall ahead.
+        filter.update(Angle::from_degrees(0.0), &[beam(0.0, 5.0)]);
+
+        assert!(distance(filter.estimate(), Vec2::new(30.0, 30.0)) < 1.0);
+        let total: f64 = filter
+            .particles()
+            .iter()
+            .map(|particle| particle.weight)
+            .sum();
+        assert!((total - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn resample_duplicates_heavy_particles() {
+        let mut filter = filter((0.0, 0.0), 6.0);
+        let count = filter.particles().len();
+        let heavy = filter.particles[7].position;
+
+        for (index, particle) in filter.particles.iter_mut().enumerate() {
+            particle.weight = if index == 7 { 1.0 } else { 0.0 };
+        }
+        filter.resample();
+
+        assert_eq!(filter.particles().len(), count);
+        for particle in filter.particles() {
+            assert_eq!(particle.position, heavy);
+            assert!((particle.weight - 1.0 / count as f64).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn resample_keeps_evenly_weighted_particles() {
+        let mut filter = filter((0.0, 0.0), 6.0);
+        let mut positions: Vec<_> = filter
+            .particles()
+            .iter()
+            .map(|particle| (particle.position.x, particle.position.y))
+            .collect();
+
+        filter.resample();
+
+        let mut resampled: Vec<_> = filter
+            .particles()
+            .iter()
+            .map(|particle| (particle.position.x, particle.position.y))
+            .collect();
+        positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        resampled.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(positions, resampled);
+    }
+}
@@ -0,0 +1,126 @@
+//! Single-shot odometry correction from distance sensors facing the field walls.
+
+use evian::{
+    math::{Angle, Vec2},
+    prelude::{TracksHeading, TracksPosition},
+};
+use log::debug;
+
+use crate::{
+    localization::{FieldWalls, SensorMount, SetPosition, Wall},
+    motion::distance_sensor::ObjectDistance,
+};
+
+/// Absolute position correction computed from wall measurements.
+///
+/// Each axis is `None` if no sensor produced a usable measurement for it.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct WallCorrection {
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+}
+
+/// Re-anchors odometry to field coordinates using distance sensors pointed at
+/// the field walls.
+pub struct WallLocalizer<S: ObjectDistance> {
+    pub mounts: Vec<SensorMount<S>>,
+    pub walls: FieldWalls,
+
+    /// Largest angle between a sensor's beam and the wall normal that is
+    /// trusted. Readings at glancing angles are noisy.
+    pub max_incidence: Angle,
+
+    /// Largest difference between the measured and expected distance that is
+    /// trusted. Larger disagreements usually mean the beam hit a game element.
+    pub max_disagreement: f64,
+}
+
+impl<S: ObjectDistance> WallLocalizer<S> {
+    /// Creates a localizer with default rejection thresholds.
+    pub fn new(mounts: Vec<SensorMount<S>>, walls: FieldWalls) -> Self {
+        Self {
+            mounts,
+            walls,
+            max_incidence: Angle::from_degrees(25.0),
+            max_disagreement: 12.0,
+        }
+    }
+
+    /// Computes an absolute position correction given the current pose estimate.
+    ///
+    /// The estimate is only used to decide which wall each sensor is facing and to
+    /// reject readings that disagree with it too much.
+    pub fn correction(&self, position: Vec2<f64>, heading: Angle) -> WallCorrection {
+        let mut x_sum = 0.0;
+        let mut x_count = 0;
+        let mut y_sum = 0.0;
+        let mut y_count = 0;
+
+        for mount in &self.mounts {
+            let Some(distance) = mount.sensor.object_distance() else {
+                continue;
+            };
+
+            let origin = mount.origin(position, heading);
+            let direction = mount.direction(heading);
+
+            let Some((wall, expected)) = self.walls.raycast(origin, direction) else {
+                continue;
+            };
+
+            if wall.incidence(direction) > self.max_incidence {
+                continue;
+            }
+            if (distance - expected).abs() > self.max_disagreement {
+                debug!(
+                    "Rejected wall reading of {:.1}\" (expected {:.1}\").",
+                    distance, expected
+                );
+                continue;
+            }
+
+            // Offset from the robot's center to where the beam hits the wall.
+            let reach =
+                (origin - position) + Vec2::new(direction.cos(), direction.sin()) * distance;
+
+            match wall {
+                Wall::Left | Wall::Right => {
+                    x_sum += self.walls.coordinate(wall) - reach.x;
+                    x_count += 1;
+                }
+                Wall::Bottom | Wall::Top => {
+                    y_sum += self.walls.coordinate(wall) - reach.y;
+                    y_count += 1;
+                }
+            }
+        }
+
+        WallCorrection {
+            x: (x_count > 0).then(|| x_sum / x_count as f64),
+            y: (y_count > 0).then(|| y_sum / y_count as f64),
+        }
+    }
+
+    /// Computes a correction from the tracking's current pose and applies it.
+    pub fn apply<T>(&self, tracking: &mut T) -> WallCorrection
+    where
+        T: TracksPosition + TracksHeading + SetPosition,
+    {
+        let position = tracking.position();
+        let correction = self.correction(position, tracking.heading());
+
+        let corrected = Vec2::new(
+            correction.x.unwrap_or(position.x),
+            correction.y.unwrap_or(position.y),
+        );
+        if corrected != position {
+            debug!(
+                "Wall reset moved odometry from {} to {}.",
+                position, corrected
+            );
+            tracking.set_position(corrected);
+        }
+
+        correction
+    }
+}
@@ -0,0 +1,198 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use crate::motion::{
+    CancelToken, MotionResult,
+    future::{
+        self, DriveState, angular_pid_modifiers, drive_modifiers, linear_pid_modifiers,
+        motion_modifiers,
+    },
+    report::AutonReport,
+};
+
+use evian::{
+    control::{
+        Tolerances,
+        loops::{AngularPid, Feedback, Pid},
+    },
+    drivetrain::{Drivetrain, model::Arcade},
+    math::Angle,
+    tracking::{TracksForwardTravel, TracksHeading, TracksVelocity},
+};
+
+/// Where the arc started, captured on the first poll.
+pub(crate) struct ArcStart {
+    heading: Angle,
+    forward_travel: f64,
+
+    /// Signed heading change from the start to the target, in radians.
+    sweep: f64,
+}
+
+/// Drives the robot along a constant-radius arc until it reaches a heading.
+///
+/// The arc turns whichever way is shortest to the target heading. A negative
+/// radius drives the arc backwards. The radius must be nonzero; to turn in
+/// place, use [`Basic::turn_to_heading`](evian::motion::Basic::turn_to_heading).
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct ArcFuture<'a, M, L, A, T>
+where
+    M: Arcade,
+    L: Feedback<State = f64, Signal = f64> + Unpin,
+    A: Feedback<State = Angle, Signal = f64> + Unpin,
+    T: TracksForwardTravel + TracksHeading + TracksVelocity,
+{
+    pub(crate) radius: f64,
+    pub(crate) target_heading: Angle,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) linear_tolerances: Tolerances,
+    pub(crate) angular_tolerances: Tolerances,
+    pub(crate) linear_controller: L,
+    pub(crate) angular_controller: A,
+    pub(crate) drivetrain: &'a mut Drivetrain<M, T>,
+
+    /// Token that stops this motion early when cancelled.
+    pub(crate) cancel: Option<CancelToken>,
+
+    /// Report to record this motion's outcome into.
+    pub(crate) report: Option<(AutonReport, &'static str)>,
+
+    pub(crate) start: Option<ArcStart>,
+
+    /// Internal future state ("local variables").
+    pub(crate) state: Option<DriveState>,
+}
+
+// MARK: Future Poll
+
+impl<M, L, A, T> Future for ArcFuture<'_, M, L, A, T>
+where
+    M: Arcade,
+    L: Feedback<State = f64, Signal = f64> + Unpin,
+    A: Feedback<State = Angle, Signal = f64> + Unpin,
+    T: TracksForwardTravel + TracksHeading + TracksVelocity,
+{
+    type Output = MotionResult;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let state = this.state.get_or_insert_with(DriveState::new);
+
+        if Pin::new(&mut state.sleep).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+
+        let dt = state.prev_time.elapsed();
+
+        let heading = this.drivetrain.tracking.heading();
+        let forward_travel = this.drivetrain.tracking.forward_travel();
+        let target_heading = this.target_heading;
+        let start = this.start.get_or_insert_with(|| ArcStart {
+            heading,
+            forward_travel,
+            sweep: (target_heading - heading).wrapped_half().as_radians(),
+        });
+
+        let direction = this.radius.signum();
+        let radius = this.radius.abs();
+
+        // Distance along the arc, and how far the robot should have turned by
+        // now to stay on it.
+        let travelled = (forward_travel - start.forward_travel) * direction;
+        let progress = (travelled / radius).clamp(0.0, start.sweep.abs());
+        let setpoint = start.heading + Angle::from_radians(progress.copysign(start.sweep));
+
+        let linear_error = radius * start.sweep.abs() - travelled;
+        let angular_error = (this.target_heading - heading).wrapped_half();
+
+        if this
+            .linear_tolerances
+            .check(linear_error, this.drivetrain.tracking.linear_velocity())
+        {
+            state.linear_settled = true;
+        }
+        if this.angular_tolerances.check(
+            angular_error.as_radians(),
+            this.drivetrain.tracking.angular_velocity(),
+        ) {
+            state.angular_settled = true;
+        }
+
+        let outcome = future::outcome(
+            state.linear_settled && state.angular_settled,
+            this.cancel.as_ref(),
+            this.timeout,
+            state.start_time,
+        );
+
+        if let Some(outcome) = outcome {
+            drop(this.drivetrain.model.drive_arcade(0.0, 0.0));
+
+            let result = MotionResult {
+                outcome,
+                elapsed: state.start_time.elapsed(),
+                linear_error,
+                angular_error,
+            };
+            return Poll::Ready(future::record(&mut this.report, result));
+        }
+
+        let tracking_error = (setpoint - heading).wrapped_half();
+        let linear_output = this.linear_controller.update(-linear_error, 0.0, dt)
+            * direction
+            * tracking_error.cos().abs();
+
+        let angular_output = this.angular_controller.update(heading, setpoint, dt);
+
+        drop(
+            this.drivetrain
+                .model
+                .drive_arcade(linear_output, angular_output),
+        );
+
+        state.reset_sleep();
+
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+// MARK: Generic Modifiers
+
+impl<M, L, A, T> ArcFuture<'_, M, L, A, T>
+where
+    M: Arcade,
+    L: Feedback<State = f64, Signal = f64> + Unpin,
+    A: Feedback<State = Angle, Signal = f64> + Unpin,
+    T: TracksForwardTravel + TracksHeading + TracksVelocity,
+{
+    motion_modifiers!();
+
+    drive_modifiers!(L, A);
+}
+
+// MARK: Linear PID Modifiers
+
+impl<M, A, T> ArcFuture<'_, M, Pid, A, T>
+where
+    M: Arcade,
+    A: Feedback<State = Angle, Signal = f64> + Unpin,
+    T: TracksForwardTravel + TracksHeading + TracksVelocity,
+{
+    linear_pid_modifiers!();
+}
+
+// MARK: Angular PID Modifiers
+
+impl<M, L, T> ArcFuture<'_, M, L, AngularPid, T>
+where
+    M: Arcade,
+    L: Feedback<State = f64, Signal = f64> + Unpin,
+    T: TracksForwardTravel + TracksHeading + TracksVelocity,
+{
+    angular_pid_modifiers!();
+}
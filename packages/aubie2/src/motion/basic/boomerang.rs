@@ -0,0 +1,285 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use crate::motion::{
+    CancelToken, MotionResult,
+    future::{
+        self, DriveState, angular_pid_modifiers, drive_modifiers, linear_pid_modifiers,
+        motion_modifiers,
+    },
+    report::AutonReport,
+};
+
+use evian::{
+    control::{
+        Tolerances,
+        loops::{AngularPid, Feedback, Pid},
+    },
+    drivetrain::{Drivetrain, model::Arcade},
+    math::{Angle, Vec2},
+    tracking::{TracksHeading, TracksPosition, TracksVelocity},
+};
+
+/// Drives the robot to a point, arriving at a heading.
+///
+/// Rather than aiming straight at the target, the robot chases a "carrot" point
+/// placed behind the target along its final heading. The carrot's distance from
+/// the target is `lead` times the robot's distance from the target, so it slides
+/// onto the target as the robot approaches, bending the path into a curve that
+/// ends facing the right way.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct BoomerangFuture<'a, M, L, A, T>
+where
+    M: Arcade,
+    L: Feedback<State = f64, Signal = f64> + Unpin,
+    A: Feedback<State = Angle, Signal = f64> + Unpin,
+    T: TracksPosition + TracksHeading + TracksVelocity,
+{
+    pub(crate) target_point: Vec2<f64>,
+    pub(crate) target_heading: Angle,
+
+    /// How far behind the target the carrot is placed, as a fraction of the
+    /// distance to the target.
+    pub(crate) lead: f64,
+
+    /// Limits speed through curves so the robot doesn't slide sideways.
+    pub(crate) drift: Option<f64>,
+
+    /// Distance from the target at which the robot stops chasing the carrot and
+    /// turns to the target heading.
+    pub(crate) settle_distance: f64,
+    pub(crate) reverse: bool,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) linear_tolerances: Tolerances,
+    pub(crate) angular_tolerances: Tolerances,
+    pub(crate) linear_controller: L,
+    pub(crate) angular_controller: A,
+    pub(crate) drivetrain: &'a mut Drivetrain<M, T>,
+
+    /// Token that stops this motion early when cancelled.
+    pub(crate) cancel: Option<CancelToken>,
+
+    /// Report to record this motion's outcome into.
+    pub(crate) report: Option<(AutonReport, &'static str)>,
+
+    /// Internal future state ("local variables").
+    pub(crate) state: Option<DriveState>,
+}
+
+// MARK: Future Poll
+
+impl<M, L, A, T> Future for BoomerangFuture<'_, M, L, A, T>
+where
+    M: Arcade,
+    L: Feedback<State = f64, Signal = f64> + Unpin,
+    A: Feedback<State = Angle, Signal = f64> + Unpin,
+    T: TracksPosition + TracksHeading + TracksVelocity,
+{
+    type Output = MotionResult;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let state = this.state.get_or_insert_with(DriveState::new);
+
+        if Pin::new(&mut state.sleep).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+
+        let dt = state.prev_time.elapsed();
+
+        let position = this.drivetrain.tracking.position();
+        let heading = this.drivetrain.tracking.heading();
+
+        // Driving backwards is the same as driving forwards with the robot
+        // turned around.
+        let (facing, direction) = if this.reverse {
+            (heading + Angle::from_degrees(180.0), -1.0)
+        } else {
+            (heading, 1.0)
+        };
+
+        let target = this.target_point;
+        let distance = (target.x - position.x).hypot(target.y - position.y);
+
+        // The heading the robot has to arrive at while facing the way it drives.
+        let final_facing = if this.reverse {
+            this.target_heading + Angle::from_degrees(180.0)
+        } else {
+            this.target_heading
+        };
+
+        let (aim, setpoint, carrot_distance) = if distance < this.settle_distance {
+            // Close in, the carrot would swing wildly around the robot, so hold
+            // the final heading and only correct distance along it.
+            (target, final_facing, distance)
+        } else {
+            let lead = this.lead * distance;
+            let carrot = Vec2::new(
+                target.x - lead * final_facing.cos(),
+                target.y - lead * final_facing.sin(),
+            );
+            let angle = Angle::from_radians((carrot.y - position.y).atan2(carrot.x - position.x));
+
+            (
+                carrot,
+                angle,
+                (carrot.x - position.x).hypot(carrot.y - position.y),
+            )
+        };
+
+        // Distance to the aim point along the way the robot is facing.
+        let aim_angle = Angle::from_radians((aim.y - position.y).atan2(aim.x - position.x));
+        let linear_error = carrot_distance * (aim_angle - facing).wrapped_half().cos();
+        let angular_error = (this.target_heading - heading).wrapped_half();
+
+        // Settling is only checked once close in, and against the straight-line
+        // distance, since the error along the robot's heading is near zero
+        // whenever it faces across the target.
+        let close = distance < this.settle_distance;
+        if close
+            && this
+                .linear_tolerances
+                .check(distance, this.drivetrain.tracking.linear_velocity())
+        {
+            state.linear_settled = true;
+        }
+        if close
+            && this.angular_tolerances.check(
+                angular_error.as_radians(),
+                this.drivetrain.tracking.angular_velocity(),
+            )
+        {
+            state.angular_settled = true;
+        }
+
+        let outcome = future::outcome(
+            state.linear_settled && state.angular_settled,
+            this.cancel.as_ref(),
+            this.timeout,
+            state.start_time,
+        );
+
+        if let Some(outcome) = outcome {
+            drop(this.drivetrain.model.drive_arcade(0.0, 0.0));
+
+            let result = MotionResult {
+                outcome,
+                elapsed: state.start_time.elapsed(),
+                linear_error: distance,
+                angular_error,
+            };
+            return Poll::Ready(future::record(&mut this.report, result));
+        }
+
+        let mut linear_output = this.linear_controller.update(-linear_error, 0.0, dt);
+
+        // Radius of the arc from the robot to the carrot, as in pure pursuit.
+        if let Some(drift) = this.drift {
+            let alpha = (setpoint - facing).wrapped_half().as_radians();
+            let radius = carrot_distance / (2.0 * alpha.sin().abs()).max(f64::EPSILON);
+            let limit = (drift * radius).sqrt();
+
+            linear_output = linear_output.clamp(-limit, limit);
+        }
+
+        let angular_output = this.angular_controller.update(facing, setpoint, dt);
+
+        drop(
+            this.drivetrain
+                .model
+                .drive_arcade(linear_output * direction, angular_output),
+        );
+
+        state.reset_sleep();
+
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+// MARK: Boomerang Modifiers
+
+impl<M, L, A, T> BoomerangFuture<'_, M, L, A, T>
+where
+    M: Arcade,
+    L: Feedback<State = f64, Signal = f64> + Unpin,
+    A: Feedback<State = Angle, Signal = f64> + Unpin,
+    T: TracksPosition + TracksHeading + TracksVelocity,
+{
+    /// Modifies how far behind the target the carrot point is placed, as a
+    /// fraction of the distance to the target.
+    ///
+    /// Larger values give wider curves. Zero drives straight at the target.
+    pub const fn with_lead(&mut self, lead: f64) -> &mut Self {
+        self.lead = lead;
+        self
+    }
+
+    /// Limits speed through curves so that the robot doesn't slide sideways.
+    ///
+    /// The linear output is capped at `sqrt(drift * radius)`, where `radius` is
+    /// the turning radius in inches, so higher values take curves faster.
+    pub const fn with_drift(&mut self, drift: f64) -> &mut Self {
+        self.drift = Some(drift);
+        self
+    }
+
+    /// Removes this motion's curve speed limit.
+    pub const fn without_drift(&mut self) -> &mut Self {
+        self.drift = None;
+        self
+    }
+
+    /// Modifies the distance from the target at which the robot stops chasing
+    /// the carrot and settles onto the target heading.
+    pub const fn with_settle_distance(&mut self, distance: f64) -> &mut Self {
+        self.settle_distance = distance;
+        self
+    }
+
+    /// Drives to the pose backwards.
+    pub const fn reverse(&mut self) -> &mut Self {
+        self.reverse = true;
+        self
+    }
+}
+
+// MARK: Generic Modifiers
+
+impl<M, L, A, T> BoomerangFuture<'_, M, L, A, T>
+where
+    M: Arcade,
+    L: Feedback<State = f64, Signal = f64> + Unpin,
+    A: Feedback<State = Angle, Signal = f64> + Unpin,
+    T: TracksPosition + TracksHeading + TracksVelocity,
+{
+    motion_modifiers!();
+
+    drive_modifiers!(L, A);
+}
+
+// MARK: Linear PID Modifiers
+
+impl<M, A, T> BoomerangFuture<'_, M, Pid, A, T>
+where
+    M: Arcade,
+    A: Feedback<State = Angle, Signal = f64> + Unpin,
+    T: TracksPosition + TracksHeading + TracksVelocity,
+{
+    linear_pid_modifiers!();
+}
+
+// MARK: Angular PID Modifiers
+
+impl<M, L, T> BoomerangFuture<'_, M, L, AngularPid, T>
+where
+    M: Arcade,
+    L: Feedback<State = f64, Signal = f64> + Unpin,
+    T: TracksPosition + TracksHeading + TracksVelocity,
+{
+    angular_pid_modifiers!();
+}
@@ -2,10 +2,17 @@ use std::{
     future::Future,
     pin::Pin,
     task::{Context, Poll},
-    time::{Duration, Instant},
+    time::Duration,
 };
 
-use vexide::time::{Sleep, sleep};
+use crate::motion::{
+    CancelToken, MotionResult,
+    future::{
+        self, DriveState, angular_pid_modifiers, drive_modifiers, linear_pid_modifiers,
+        motion_modifiers,
+    },
+    report::AutonReport,
+};
 
 use evian::{
     control::{
@@ -19,14 +26,6 @@ use evian::{
     tracking::{TracksForwardTravel, TracksHeading, TracksPosition, TracksVelocity}
 };
 
-pub(crate) struct DriveState {
-    pub sleep: Sleep,
-    pub start_time: Instant,
-    pub prev_time: Instant,
-    pub linear_settled: bool,
-    pub angular_settled: bool,
-}
-
 pub(crate) enum Coordinate {
     X,
     Y,
@@ -51,6 +50,12 @@ where
     pub(crate) angular_controller: A,
     pub(crate) drivetrain: &'a mut Drivetrain<M, T>,
 
+    /// Token that stops this motion early when cancelled.
+    pub(crate) cancel: Option<CancelToken>,
+
+    /// Report to record this motion's outcome into.
+    pub(crate) report: Option<(AutonReport, &'static str)>,
+
     /// Internal future state ("local variables").
     pub(crate) state: Option<DriveState>,
 }
@@ -64,20 +69,11 @@ where
     A: Feedback<State = Angle, Signal = f64> + Unpin,
     T: TracksPosition + TracksForwardTravel + TracksHeading + TracksVelocity,
 {
-    type Output = ();
+    type Output = MotionResult;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
-        let state = this.state.get_or_insert_with(|| {
-            let now = Instant::now();
-            DriveState {
-                sleep: sleep(Duration::from_millis(5)),
-                start_time: now,
-                prev_time: now,
-                linear_settled: false,
-                angular_settled: false,
-            }
-        });
+        let state = this.state.get_or_insert_with(DriveState::new);
 
         if Pin::new(&mut state.sleep).poll(cx).is_pending() {
             return Poll::Pending;
@@ -109,13 +105,23 @@ where
             state.angular_settled = true;
         }
 
-        if (state.linear_settled && state.angular_settled)
-            || this
-                .timeout
-                .is_some_and(|timeout| state.start_time.elapsed() > timeout)
-        {
+        let outcome = future::outcome(
+            state.linear_settled && state.angular_settled,
+            this.cancel.as_ref(),
+            this.timeout,
+            state.start_time,
+        );
+
+        if let Some(outcome) = outcome {
             drop(this.drivetrain.model.drive_arcade(0.0, 0.0));
-            return Poll::Ready(());
+
+            let result = MotionResult {
+                outcome,
+                elapsed: state.start_time.elapsed(),
+                linear_error,
+                angular_error,
+            };
+            return Poll::Ready(future::record(&mut this.report, result));
         }
 
         let linear_output =
@@ -131,8 +137,7 @@ where
                 .drive_arcade(linear_output, angular_output),
         );
 
-        state.sleep = sleep(Duration::from_millis(5));
-        state.prev_time = Instant::now();
+        state.reset_sleep();
 
         cx.waker().wake_by_ref();
         Poll::Pending
@@ -148,120 +153,9 @@ where
     A: Feedback<State = Angle, Signal = f64> + Unpin,
     T: TracksPosition + TracksForwardTravel + TracksHeading + TracksVelocity,
 {
-    /// Modifies this motion's linear feedback controller.
-    pub fn with_linear_controller(&mut self, controller: L) -> &mut Self {
-        self.linear_controller = controller;
-        self
-    }
-
-    /// Modifies this motion's angular feedback controller.
-    pub fn with_angular_controller(&mut self, controller: A) -> &mut Self {
-        self.angular_controller = controller;
-        self
-    }
-
-    /// Modifies this motion's timeout duration.
-    pub const fn with_timeout(&mut self, timeout: Duration) -> &mut Self {
-        self.timeout = Some(timeout);
-        self
-    }
-
-    /// Removes this motion's timeout duration.
-    pub const fn without_timeout(&mut self) -> &mut Self {
-        self.timeout = None;
-        self
-    }
-
-    /// Modifies this motion's linear tolerances.
-    pub const fn with_linear_tolerances(&mut self, tolerances: Tolerances) -> &mut Self {
-        self.linear_tolerances = tolerances;
-        self
-    }
-
-    /// Modifies this motion's linear error tolerance.
-    pub const fn with_linear_error_tolerance(&mut self, tolerance: f64) -> &mut Self {
-        self.linear_tolerances.error_tolerance = Some(tolerance);
-        self
-    }
-
-    /// Removes this motion's linear error tolerance.
-    pub const fn without_linear_error_tolerance(&mut self) -> &mut Self {
-        self.linear_tolerances.error_tolerance = None;
-        self
-    }
-
-    /// Modifies this motion's linear velocity tolerance.
-    pub const fn with_linear_velocity_tolerance(&mut self, tolerance: f64) -> &mut Self {
-        self.linear_tolerances.velocity_tolerance = Some(tolerance);
-        self
-    }
-
-    /// Removes this motion's linear velocity tolerance.
-    pub const fn without_linear_velocity_tolerance(&mut self) -> &mut Self {
-        self.linear_tolerances.velocity_tolerance = None;
-        self
-    }
-
-    /// Modifies this motion's linear tolerance duration.
-    pub const fn with_linear_tolerance_duration(&mut self, duration: Duration) -> &mut Self {
-        self.linear_tolerances.duration = Some(duration);
-        self
-    }
-
-    /// Removes this motion's linear tolerance duration.
-    pub const fn without_linear_tolerance_duration(&mut self) -> &mut Self {
-        self.linear_tolerances.duration = None;
-        self
-    }
-
-    /// Removes this motion's linear and angular tolerance durations.
-    pub const fn without_tolerance_duration(&mut self) -> &mut Self {
-        self.linear_tolerances.duration = None;
-        self.angular_tolerances.duration = None;
-        self
-    }
-
-    /// Modifies this motion's angular tolerances.
-    pub const fn with_angular_tolerances(&mut self, tolerances: Tolerances) -> &mut Self {
-        self.angular_tolerances = tolerances;
-        self
-    }
-
-    /// Modifies this motion's angular error tolerance.
-    pub const fn with_angular_error_tolerance(&mut self, tolerance: f64) -> &mut Self {
-        self.angular_tolerances.error_tolerance = Some(tolerance);
-        self
-    }
-
-    /// Removes this motion's angular error tolerance.
-    pub const fn without_angular_error_tolerance(&mut self) -> &mut Self {
-        self.angular_tolerances.error_tolerance = None;
-        self
-    }
-
-    /// Modifies this motion's angular velocity tolerance.
-    pub const fn with_angular_velocity_tolerance(&mut self, tolerance: f64) -> &mut Self {
-        self.angular_tolerances.velocity_tolerance = Some(tolerance);
-        self
-    }
-
-    /// Removes this motion's angular velocity tolerance.
-    pub const fn without_angular_velocity_tolerance(&mut self) -> &mut Self {
-        self.angular_tolerances.velocity_tolerance = None;
-        self
-    }
+    motion_modifiers!();
 
-    /// Modifies this motion's angular tolerance duration.
-    pub const fn with_angular_tolerance_duration(&mut self, duration: Duration) -> &mut Self {
-        self.angular_tolerances.duration = Some(duration);
-        self
-    }
-
-    /// Removes this motion's angular tolerance duration.
-    pub const fn without_angular_tolerance_duration(&mut self) -> &mut Self {
-        self.angular_tolerances.duration = None;
-        self
-    }
+    drive_modifiers!(L, A);
 }
 
 // MARK: Linear PID Modifiers
@@ -272,54 +166,7 @@ where
     A: Feedback<State = Angle, Signal = f64> + Unpin,
     T: TracksPosition + TracksForwardTravel + TracksHeading + TracksVelocity,
 {
-    /// Modifies this motion's linear PID gains.
-    pub const fn with_linear_gains(&mut self, kp: f64, ki: f64, kd: f64) -> &mut Self {
-        self.linear_controller.set_gains(kp, ki, kd);
-        self
-    }
-
-    /// Modifies this motion's linear proportional gain (`kp`).
-    pub const fn with_linear_kp(&mut self, kp: f64) -> &mut Self {
-        self.linear_controller.set_kp(kp);
-        self
-    }
-
-    /// Modifies this motion's linear integral gain (`ki`).
-    pub const fn with_linear_ki(&mut self, ki: f64) -> &mut Self {
-        self.linear_controller.set_ki(ki);
-        self
-    }
-
-    /// Modifies this motion's linear derivative gain (`kd`).
-    pub const fn with_linear_kd(&mut self, kd: f64) -> &mut Self {
-        self.linear_controller.set_kd(kd);
-        self
-    }
-
-    /// Modifies this motion's linear integration range.
-    pub const fn with_linear_integration_range(&mut self, integration_range: f64) -> &mut Self {
-        self.linear_controller
-            .set_integration_range(Some(integration_range));
-        self
-    }
-
-    /// Removes this motion's linear integration range.
-    pub const fn without_linear_integration_range(&mut self) -> &mut Self {
-        self.linear_controller.set_integration_range(None);
-        self
-    }
-
-    /// Modifies this motion's linear output limit.
-    pub const fn with_linear_output_limit(&mut self, limit: f64) -> &mut Self {
-        self.linear_controller.set_output_limit(Some(limit));
-        self
-    }
-
-    /// Removes this motion's linear output limit.
-    pub const fn without_linear_output_limit(&mut self) -> &mut Self {
-        self.linear_controller.set_output_limit(None);
-        self
-    }
+    linear_pid_modifiers!();
 }
 
 // MARK: Angular PID Modifiers
@@ -330,52 +177,5 @@ where
     L: Feedback<State = f64, Signal = f64> + Unpin,
     T: TracksPosition + TracksForwardTravel + TracksHeading + TracksVelocity,
 {
-    /// Modifies this motion's angular PID gains.
-    pub const fn with_angular_gains(&mut self, kp: f64, ki: f64, kd: f64) -> &mut Self {
-        self.angular_controller.set_gains(kp, ki, kd);
-        self
-    }
-
-    /// Modifies this motion's angular proportional gain (`kp`).
-    pub const fn with_angular_kp(&mut self, kp: f64) -> &mut Self {
-        self.angular_controller.set_kp(kp);
-        self
-    }
-
-    /// Modifies this motion's angular integral gain (`ki`).
-    pub const fn with_angular_ki(&mut self, ki: f64) -> &mut Self {
-        self.angular_controller.set_ki(ki);
-        self
-    }
-
-    /// Modifies this motion's angular derivative gain (`kd`).
-    pub const fn with_angular_kd(&mut self, kd: f64) -> &mut Self {
-        self.angular_controller.set_kd(kd);
-        self
-    }
-
-    /// Modifies this motion's angular integration range.
-    pub const fn with_angular_integration_range(&mut self, integration_range: Angle) -> &mut Self {
-        self.angular_controller
-            .set_integration_range(Some(integration_range));
-        self
-    }
-
-    /// Modifies this motion's angular output limit.
-    pub const fn with_angular_output_limit(&mut self, limit: f64) -> &mut Self {
-        self.angular_controller.set_output_limit(Some(limit));
-        self
-    }
-
-    /// Removes this motion's angular integration range.
-    pub const fn without_angular_integration_range(&mut self) -> &mut Self {
-        self.angular_controller.set_integration_range(None);
-        self
-    }
-
-    /// Removes this motion's angular output limit.
-    pub const fn without_angular_output_limit(&mut self) -> &mut Self {
-        self.angular_controller.set_output_limit(None);
-        self
-    }
+    angular_pid_modifiers!();
 }
@@ -0,0 +1,178 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use crate::motion::{
+    CancelToken, MotionResult,
+    future::{
+        self, DriveState, angular_pid_modifiers, drive_modifiers, linear_pid_modifiers,
+        motion_modifiers,
+    },
+    report::AutonReport,
+};
+
+use evian::{
+    control::{
+        Tolerances,
+        loops::{AngularPid, Feedback, Pid},
+    },
+    drivetrain::{Drivetrain, model::Arcade},
+    math::Angle,
+    tracking::{TracksForwardTravel, TracksHeading, TracksVelocity},
+};
+
+/// Drives the robot forward or backwards for a distance at a given heading.
+///
+/// Unlike [`Basic::drive_distance_at_heading`], this resolves to a
+/// [`MotionResult`] and can be recorded into an [`AutonReport`].
+///
+/// [`Basic::drive_distance_at_heading`]: evian::motion::Basic::drive_distance_at_heading
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct DriveAtHeadingFuture<'a, M, L, A, T>
+where
+    M: Arcade,
+    L: Feedback<State = f64, Signal = f64> + Unpin,
+    A: Feedback<State = Angle, Signal = f64> + Unpin,
+    T: TracksForwardTravel + TracksHeading + TracksVelocity,
+{
+    pub(crate) target_distance: f64,
+    pub(crate) target_heading: Angle,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) linear_tolerances: Tolerances,
+    pub(crate) angular_tolerances: Tolerances,
+    pub(crate) linear_controller: L,
+    pub(crate) angular_controller: A,
+    pub(crate) drivetrain: &'a mut Drivetrain<M, T>,
+
+    /// Token that stops this motion early when cancelled.
+    pub(crate) cancel: Option<CancelToken>,
+
+    /// Report to record this motion's outcome into.
+    pub(crate) report: Option<(AutonReport, &'static str)>,
+
+    /// Forward travel when the motion started, captured on the first poll.
+    pub(crate) start_travel: Option<f64>,
+
+    /// Internal future state ("local variables").
+    pub(crate) state: Option<DriveState>,
+}
+
+// MARK: Future Poll
+
+impl<M, L, A, T> Future for DriveAtHeadingFuture<'_, M, L, A, T>
+where
+    M: Arcade,
+    L: Feedback<State = f64, Signal = f64> + Unpin,
+    A: Feedback<State = Angle, Signal = f64> + Unpin,
+    T: TracksForwardTravel + TracksHeading + TracksVelocity,
+{
+    type Output = MotionResult;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let state = this.state.get_or_insert_with(DriveState::new);
+
+        if Pin::new(&mut state.sleep).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+
+        let dt = state.prev_time.elapsed();
+
+        let heading = this.drivetrain.tracking.heading();
+        let forward_travel = this.drivetrain.tracking.forward_travel();
+        let travelled = forward_travel - *this.start_travel.get_or_insert(forward_travel);
+
+        let linear_error = this.target_distance - travelled;
+        let angular_error = (this.target_heading - heading).wrapped_half();
+
+        if this
+            .linear_tolerances
+            .check(linear_error, this.drivetrain.tracking.linear_velocity())
+        {
+            state.linear_settled = true;
+        }
+        if this.angular_tolerances.check(
+            angular_error.as_radians(),
+            this.drivetrain.tracking.angular_velocity(),
+        ) {
+            state.angular_settled = true;
+        }
+
+        let outcome = future::outcome(
+            state.linear_settled && state.angular_settled,
+            this.cancel.as_ref(),
+            this.timeout,
+            state.start_time,
+        );
+
+        if let Some(outcome) = outcome {
+            drop(this.drivetrain.model.drive_arcade(0.0, 0.0));
+
+            let result = MotionResult {
+                outcome,
+                elapsed: state.start_time.elapsed(),
+                linear_error,
+                angular_error,
+            };
+            return Poll::Ready(future::record(&mut this.report, result));
+        }
+
+        let linear_output = this
+            .linear_controller
+            .update(travelled, this.target_distance, dt);
+
+        let angular_output = this
+            .angular_controller
+            .update(heading, this.target_heading, dt);
+
+        drop(
+            this.drivetrain
+                .model
+                .drive_arcade(linear_output, angular_output),
+        );
+
+        state.reset_sleep();
+
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+// MARK: Generic Modifiers
+
+impl<M, L, A, T> DriveAtHeadingFuture<'_, M, L, A, T>
+where
+    M: Arcade,
+    L: Feedback<State = f64, Signal = f64> + Unpin,
+    A: Feedback<State = Angle, Signal = f64> + Unpin,
+    T: TracksForwardTravel + TracksHeading + TracksVelocity,
+{
+    motion_modifiers!();
+
+    drive_modifiers!(L, A);
+}
+
+// MARK: Linear PID Modifiers
+
+impl<M, A, T> DriveAtHeadingFuture<'_, M, Pid, A, T>
+where
+    M: Arcade,
+    A: Feedback<State = Angle, Signal = f64> + Unpin,
+    T: TracksForwardTravel + TracksHeading + TracksVelocity,
+{
+    linear_pid_modifiers!();
+}
+
+// MARK: Angular PID Modifiers
+
+impl<M, L, T> DriveAtHeadingFuture<'_, M, L, AngularPid, T>
+where
+    M: Arcade,
+    L: Feedback<State = f64, Signal = f64> + Unpin,
+    T: TracksForwardTravel + TracksHeading + TracksVelocity,
+{
+    angular_pid_modifiers!();
+}
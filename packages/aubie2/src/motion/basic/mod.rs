@@ -1,21 +1,54 @@
 use evian::{
     control::loops::Feedback,
-    math::Angle,
+    drivetrain::model::Tank,
+    math::{Angle, Vec2},
     motion::Basic,
     prelude::{
         Arcade, Drivetrain, TracksForwardTravel, TracksHeading, TracksPosition, TracksVelocity,
     },
 };
 
-use crate::motion::basic::cartesian_drive::CartesianDriveFuture;
+pub use crate::motion::basic::swing::SwingSide;
+use crate::motion::basic::{
+    arc::ArcFuture, boomerang::BoomerangFuture, cartesian_drive::CartesianDriveFuture,
+    drive_at_heading::DriveAtHeadingFuture, swing::SwingFuture, turn::TurnFuture,
+};
 
+pub(crate) mod arc;
+pub(crate) mod boomerang;
 pub(crate) mod cartesian_drive;
+pub(crate) mod drive_at_heading;
+pub(crate) mod swing;
+pub(crate) mod turn;
 
 pub trait BasicExt<
     L: Feedback<State = f64, Signal = f64> + Unpin + Clone,
     A: Feedback<State = Angle, Signal = f64> + Unpin + Clone,
 >
 {
+    /// Drives a distance while holding a heading.
+    ///
+    /// Behaves like [`Basic::drive_distance_at_heading`], but resolves to a
+    /// [`MotionResult`](crate::motion::MotionResult) that can be recorded with
+    /// `with_report`.
+    fn drive_at_heading<'a, M: Arcade, T: TracksForwardTravel + TracksHeading + TracksVelocity>(
+        &mut self,
+        drivetrain: &'a mut Drivetrain<M, T>,
+        distance: f64,
+        heading: Angle,
+    ) -> DriveAtHeadingFuture<'a, M, L, A, T>;
+
+    /// Turns in place to a heading.
+    ///
+    /// Behaves like [`Basic::turn_to_heading`], but resolves to a
+    /// [`MotionResult`](crate::motion::MotionResult) that can be recorded with
+    /// `with_report`.
+    fn turn_to<'a, M: Arcade, T: TracksHeading + TracksVelocity>(
+        &mut self,
+        drivetrain: &'a mut Drivetrain<M, T>,
+        heading: Angle,
+    ) -> TurnFuture<'a, M, A, T>;
+
     fn drive_to_x<
         'a,
         M: Arcade,
@@ -37,6 +70,45 @@ pub trait BasicExt<
         y: f64,
         heading: Angle,
     ) -> CartesianDriveFuture<'a, M, L, A, T>;
+
+    /// Turns to a heading by driving one side of the drivetrain while the
+    /// `locked_side` is held still.
+    ///
+    /// The locked side's motors must be set to
+    /// [`BrakeMode::Hold`](vexide::smart::motor::BrakeMode::Hold) beforehand,
+    /// since it's only commanded 0 V.
+    fn swing_to_heading<'a, M: Tank, T: TracksHeading + TracksVelocity>(
+        &mut self,
+        drivetrain: &'a mut Drivetrain<M, T>,
+        heading: Angle,
+        locked_side: SwingSide,
+    ) -> SwingFuture<'a, M, A, T>;
+
+    /// Drives along an arc of the given radius in inches until reaching a
+    /// heading, backwards if the radius is negative.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radius` is zero or not finite. Use
+    /// [`Basic::turn_to_heading`] to turn in place.
+    fn arc_to_heading<'a, M: Arcade, T: TracksForwardTravel + TracksHeading + TracksVelocity>(
+        &mut self,
+        drivetrain: &'a mut Drivetrain<M, T>,
+        heading: Angle,
+        radius: f64,
+    ) -> ArcFuture<'a, M, L, A, T>;
+
+    /// Drives to a point, arriving at a heading, by chasing a carrot point
+    /// placed behind the target.
+    ///
+    /// The motion only settles within its settle distance of the target, once
+    /// the straight-line distance to it is within the linear tolerances.
+    fn move_to_pose<'a, M: Arcade, T: TracksPosition + TracksHeading + TracksVelocity>(
+        &mut self,
+        drivetrain: &'a mut Drivetrain<M, T>,
+        point: impl Into<Vec2<f64>>,
+        heading: Angle,
+    ) -> BoomerangFuture<'a, M, L, A, T>;
 }
 
 impl<
@@ -44,6 +116,45 @@ impl<
     A: Feedback<State = Angle, Signal = f64> + Unpin + Clone,
 > BasicExt<L, A> for Basic<L, A>
 {
+    fn drive_at_heading<'a, M: Arcade, T: TracksForwardTravel + TracksHeading + TracksVelocity>(
+        &mut self,
+        drivetrain: &'a mut Drivetrain<M, T>,
+        distance: f64,
+        heading: Angle,
+    ) -> DriveAtHeadingFuture<'a, M, L, A, T> {
+        DriveAtHeadingFuture {
+            target_distance: distance,
+            target_heading: heading,
+            timeout: self.timeout,
+            linear_tolerances: self.linear_tolerances,
+            angular_tolerances: self.angular_tolerances,
+            linear_controller: self.linear_controller.clone(),
+            angular_controller: self.angular_controller.clone(),
+            drivetrain,
+            cancel: None,
+            report: None,
+            start_travel: None,
+            state: None,
+        }
+    }
+
+    fn turn_to<'a, M: Arcade, T: TracksHeading + TracksVelocity>(
+        &mut self,
+        drivetrain: &'a mut Drivetrain<M, T>,
+        heading: Angle,
+    ) -> TurnFuture<'a, M, A, T> {
+        TurnFuture {
+            target_heading: heading,
+            timeout: self.timeout,
+            angular_tolerances: self.angular_tolerances,
+            angular_controller: self.angular_controller.clone(),
+            drivetrain,
+            cancel: None,
+            report: None,
+            state: None,
+        }
+    }
+
     fn drive_to_x<
         'a,
         M: Arcade,
@@ -64,6 +175,8 @@ impl<
             linear_controller: self.linear_controller.clone(),
             angular_controller: self.angular_controller.clone(),
             drivetrain,
+            cancel: None,
+            report: None,
             state: None,
         }
     }
@@ -88,6 +201,79 @@ impl<
             linear_controller: self.linear_controller.clone(),
             angular_controller: self.angular_controller.clone(),
             drivetrain,
+            cancel: None,
+            report: None,
+            state: None,
+        }
+    }
+
+    fn swing_to_heading<'a, M: Tank, T: TracksHeading + TracksVelocity>(
+        &mut self,
+        drivetrain: &'a mut Drivetrain<M, T>,
+        heading: Angle,
+        locked_side: SwingSide,
+    ) -> SwingFuture<'a, M, A, T> {
+        SwingFuture {
+            target_heading: heading,
+            locked_side,
+            timeout: self.timeout,
+            angular_tolerances: self.angular_tolerances,
+            angular_controller: self.angular_controller.clone(),
+            drivetrain,
+            cancel: None,
+            report: None,
+            state: None,
+        }
+    }
+
+    fn arc_to_heading<'a, M: Arcade, T: TracksForwardTravel + TracksHeading + TracksVelocity>(
+        &mut self,
+        drivetrain: &'a mut Drivetrain<M, T>,
+        heading: Angle,
+        radius: f64,
+    ) -> ArcFuture<'a, M, L, A, T> {
+        assert!(
+            radius != 0.0 && radius.is_finite(),
+            "An arc needs a nonzero, finite radius."
+        );
+
+        ArcFuture {
+            radius,
+            target_heading: heading,
+            timeout: self.timeout,
+            linear_tolerances: self.linear_tolerances,
+            angular_tolerances: self.angular_tolerances,
+            linear_controller: self.linear_controller.clone(),
+            angular_controller: self.angular_controller.clone(),
+            drivetrain,
+            cancel: None,
+            report: None,
+            start: None,
+            state: None,
+        }
+    }
+
+    fn move_to_pose<'a, M: Arcade, T: TracksPosition + TracksHeading + TracksVelocity>(
+        &mut self,
+        drivetrain: &'a mut Drivetrain<M, T>,
+        point: impl Into<Vec2<f64>>,
+        heading: Angle,
+    ) -> BoomerangFuture<'a, M, L, A, T> {
+        BoomerangFuture {
+            target_point: point.into(),
+            target_heading: heading,
+            lead: 0.6,
+            drift: None,
+            settle_distance: 6.0,
+            reverse: false,
+            timeout: self.timeout,
+            linear_tolerances: self.linear_tolerances,
+            angular_tolerances: self.angular_tolerances,
+            linear_controller: self.linear_controller.clone(),
+            angular_controller: self.angular_controller.clone(),
+            drivetrain,
+            cancel: None,
+            report: None,
             state: None,
         }
     }
@@ -0,0 +1,151 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use crate::motion::{
+    CancelToken, MotionResult,
+    future::{self, DriveState, angular_modifiers, angular_pid_modifiers, motion_modifiers},
+    report::AutonReport,
+};
+
+use evian::{
+    control::{
+        Tolerances,
+        loops::{AngularPid, Feedback},
+    },
+    drivetrain::{Drivetrain, model::Tank},
+    math::Angle,
+    tracking::{TracksHeading, TracksVelocity},
+};
+
+/// The side of the drivetrain held still during a swing turn.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SwingSide {
+    Left,
+    Right,
+}
+
+/// Turns the robot to a heading by driving one side while the other is locked.
+///
+/// The locked side is commanded 0 V, which only holds it still if its motors
+/// are set to [`BrakeMode::Hold`]. With any other brake mode it coasts, and the
+/// robot pivots about a point that drifts during the turn.
+///
+/// [`BrakeMode::Hold`]: vexide::smart::motor::BrakeMode::Hold
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SwingFuture<'a, M, A, T>
+where
+    M: Tank,
+    A: Feedback<State = Angle, Signal = f64> + Unpin,
+    T: TracksHeading + TracksVelocity,
+{
+    pub(crate) target_heading: Angle,
+    pub(crate) locked_side: SwingSide,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) angular_tolerances: Tolerances,
+    pub(crate) angular_controller: A,
+    pub(crate) drivetrain: &'a mut Drivetrain<M, T>,
+
+    /// Token that stops this motion early when cancelled.
+    pub(crate) cancel: Option<CancelToken>,
+
+    /// Report to record this motion's outcome into.
+    pub(crate) report: Option<(AutonReport, &'static str)>,
+
+    /// Internal future state ("local variables").
+    pub(crate) state: Option<DriveState>,
+}
+
+// MARK: Future Poll
+
+impl<M, A, T> Future for SwingFuture<'_, M, A, T>
+where
+    M: Tank,
+    A: Feedback<State = Angle, Signal = f64> + Unpin,
+    T: TracksHeading + TracksVelocity,
+{
+    type Output = MotionResult;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let state = this.state.get_or_insert_with(DriveState::new);
+
+        if Pin::new(&mut state.sleep).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+
+        let dt = state.prev_time.elapsed();
+
+        let heading = this.drivetrain.tracking.heading();
+        let angular_error = (this.target_heading - heading).wrapped_half();
+
+        if this.angular_tolerances.check(
+            angular_error.as_radians(),
+            this.drivetrain.tracking.angular_velocity(),
+        ) {
+            state.angular_settled = true;
+        }
+
+        let outcome = future::outcome(
+            state.angular_settled,
+            this.cancel.as_ref(),
+            this.timeout,
+            state.start_time,
+        );
+
+        if let Some(outcome) = outcome {
+            drop(this.drivetrain.model.drive_tank(0.0, 0.0));
+
+            let result = MotionResult {
+                outcome,
+                elapsed: state.start_time.elapsed(),
+                linear_error: f64::NAN,
+                angular_error,
+            };
+            return Poll::Ready(future::record(&mut this.report, result));
+        }
+
+        let angular_output = this
+            .angular_controller
+            .update(heading, this.target_heading, dt);
+
+        // Counterclockwise output drives the right side forward or the left
+        // side backward, whichever one is free.
+        let (left, right) = match this.locked_side {
+            SwingSide::Left => (0.0, angular_output),
+            SwingSide::Right => (-angular_output, 0.0),
+        };
+        drop(this.drivetrain.model.drive_tank(left, right));
+
+        state.reset_sleep();
+
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+// MARK: Generic Modifiers
+
+impl<M, A, T> SwingFuture<'_, M, A, T>
+where
+    M: Tank,
+    A: Feedback<State = Angle, Signal = f64> + Unpin,
+    T: TracksHeading + TracksVelocity,
+{
+    motion_modifiers!();
+
+    angular_modifiers!(A);
+}
+
+// MARK: Angular PID Modifiers
+
+impl<M, T> SwingFuture<'_, M, AngularPid, T>
+where
+    M: Tank,
+    T: TracksHeading + TracksVelocity,
+{
+    angular_pid_modifiers!();
+}
@@ -2,26 +2,37 @@ use std::{
     future::Future,
     pin::Pin,
     task::{Context, Poll},
-    time::{Duration, Instant},
+    time::Duration,
 };
 
 use evian::{
     control::loops::{AngularPid, Feedback, Pid},
     math::Angle,
-    prelude::{Arcade, Drivetrain, Tolerances, TracksHeading, TracksPosition, TracksVelocity},
+    prelude::{Arcade, Drivetrain, Tolerances, TracksHeading, TracksVelocity},
 };
-use vexide::{prelude::DistanceSensor, time::sleep};
 
-use crate::motion::basic::cartesian_drive::DriveState;
+use crate::{
+    motion::{
+        CancelToken, MotionOutcome, MotionResult,
+        distance_sensor::ObjectDistance,
+        future::{
+            self, DriveState, angular_pid_modifiers, drive_modifiers, linear_pid_modifiers,
+            motion_modifiers,
+        },
+        report::AutonReport,
+    },
+    time::Instant,
+};
 
 /// Drives the robot forward or backwards for a distance at a given heading.
 #[must_use = "futures do nothing unless you `.await` or poll them"]
-pub struct DistanceDriveFuture<'a, M, L, A, T>
+pub struct DistanceDriveFuture<'a, M, L, A, T, S>
 where
     M: Arcade,
     L: Feedback<State = f64, Signal = f64> + Unpin,
     A: Feedback<State = Angle, Signal = f64> + Unpin,
     T: TracksHeading + TracksVelocity,
+    S: ObjectDistance,
 {
     pub(crate) target_distance: f64,
     pub(crate) target_heading: Angle,
@@ -31,87 +42,118 @@ where
     pub(crate) linear_controller: L,
     pub(crate) angular_controller: A,
     pub(crate) drivetrain: &'a mut Drivetrain<M, T>,
-    pub(crate) sensor: &'a DistanceSensor,
+    pub(crate) sensor: &'a S,
+
+    /// Maximum time without a sensor reading before the motion gives up, or
+    /// `None` to wait for a reading indefinitely.
+    pub(crate) sensor_timeout: Option<Duration>,
+
+    /// Token that stops this motion early when cancelled.
+    pub(crate) cancel: Option<CancelToken>,
+
+    /// Report to record this motion's outcome into.
+    pub(crate) report: Option<(AutonReport, &'static str)>,
 
     /// Internal future state ("local variables").
-    pub(crate) state: Option<DriveState>,
+    pub(crate) state: Option<DistanceDriveState>,
+}
+
+pub(crate) struct DistanceDriveState {
+    drive: DriveState,
+    last_measurement: Instant,
+
+    /// Error from the latest sensor reading, or `NaN` before the first one.
+    linear_error: f64,
 }
 
 // MARK: Future Poll
 
-impl<M, L, A, T> Future for DistanceDriveFuture<'_, M, L, A, T>
+impl<M, L, A, T, S> Future for DistanceDriveFuture<'_, M, L, A, T, S>
 where
     M: Arcade,
     L: Feedback<State = f64, Signal = f64> + Unpin,
     A: Feedback<State = Angle, Signal = f64> + Unpin,
-    T: TracksPosition + TracksHeading + TracksVelocity,
+    T: TracksHeading + TracksVelocity,
+    S: ObjectDistance,
 {
-    type Output = ();
+    type Output = MotionResult;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.get_mut();
-        let state = this.state.get_or_insert_with(|| {
-            let now = Instant::now();
-            DriveState {
-                sleep: sleep(Duration::from_millis(5)),
-                start_time: now,
-                prev_time: now,
-                linear_settled: false,
-                angular_settled: false,
-            }
+        let state = this.state.get_or_insert_with(|| DistanceDriveState {
+            drive: DriveState::new(),
+            last_measurement: Instant::now(),
+            linear_error: f64::NAN,
         });
 
-        if Pin::new(&mut state.sleep).poll(cx).is_pending() {
+        if Pin::new(&mut state.drive.sleep).poll(cx).is_pending() {
             return Poll::Pending;
         }
 
-        let dt = state.prev_time.elapsed();
+        let dt = state.drive.prev_time.elapsed();
 
         let heading = this.drivetrain.tracking.heading();
+        let angular_error = (this.target_heading - heading).wrapped_half();
+        let current_distance = this.sensor.object_distance();
 
-        if let Ok(Some(object)) = this.sensor.object() {
-            let current_distance = (object.distance as f64) / 25.4;
-            let linear_error = this.target_distance - current_distance;
-            let angular_error = (this.target_heading - heading).wrapped_half();
+        if let Some(current_distance) = current_distance {
+            state.last_measurement = Instant::now();
+            state.linear_error = this.target_distance - current_distance;
 
-            if this
-                .linear_tolerances
-                .check(linear_error, this.drivetrain.tracking.linear_velocity())
-            {
-                state.linear_settled = true;
-            }
-            if this.angular_tolerances.check(
-                angular_error.as_radians(),
-                this.drivetrain.tracking.angular_velocity(),
+            if this.linear_tolerances.check(
+                state.linear_error,
+                this.drivetrain.tracking.linear_velocity(),
             ) {
-                state.angular_settled = true;
+                state.drive.linear_settled = true;
             }
+        }
+        if this.angular_tolerances.check(
+            angular_error.as_radians(),
+            this.drivetrain.tracking.angular_velocity(),
+        ) {
+            state.drive.angular_settled = true;
+        }
 
-            if (state.linear_settled && state.angular_settled)
-                || this
-                    .timeout
-                    .is_some_and(|timeout| state.start_time.elapsed() > timeout)
-            {
-                drop(this.drivetrain.model.drive_arcade(0.0, 0.0));
-                return Poll::Ready(());
-            }
+        let outcome = future::outcome(
+            state.drive.linear_settled && state.drive.angular_settled,
+            this.cancel.as_ref(),
+            this.timeout,
+            state.drive.start_time,
+        )
+        .or_else(|| {
+            this.sensor_timeout
+                .is_some_and(|timeout| state.last_measurement.elapsed() > timeout)
+                .then_some(MotionOutcome::SensorLost)
+        });
+
+        if let Some(outcome) = outcome {
+            drop(this.drivetrain.model.drive_arcade(0.0, 0.0));
 
-            let linear_output =
-                this.linear_controller
-                    .update(current_distance, this.target_distance, dt);
-            let angular_output = this
-                .angular_controller
-                .update(heading, this.target_heading, dt);
-
-            drop(
-                this.drivetrain
-                    .model
-                    .drive_arcade(linear_output, angular_output),
-            );
+            let result = MotionResult {
+                outcome,
+                elapsed: state.drive.start_time.elapsed(),
+                linear_error: state.linear_error,
+                angular_error,
+            };
+            return Poll::Ready(future::record(&mut this.report, result));
         }
 
-        state.sleep = sleep(Duration::from_millis(5));
-        state.prev_time = Instant::now();
+        // Hold heading without driving forwards while the sensor has no reading.
+        let linear_output = current_distance.map_or(0.0, |current_distance| {
+            this.linear_controller
+                .update(current_distance, this.target_distance, dt)
+        });
+        let angular_output = this
+            .angular_controller
+            .update(heading, this.target_heading, dt);
+
+        drop(
+            this.drivetrain
+                .model
+                .drive_arcade(linear_output, angular_output),
+        );
+
+        state.drive.reset_sleep();
 
         cx.waker().wake_by_ref();
         Poll::Pending
@@ -120,241 +162,54 @@ where
 
 // MARK: Generic Modifiers
 
-impl<M, L, A, T> DistanceDriveFuture<'_, M, L, A, T>
+impl<M, L, A, T, S> DistanceDriveFuture<'_, M, L, A, T, S>
 where
     M: Arcade,
     L: Feedback<State = f64, Signal = f64> + Unpin,
     A: Feedback<State = Angle, Signal = f64> + Unpin,
     T: TracksHeading + TracksVelocity,
+    S: ObjectDistance,
 {
-    /// Modifies this motion's linear feedback controller.
-    pub fn with_linear_controller(&mut self, controller: L) -> &mut Self {
-        self.linear_controller = controller;
-        self
-    }
-
-    /// Modifies this motion's angular feedback controller.
-    pub fn with_angular_controller(&mut self, controller: A) -> &mut Self {
-        self.angular_controller = controller;
-        self
-    }
-
-    /// Modifies this motion's timeout duration.
-    pub const fn with_timeout(&mut self, timeout: Duration) -> &mut Self {
-        self.timeout = Some(timeout);
+    /// Modifies how long the motion can go without a sensor reading before
+    /// resolving with [`MotionOutcome::SensorLost`].
+    ///
+    /// Motions have no sensor timeout unless one is set here.
+    pub const fn with_sensor_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.sensor_timeout = Some(timeout);
         self
     }
 
-    /// Removes this motion's timeout duration.
-    pub const fn without_timeout(&mut self) -> &mut Self {
-        self.timeout = None;
+    /// Allows the motion to wait indefinitely for a sensor reading.
+    pub const fn without_sensor_timeout(&mut self) -> &mut Self {
+        self.sensor_timeout = None;
         self
     }
 
-    /// Modifies this motion's linear tolerances.
-    pub const fn with_linear_tolerances(&mut self, tolerances: Tolerances) -> &mut Self {
-        self.linear_tolerances = tolerances;
-        self
-    }
-
-    /// Modifies this motion's linear error tolerance.
-    pub const fn with_linear_error_tolerance(&mut self, tolerance: f64) -> &mut Self {
-        self.linear_tolerances.error_tolerance = Some(tolerance);
-        self
-    }
+    motion_modifiers!();
 
-    /// Removes this motion's linear error tolerance.
-    pub const fn without_linear_error_tolerance(&mut self) -> &mut Self {
-        self.linear_tolerances.error_tolerance = None;
-        self
-    }
-
-    /// Modifies this motion's linear velocity tolerance.
-    pub const fn with_linear_velocity_tolerance(&mut self, tolerance: f64) -> &mut Self {
-        self.linear_tolerances.velocity_tolerance = Some(tolerance);
-        self
-    }
-
-    /// Removes this motion's linear velocity tolerance.
-    pub const fn without_linear_velocity_tolerance(&mut self) -> &mut Self {
-        self.linear_tolerances.velocity_tolerance = None;
-        self
-    }
-
-    /// Modifies this motion's linear tolerance duration.
-    pub const fn with_linear_tolerance_duration(&mut self, duration: Duration) -> &mut Self {
-        self.linear_tolerances.duration = Some(duration);
-        self
-    }
-
-    /// Removes this motion's linear tolerance duration.
-    pub const fn without_linear_tolerance_duration(&mut self) -> &mut Self {
-        self.linear_tolerances.duration = None;
-        self
-    }
-
-    /// Removes this motion's linear and angular tolerance durations.
-    pub const fn without_tolerance_duration(&mut self) -> &mut Self {
-        self.linear_tolerances.duration = None;
-        self.angular_tolerances.duration = None;
-        self
-    }
-
-    /// Modifies this motion's angular tolerances.
-    pub const fn with_angular_tolerances(&mut self, tolerances: Tolerances) -> &mut Self {
-        self.angular_tolerances = tolerances;
-        self
-    }
-
-    /// Modifies this motion's angular error tolerance.
-    pub const fn with_angular_error_tolerance(&mut self, tolerance: f64) -> &mut Self {
-        self.angular_tolerances.error_tolerance = Some(tolerance);
-        self
-    }
-
-    /// Removes this motion's angular error tolerance.
-    pub const fn without_angular_error_tolerance(&mut self) -> &mut Self {
-        self.angular_tolerances.error_tolerance = None;
-        self
-    }
-
-    /// Modifies this motion's angular velocity tolerance.
-    pub const fn with_angular_velocity_tolerance(&mut self, tolerance: f64) -> &mut Self {
-        self.angular_tolerances.velocity_tolerance = Some(tolerance);
-        self
-    }
-
-    /// Removes this motion's angular velocity tolerance.
-    pub const fn without_angular_velocity_tolerance(&mut self) -> &mut Self {
-        self.angular_tolerances.velocity_tolerance = None;
-        self
-    }
-
-    /// Modifies this motion's angular tolerance duration.
-    pub const fn with_angular_tolerance_duration(&mut self, duration: Duration) -> &mut Self {
-        self.angular_tolerances.duration = Some(duration);
-        self
-    }
-
-    /// Removes this motion's angular tolerance duration.
-    pub const fn without_angular_tolerance_duration(&mut self) -> &mut Self {
-        self.angular_tolerances.duration = None;
-        self
-    }
+    drive_modifiers!(L, A);
 }
 
 // MARK: Linear PID Modifiers
 
-impl<M, A, T> DistanceDriveFuture<'_, M, Pid, A, T>
+impl<M, A, T, S> DistanceDriveFuture<'_, M, Pid, A, T, S>
 where
     M: Arcade,
     A: Feedback<State = Angle, Signal = f64> + Unpin,
     T: TracksHeading + TracksVelocity,
+    S: ObjectDistance,
 {
-    /// Modifies this motion's linear PID gains.
-    pub const fn with_linear_gains(&mut self, kp: f64, ki: f64, kd: f64) -> &mut Self {
-        self.linear_controller.set_gains(kp, ki, kd);
-        self
-    }
-
-    /// Modifies this motion's linear proportional gain (`kp`).
-    pub const fn with_linear_kp(&mut self, kp: f64) -> &mut Self {
-        self.linear_controller.set_kp(kp);
-        self
-    }
-
-    /// Modifies this motion's linear integral gain (`ki`).
-    pub const fn with_linear_ki(&mut self, ki: f64) -> &mut Self {
-        self.linear_controller.set_ki(ki);
-        self
-    }
-
-    /// Modifies this motion's linear derivative gain (`kd`).
-    pub const fn with_linear_kd(&mut self, kd: f64) -> &mut Self {
-        self.linear_controller.set_kd(kd);
-        self
-    }
-
-    /// Modifies this motion's linear integration range.
-    pub const fn with_linear_integration_range(&mut self, integration_range: f64) -> &mut Self {
-        self.linear_controller
-            .set_integration_range(Some(integration_range));
-        self
-    }
-
-    /// Removes this motion's linear integration range.
-    pub const fn without_linear_integration_range(&mut self) -> &mut Self {
-        self.linear_controller.set_integration_range(None);
-        self
-    }
-
-    /// Modifies this motion's linear output limit.
-    pub const fn with_linear_output_limit(&mut self, limit: f64) -> &mut Self {
-        self.linear_controller.set_output_limit(Some(limit));
-        self
-    }
-
-    /// Removes this motion's linear output limit.
-    pub const fn without_linear_output_limit(&mut self) -> &mut Self {
-        self.linear_controller.set_output_limit(None);
-        self
-    }
+    linear_pid_modifiers!();
 }
 
 // MARK: Angular PID Modifiers
 
-impl<M, L, T> DistanceDriveFuture<'_, M, L, AngularPid, T>
+impl<M, L, T, S> DistanceDriveFuture<'_, M, L, AngularPid, T, S>
 where
     M: Arcade,
     L: Feedback<State = f64, Signal = f64> + Unpin,
     T: TracksHeading + TracksVelocity,
+    S: ObjectDistance,
 {
-    /// Modifies this motion's angular PID gains.
-    pub const fn with_angular_gains(&mut self, kp: f64, ki: f64, kd: f64) -> &mut Self {
-        self.angular_controller.set_gains(kp, ki, kd);
-        self
-    }
-
-    /// Modifies this motion's angular proportional gain (`kp`).
-    pub const fn with_angular_kp(&mut self, kp: f64) -> &mut Self {
-        self.angular_controller.set_kp(kp);
-        self
-    }
-
-    /// Modifies this motion's angular integral gain (`ki`).
-    pub const fn with_angular_ki(&mut self, ki: f64) -> &mut Self {
-        self.angular_controller.set_ki(ki);
-        self
-    }
-
-    /// Modifies this motion's angular derivative gain (`kd`).
-    pub const fn with_angular_kd(&mut self, kd: f64) -> &mut Self {
-        self.angular_controller.set_kd(kd);
-        self
-    }
-
-    /// Modifies this motion's angular integration range.
-    pub const fn with_angular_integration_range(&mut self, integration_range: Angle) -> &mut Self {
-        self.angular_controller
-            .set_integration_range(Some(integration_range));
-        self
-    }
-
-    /// Modifies this motion's angular output limit.
-    pub const fn with_angular_output_limit(&mut self, limit: f64) -> &mut Self {
-        self.angular_controller.set_output_limit(Some(limit));
-        self
-    }
-
-    /// Removes this motion's angular integration range.
-    pub const fn without_angular_integration_range(&mut self) -> &mut Self {
-        self.angular_controller.set_integration_range(None);
-        self
-    }
-
-    /// Removes this motion's angular output limit.
-    pub const fn without_angular_output_limit(&mut self) -> &mut Self {
-        self.angular_controller.set_output_limit(None);
-        self
-    }
+    angular_pid_modifiers!();
 }
@@ -1,4 +1,4 @@
-//! Feedback-driven driving and turning.
+//! Driving to a distance sensor reading.
 
 use std::time::Duration;
 
@@ -9,7 +9,24 @@ use crate::motion::distance_sensor::future::DistanceDriveFuture;
 
 mod future;
 
-/// Feedback-driven driving and turning.
+/// A sensor that can measure the distance to an object in front of it.
+pub trait ObjectDistance {
+    /// Returns the distance to the detected object in inches, or `None` if
+    /// nothing is in range.
+    fn object_distance(&self) -> Option<f64>;
+}
+
+impl ObjectDistance for DistanceSensor {
+    fn object_distance(&self) -> Option<f64> {
+        match self.object() {
+            Ok(Some(object)) => Some((object.distance as f64) / 25.4),
+            _ => None,
+        }
+    }
+}
+
+/// Drives until a distance sensor reads a target distance to an object, such
+/// as a wall or goal, while holding a heading.
 #[derive(PartialEq)]
 pub struct DistanceSensorDriving<L, A>
 where
@@ -37,21 +54,20 @@ where
     L: Feedback<State = f64, Signal = f64> + Unpin + Clone,
     A: Feedback<State = Angle, Signal = f64> + Unpin + Clone,
 {
-    /// Moves the robot forwards by a given distance (measured in wheel units) while
-    /// turning to face a heading.
-    ///
-    /// Negative `target_distance` values will move the robot backwards.
+    /// Drives forwards or backwards until `sensor` reads `target_distance` inches,
+    /// while turning to face a heading.
     pub fn drive_to_distance<
         'a,
         M: Arcade,
         T: TracksHeading + TracksVelocity,
+        S: ObjectDistance,
     >(
         &mut self,
         drivetrain: &'a mut Drivetrain<M, T>,
-        sensor: &'a DistanceSensor,
+        sensor: &'a S,
         target_distance: f64,
         target_heading: Angle,
-    ) -> DistanceDriveFuture<'a, M, L, A, T> {
+    ) -> DistanceDriveFuture<'a, M, L, A, T, S> {
         DistanceDriveFuture {
             target_distance,
             target_heading,
@@ -62,6 +78,9 @@ where
             angular_controller: self.angular_controller.clone(),
             sensor,
             drivetrain,
+            sensor_timeout: None,
+            cancel: None,
+            report: None,
             state: None,
         }
     }
@@ -0,0 +1,447 @@
+//! Pieces shared by this crate's motion futures.
+//!
+//! Every future keeps `timeout`, `cancel` and `report` fields, and the modifier
+//! macros here expect the usual `linear_controller`/`angular_controller` and
+//! `linear_tolerances`/`angular_tolerances` field names.
+
+use std::time::Duration;
+
+use crate::{
+    motion::{
+        CancelToken, MotionOutcome, MotionResult,
+        report::{AutonReport, MotionRecord},
+    },
+    time::{Instant, Sleep, sleep},
+};
+
+/// How often motions update their outputs.
+pub(crate) const UPDATE_INTERVAL: Duration = Duration::from_millis(5);
+
+/// State kept between polls of a motion driving towards a target.
+pub(crate) struct DriveState {
+    pub sleep: Sleep,
+    pub start_time: Instant,
+    pub prev_time: Instant,
+    pub linear_settled: bool,
+    pub angular_settled: bool,
+}
+
+impl DriveState {
+    pub fn new() -> Self {
+        let now = Instant::now();
+
+        Self {
+            sleep: sleep(UPDATE_INTERVAL),
+            start_time: now,
+            prev_time: now,
+            linear_settled: false,
+            angular_settled: false,
+        }
+    }
+
+    /// Starts waiting for the next update.
+    pub fn reset_sleep(&mut self) {
+        self.sleep = sleep(UPDATE_INTERVAL);
+        self.prev_time = Instant::now();
+    }
+}
+
+/// Decides whether a motion is finished.
+///
+/// Settling takes precedence over cancellation, which takes precedence over
+/// timing out.
+pub(crate) fn outcome(
+    settled: bool,
+    cancel: Option<&CancelToken>,
+    timeout: Option<Duration>,
+    start_time: Instant,
+) -> Option<MotionOutcome> {
+    if settled {
+        Some(MotionOutcome::Settled)
+    } else if cancel.is_some_and(CancelToken::is_cancelled) {
+        Some(MotionOutcome::Cancelled)
+    } else if timeout.is_some_and(|timeout| start_time.elapsed() > timeout) {
+        Some(MotionOutcome::TimedOut)
+    } else {
+        None
+    }
+}
+
+/// Records a finished motion into its report, if it has one.
+pub(crate) fn record(
+    report: &mut Option<(AutonReport, &'static str)>,
+    result: MotionResult,
+) -> MotionResult {
+    if let Some((report, name)) = report.take() {
+        report.record(MotionRecord::new(name, result));
+    }
+
+    result
+}
+
+// MARK: Modifier Macros
+
+/// Cancellation, reporting and timeout modifiers.
+macro_rules! motion_modifiers {
+    () => {
+        /// Stops this motion early with [`MotionOutcome::Cancelled`] once `token` is cancelled.
+        ///
+        /// [`MotionOutcome::Cancelled`]: crate::motion::MotionOutcome::Cancelled
+        pub fn with_cancel_token(&mut self, token: &$crate::motion::CancelToken) -> &mut Self {
+            self.cancel = Some(token.clone());
+            self
+        }
+
+        /// Records this motion's outcome into a report under the given name.
+        pub fn with_report(
+            &mut self,
+            report: &$crate::motion::report::AutonReport,
+            name: &'static str,
+        ) -> &mut Self {
+            self.report = Some((report.clone(), name));
+            self
+        }
+
+        /// Modifies this motion's timeout duration.
+        pub const fn with_timeout(&mut self, timeout: ::std::time::Duration) -> &mut Self {
+            self.timeout = Some(timeout);
+            self
+        }
+
+        /// Removes this motion's timeout duration.
+        pub const fn without_timeout(&mut self) -> &mut Self {
+            self.timeout = None;
+            self
+        }
+    };
+}
+
+/// Linear controller and tolerance modifiers, for a linear controller of type `$L`.
+macro_rules! linear_modifiers {
+    ($L:ty) => {
+        /// Modifies this motion's linear feedback controller.
+        pub fn with_linear_controller(&mut self, controller: $L) -> &mut Self {
+            self.linear_controller = controller;
+            self
+        }
+
+        /// Modifies this motion's linear tolerances.
+        pub const fn with_linear_tolerances(
+            &mut self,
+            tolerances: ::evian::control::Tolerances,
+        ) -> &mut Self {
+            self.linear_tolerances = tolerances;
+            self
+        }
+
+        /// Modifies this motion's linear error tolerance.
+        pub const fn with_linear_error_tolerance(&mut self, tolerance: f64) -> &mut Self {
+            self.linear_tolerances.error_tolerance = Some(tolerance);
+            self
+        }
+
+        /// Removes this motion's linear error tolerance.
+        pub const fn without_linear_error_tolerance(&mut self) -> &mut Self {
+            self.linear_tolerances.error_tolerance = None;
+            self
+        }
+
+        /// Modifies this motion's linear velocity tolerance.
+        pub const fn with_linear_velocity_tolerance(&mut self, tolerance: f64) -> &mut Self {
+            self.linear_tolerances.velocity_tolerance = Some(tolerance);
+            self
+        }
+
+        /// Removes this motion's linear velocity tolerance.
+        pub const fn without_linear_velocity_tolerance(&mut self) -> &mut Self {
+            self.linear_tolerances.velocity_tolerance = None;
+            self
+        }
+
+        /// Modifies this motion's linear tolerance duration.
+        pub const fn with_linear_tolerance_duration(
+            &mut self,
+            duration: ::std::time::Duration,
+        ) -> &mut Self {
+            self.linear_tolerances.duration = Some(duration);
+            self
+        }
+
+        /// Removes this motion's linear tolerance duration.
+        pub const fn without_linear_tolerance_duration(&mut self) -> &mut Self {
+            self.linear_tolerances.duration = None;
+            self
+        }
+    };
+}
+
+/// Angular controller and tolerance modifiers, for an angular controller of type `$A`.
+macro_rules! angular_modifiers {
+    ($A:ty) => {
+        /// Modifies this motion's angular feedback controller.
+        pub fn with_angular_controller(&mut self, controller: $A) -> &mut Self {
+            self.angular_controller = controller;
+            self
+        }
+
+        /// Modifies this motion's angular tolerances.
+        pub const fn with_angular_tolerances(
+            &mut self,
+            tolerances: ::evian::control::Tolerances,
+        ) -> &mut Self {
+            self.angular_tolerances = tolerances;
+            self
+        }
+
+        /// Modifies this motion's angular error tolerance.
+        pub const fn with_angular_error_tolerance(&mut self, tolerance: f64) -> &mut Self {
+            self.angular_tolerances.error_tolerance = Some(tolerance);
+            self
+        }
+
+        /// Removes this motion's angular error tolerance.
+        pub const fn without_angular_error_tolerance(&mut self) -> &mut Self {
+            self.angular_tolerances.error_tolerance = None;
+            self
+        }
+
+        /// Modifies this motion's angular velocity tolerance.
+        pub const fn with_angular_velocity_tolerance(&mut self, tolerance: f64) -> &mut Self {
+            self.angular_tolerances.velocity_tolerance = Some(tolerance);
+            self
+        }
+
+        /// Removes this motion's angular velocity tolerance.
+        pub const fn without_angular_velocity_tolerance(&mut self) -> &mut Self {
+            self.angular_tolerances.velocity_tolerance = None;
+            self
+        }
+
+        /// Modifies this motion's angular tolerance duration.
+        pub const fn with_angular_tolerance_duration(
+            &mut self,
+            duration: ::std::time::Duration,
+        ) -> &mut Self {
+            self.angular_tolerances.duration = Some(duration);
+            self
+        }
+
+        /// Removes this motion's angular tolerance duration.
+        pub const fn without_angular_tolerance_duration(&mut self) -> &mut Self {
+            self.angular_tolerances.duration = None;
+            self
+        }
+    };
+}
+
+/// Modifiers for motions with both linear and angular controllers.
+macro_rules! drive_modifiers {
+    ($L:ty, $A:ty) => {
+        $crate::motion::future::linear_modifiers!($L);
+        $crate::motion::future::angular_modifiers!($A);
+
+        /// Removes this motion's linear and angular tolerance durations.
+        pub const fn without_tolerance_duration(&mut self) -> &mut Self {
+            self.linear_tolerances.duration = None;
+            self.angular_tolerances.duration = None;
+            self
+        }
+    };
+}
+
+/// Gain, integration range and output limit modifiers for a linear [`Pid`].
+///
+/// [`Pid`]: evian::control::loops::Pid
+macro_rules! linear_pid_modifiers {
+    () => {
+        /// Modifies this motion's linear PID gains.
+        pub const fn with_linear_gains(&mut self, kp: f64, ki: f64, kd: f64) -> &mut Self {
+            self.linear_controller.set_gains(kp, ki, kd);
+            self
+        }
+
+        /// Modifies this motion's linear proportional gain (`kp`).
+        pub const fn with_linear_kp(&mut self, kp: f64) -> &mut Self {
+            self.linear_controller.set_kp(kp);
+            self
+        }
+
+        /// Modifies this motion's linear integral gain (`ki`).
+        pub const fn with_linear_ki(&mut self, ki: f64) -> &mut Self {
+            self.linear_controller.set_ki(ki);
+            self
+        }
+
+        /// Modifies this motion's linear derivative gain (`kd`).
+        pub const fn with_linear_kd(&mut self, kd: f64) -> &mut Self {
+            self.linear_controller.set_kd(kd);
+            self
+        }
+
+        /// Modifies this motion's linear integration range.
+        pub const fn with_linear_integration_range(&mut self, integration_range: f64) -> &mut Self {
+            self.linear_controller
+                .set_integration_range(Some(integration_range));
+            self
+        }
+
+        /// Removes this motion's linear integration range.
+        pub const fn without_linear_integration_range(&mut self) -> &mut Self {
+            self.linear_controller.set_integration_range(None);
+            self
+        }
+
+        /// Modifies this motion's linear output limit.
+        pub const fn with_linear_output_limit(&mut self, limit: f64) -> &mut Self {
+            self.linear_controller.set_output_limit(Some(limit));
+            self
+        }
+
+        /// Removes this motion's linear output limit.
+        pub const fn without_linear_output_limit(&mut self) -> &mut Self {
+            self.linear_controller.set_output_limit(None);
+            self
+        }
+    };
+}
+
+/// Gain, integration range and output limit modifiers for an [`AngularPid`].
+///
+/// [`AngularPid`]: evian::control::loops::AngularPid
+macro_rules! angular_pid_modifiers {
+    () => {
+        /// Modifies this motion's angular PID gains.
+        pub const fn with_angular_gains(&mut self, kp: f64, ki: f64, kd: f64) -> &mut Self {
+            self.angular_controller.set_gains(kp, ki, kd);
+            self
+        }
+
+        /// Modifies this motion's angular proportional gain (`kp`).
+        pub const fn with_angular_kp(&mut self, kp: f64) -> &mut Self {
+            self.angular_controller.set_kp(kp);
+            self
+        }
+
+        /// Modifies this motion's angular integral gain (`ki`).
+        pub const fn with_angular_ki(&mut self, ki: f64) -> &mut Self {
+            self.angular_controller.set_ki(ki);
+            self
+        }
+
+        /// Modifies this motion's angular derivative gain (`kd`).
+        pub const fn with_angular_kd(&mut self, kd: f64) -> &mut Self {
+            self.angular_controller.set_kd(kd);
+            self
+        }
+
+        /// Modifies this motion's angular integration range.
+        pub const fn with_angular_integration_range(
+            &mut self,
+            integration_range: ::evian::math::Angle,
+        ) -> &mut Self {
+            self.angular_controller
+                .set_integration_range(Some(integration_range));
+            self
+        }
+
+        /// Modifies this motion's angular output limit.
+        pub const fn with_angular_output_limit(&mut self, limit: f64) -> &mut Self {
+            self.angular_controller.set_output_limit(Some(limit));
+            self
+        }
+
+        /// Removes this motion's angular integration range.
+        pub const fn without_angular_integration_range(&mut self) -> &mut Self {
+            self.angular_controller.set_integration_range(None);
+            self
+        }
+
+        /// Removes this motion's angular output limit.
+        pub const fn without_angular_output_limit(&mut self) -> &mut Self {
+            self.angular_controller.set_output_limit(None);
+            self
+        }
+    };
+}
+
+pub(crate) use {
+    angular_modifiers, angular_pid_modifiers, drive_modifiers, linear_modifiers,
+    linear_pid_modifiers, motion_modifiers,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn started_ago(duration: Duration) -> Instant {
+        let start = Instant::now();
+        crate::sim::clock::advance(duration);
+        start
+    }
+
+    #[test]
+    fn outcome_prefers_settling() {
+        let token = CancelToken::new();
+        token.cancel();
+
+        assert_eq!(
+            outcome(
+                true,
+                Some(&token),
+                Some(Duration::from_secs(1)),
+                started_ago(Duration::from_secs(2)),
+            ),
+            Some(MotionOutcome::Settled)
+        );
+    }
+
+    #[test]
+    fn outcome_prefers_cancellation_over_timeout() {
+        let token = CancelToken::new();
+        token.cancel();
+
+        assert_eq!(
+            outcome(
+                false,
+                Some(&token),
+                Some(Duration::from_secs(1)),
+                started_ago(Duration::from_secs(2)),
+            ),
+            Some(MotionOutcome::Cancelled)
+        );
+    }
+
+    #[test]
+    fn outcome_times_out_after_timeout() {
+        let start = started_ago(Duration::from_secs(2));
+
+        assert_eq!(
+            outcome(false, None, Some(Duration::from_secs(1)), start),
+            Some(MotionOutcome::TimedOut)
+        );
+        assert_eq!(
+            outcome(false, None, Some(Duration::from_secs(5)), start),
+            None
+        );
+        assert_eq!(outcome(false, None, None, start), None);
+    }
+
+    #[test]
+    fn record_adds_to_report_once() {
+        let report = AutonReport::new();
+        let mut target = Some((report.clone(), "drive"));
+        let result = MotionResult {
+            outcome: MotionOutcome::Settled,
+            elapsed: Duration::from_millis(500),
+            linear_error: 0.5,
+            angular_error: evian::math::Angle::from_degrees(1.0),
+        };
+
+        assert_eq!(record(&mut target, result), result);
+        assert_eq!(record(&mut target, result), result);
+
+        let records = report.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].name, "drive");
+    }
+}
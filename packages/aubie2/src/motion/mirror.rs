@@ -0,0 +1,117 @@
+//! Alliance mirroring for routes.
+//!
+//! Routes are written for the red alliance. When running on the blue alliance,
+//! headings, x-coordinates and points are reflected across the field centerline
+//! so that a single route source produces both variants.
+
+use evian::{
+    control::loops::Feedback,
+    math::{Angle, Vec2},
+    motion::Basic,
+    prelude::{
+        Arcade, Drivetrain, TracksForwardTravel, TracksHeading, TracksPosition, TracksVelocity,
+    },
+};
+
+use crate::{
+    motion::basic::{BasicExt, cartesian_drive::CartesianDriveFuture},
+    subsystems::selector::Alliance,
+};
+
+/// Reflects route targets across the field centerline for the blue alliance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mirror {
+    alliance: Alliance,
+
+    /// The x-coordinate of the centerline that targets are reflected across.
+    pub centerline: f64,
+}
+
+impl Mirror {
+    /// Creates a mirror for the given alliance, reflecting across `x = 0`.
+    pub const fn new(alliance: Alliance) -> Self {
+        Self {
+            alliance,
+            centerline: 0.0,
+        }
+    }
+
+    /// Creates a mirror for the alliance selected at compile time with `--cfg color`.
+    pub const fn from_cfg() -> Self {
+        Self::new(Alliance::from_cfg())
+    }
+
+    /// Modifies the x-coordinate of the centerline.
+    pub const fn with_centerline(mut self, centerline: f64) -> Self {
+        self.centerline = centerline;
+        self
+    }
+
+    /// Returns the alliance this mirror is for.
+    pub const fn alliance(&self) -> Alliance {
+        self.alliance
+    }
+
+    /// Returns `true` if targets are being reflected.
+    pub const fn is_mirrored(&self) -> bool {
+        matches!(self.alliance, Alliance::Blue)
+    }
+
+    /// Reflects a heading across the centerline.
+    pub fn heading(&self, heading: Angle) -> Angle {
+        if self.is_mirrored() {
+            (Angle::HALF_TURN - heading).wrapped_full()
+        } else {
+            heading
+        }
+    }
+
+    /// Reflects an x-coordinate across the centerline.
+    pub fn x(&self, x: f64) -> f64 {
+        if self.is_mirrored() {
+            2.0 * self.centerline - x
+        } else {
+            x
+        }
+    }
+
+    /// Reflects a point across the centerline.
+    pub fn point(&self, point: impl Into<Vec2<f64>>) -> Vec2<f64> {
+        let point = point.into();
+        Vec2::new(self.x(point.x), point.y)
+    }
+
+    /// Mirrored [`BasicExt::drive_to_x`].
+    pub fn drive_to_x<'a, L, A, M, T>(
+        &self,
+        basic: &mut Basic<L, A>,
+        drivetrain: &'a mut Drivetrain<M, T>,
+        x: f64,
+        heading: Angle,
+    ) -> CartesianDriveFuture<'a, M, L, A, T>
+    where
+        L: Feedback<State = f64, Signal = f64> + Unpin + Clone,
+        A: Feedback<State = Angle, Signal = f64> + Unpin + Clone,
+        M: Arcade,
+        T: TracksPosition + TracksForwardTravel + TracksHeading + TracksVelocity,
+    {
+        basic.drive_to_x(drivetrain, self.x(x), self.heading(heading))
+    }
+
+    /// Mirrored [`BasicExt::drive_to_y`].
+    pub fn drive_to_y<'a, L, A, M, T>(
+        &self,
+        basic: &mut Basic<L, A>,
+        drivetrain: &'a mut Drivetrain<M, T>,
+        y: f64,
+        heading: Angle,
+    ) -> CartesianDriveFuture<'a, M, L, A, T>
+    where
+        L: Feedback<State = f64, Signal = f64> + Unpin + Clone,
+        A: Feedback<State = Angle, Signal = f64> + Unpin + Clone,
+        M: Arcade,
+        T: TracksPosition + TracksForwardTravel + TracksHeading + TracksVelocity,
+    {
+        basic.drive_to_y(drivetrain, y, self.heading(heading))
+    }
+}
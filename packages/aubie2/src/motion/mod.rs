@@ -1,2 +1,72 @@
+use std::{cell::Cell, rc::Rc, time::Duration};
+
+use evian::math::Angle;
+
 pub mod distance_sensor;
-pub mod basic;
\ No newline at end of file
+mod future;
+pub mod basic;
+pub mod mirror;
+pub mod pursuit;
+pub mod report;
+
+/// How a motion finished.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MotionOutcome {
+    /// The motion reached its target within tolerances.
+    Settled,
+
+    /// The motion ran out of time before settling.
+    TimedOut,
+
+    /// The sensor the motion depends on stopped reporting measurements.
+    SensorLost,
+
+    /// The motion was stopped through its [`CancelToken`].
+    Cancelled,
+}
+
+/// The final state of a motion.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotionResult {
+    pub outcome: MotionOutcome,
+    pub elapsed: Duration,
+
+    /// Final linear error, or `NaN` if it was never measured.
+    pub linear_error: f64,
+
+    /// Final angular error.
+    pub angular_error: Angle,
+}
+
+impl MotionResult {
+    /// Returns `true` if the motion reached its target.
+    pub fn is_settled(&self) -> bool {
+        self.outcome == MotionOutcome::Settled
+    }
+}
+
+/// Handle for stopping a running motion early.
+///
+/// Cloning a `CancelToken` produces another handle to the same token.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Rc<Cell<bool>>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests that any motions using this token stop.
+    pub fn cancel(&self) {
+        self.0.set(true);
+    }
+
+    /// Clears a previous cancellation so the token can be reused.
+    pub fn reset(&self) {
+        self.0.set(false);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.get()
+    }
+}
@@ -0,0 +1,384 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use evian::{
+    control::loops::{Feedback, Pid},
+    math::{Angle, Vec2},
+    prelude::{Arcade, Drivetrain, Tolerances, TracksHeading, TracksPosition},
+};
+
+use crate::{
+    motion::{
+        CancelToken, MotionResult,
+        future::{self, UPDATE_INTERVAL, linear_modifiers, linear_pid_modifiers, motion_modifiers},
+        pursuit::Path,
+        report::AutonReport,
+    },
+    time::{Instant, Sleep, sleep},
+};
+
+/// Distance from the final point within which the robot stops steering, since
+/// the direction to the point becomes meaningless.
+const STEER_DEADBAND: f64 = 2.0;
+
+pub(crate) struct PursuitState {
+    sleep: Sleep,
+    start_time: Instant,
+    prev_time: Instant,
+    prev_position: Vec2<f64>,
+
+    /// Index of the path point nearest the robot.
+    closest: usize,
+
+    /// Fractional index of the last lookahead point, which only moves forward.
+    lookahead: f64,
+
+    /// Whether the robot has stopped pursuing and is settling on the end.
+    settling: bool,
+}
+
+/// Follows a path with pure pursuit.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct PursuitFuture<'a, M, L, T>
+where
+    M: Arcade,
+    L: Feedback<State = f64, Signal = f64> + Unpin,
+    T: TracksPosition + TracksHeading,
+{
+    pub(crate) path: &'a Path,
+
+    /// Target output at each point of the path.
+    pub(crate) velocities: Vec<f64>,
+    pub(crate) min_lookahead: f64,
+    pub(crate) max_lookahead: f64,
+    pub(crate) max_output: f64,
+    pub(crate) track_width: f64,
+    pub(crate) reverse: bool,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) linear_tolerances: Tolerances,
+    pub(crate) linear_controller: L,
+    pub(crate) drivetrain: &'a mut Drivetrain<M, T>,
+
+    /// Token that stops this motion early when cancelled.
+    pub(crate) cancel: Option<CancelToken>,
+
+    /// Report to record this motion's outcome into.
+    pub(crate) report: Option<(AutonReport, &'static str)>,
+
+    /// Internal future state ("local variables").
+    pub(crate) state: Option<PursuitState>,
+}
+
+fn distance(a: Vec2<f64>, b: Vec2<f64>) -> f64 {
+    (b.x - a.x).hypot(b.y - a.y)
+}
+
+fn angle_to(from: Vec2<f64>, to: Vec2<f64>) -> Angle {
+    Angle::from_radians((to.y - from.y).atan2(to.x - from.x))
+}
+
+/// Finds the furthest point along the path `radius` inches from `center`,
+/// returning its fractional index if it's past `after`.
+fn intersect(points: &[Vec2<f64>], center: Vec2<f64>, radius: f64, after: f64) -> Option<f64> {
+    let start = after.floor() as usize;
+
+    (start..points.len().saturating_sub(1))
+        .rev()
+        .find_map(|index| {
+            let (a, b) = (points[index], points[index + 1]);
+            let (dx, dy) = (b.x - a.x, b.y - a.y);
+            let (fx, fy) = (a.x - center.x, a.y - center.y);
+
+            let qa = dx * dx + dy * dy;
+            let qb = 2.0 * (fx * dx + fy * dy);
+            let qc = fx * fx + fy * fy - radius * radius;
+            let discriminant = qb * qb - 4.0 * qa * qc;
+            if qa < f64::EPSILON || discriminant < 0.0 {
+                return None;
+            }
+
+            // The larger root is further along the segment.
+            let t = (-qb + discriminant.sqrt()) / (2.0 * qa);
+            let fraction = index as f64 + t;
+
+            ((0.0..=1.0).contains(&t) && fraction >= after).then_some(fraction)
+        })
+}
+
+/// Finds the path point nearest `position`, searching forward from `from` up to
+/// `window` inches further along the path.
+///
+/// The nearest point only moves forward, and only within the window, so that
+/// paths which cross over or double back on themselves aren't skipped ahead.
+fn closest(path: &Path, position: Vec2<f64>, from: usize, window: f64) -> usize {
+    let limit = path.distances[from] + window;
+
+    (from..path.points.len())
+        .take_while(|index| *index == from || path.distances[*index] <= limit)
+        .min_by(|a, b| {
+            distance(path.points[*a], position).total_cmp(&distance(path.points[*b], position))
+        })
+        .unwrap_or(from)
+}
+
+/// Returns the point at a fractional index along the path.
+fn point_at(points: &[Vec2<f64>], fraction: f64) -> Vec2<f64> {
+    let index = (fraction.floor() as usize).min(points.len() - 1);
+    let Some(next) = points.get(index + 1) else {
+        return points[index];
+    };
+
+    let t = fraction - index as f64;
+    let point = points[index];
+    Vec2::new(
+        point.x + (next.x - point.x) * t,
+        point.y + (next.y - point.y) * t,
+    )
+}
+
+// MARK: Future Poll
+
+impl<M, L, T> Future for PursuitFuture<'_, M, L, T>
+where
+    M: Arcade,
+    L: Feedback<State = f64, Signal = f64> + Unpin,
+    T: TracksPosition + TracksHeading,
+{
+    type Output = MotionResult;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let position = this.drivetrain.tracking.position();
+        let state = this.state.get_or_insert_with(|| {
+            let now = Instant::now();
+            PursuitState {
+                sleep: sleep(UPDATE_INTERVAL),
+                start_time: now,
+                prev_time: now,
+                prev_position: position,
+                closest: 0,
+                lookahead: 0.0,
+                settling: false,
+            }
+        });
+
+        if Pin::new(&mut state.sleep).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+
+        let dt = state.prev_time.elapsed();
+        let points = &this.path.points;
+        let end = points[points.len() - 1];
+
+        // Driving backwards is the same as driving forwards with the robot
+        // turned around.
+        let heading = this.drivetrain.tracking.heading();
+        let (facing, direction) = if this.reverse {
+            (heading + Angle::from_degrees(180.0), -1.0)
+        } else {
+            (heading, 1.0)
+        };
+
+        state.closest = closest(this.path, position, state.closest, this.max_lookahead);
+        let target_velocity = this.velocities[state.closest];
+
+        let speed_fraction = (target_velocity / this.max_output).clamp(0.0, 1.0);
+        let lookahead =
+            this.min_lookahead + (this.max_lookahead - this.min_lookahead) * speed_fraction;
+
+        let end_distance = distance(position, end);
+        if end_distance < lookahead && state.closest + 2 >= points.len() {
+            state.settling = true;
+        }
+
+        // Distance to the end along the way the robot is facing.
+        let linear_error = end_distance * (angle_to(position, end) - facing).wrapped_half().cos();
+        let velocity = if dt.is_zero() {
+            0.0
+        } else {
+            distance(state.prev_position, position) / dt.as_secs_f64()
+        };
+
+        // Heading error against the direction of the path's final segment.
+        let final_direction = match points.len() {
+            0 | 1 => facing,
+            len => angle_to(points[len - 2], end),
+        };
+        let angular_error = (final_direction - facing).wrapped_half();
+
+        let outcome = future::outcome(
+            state.settling && this.linear_tolerances.check(linear_error, velocity),
+            this.cancel.as_ref(),
+            this.timeout,
+            state.start_time,
+        );
+
+        if let Some(outcome) = outcome {
+            drop(this.drivetrain.model.drive_arcade(0.0, 0.0));
+
+            let result = MotionResult {
+                outcome,
+                elapsed: state.start_time.elapsed(),
+                linear_error: end_distance,
+                angular_error,
+            };
+            return Poll::Ready(future::record(&mut this.report, result));
+        }
+
+        let (target, throttle) = if state.settling {
+            let throttle = this
+                .linear_controller
+                .update(-linear_error, 0.0, dt)
+                .clamp(-this.max_output, this.max_output);
+
+            (end, throttle)
+        } else {
+            if let Some(fraction) = intersect(points, position, lookahead, state.lookahead) {
+                state.lookahead = fraction;
+            }
+
+            (point_at(points, state.lookahead), target_velocity)
+        };
+
+        // Pure pursuit curvature, from the target's sideways offset in the
+        // robot's frame.
+        let target_distance = distance(position, target);
+        let curvature = if target_distance < STEER_DEADBAND {
+            0.0
+        } else {
+            let offset =
+                target_distance * (angle_to(position, target) - facing).wrapped_half().sin();
+            2.0 * offset / (target_distance * target_distance)
+        };
+
+        let mut throttle = throttle;
+        let mut steer = throttle.abs() * curvature * this.track_width / 2.0;
+
+        // Scale both down together so the robot still follows the same arc.
+        let saturation = (throttle.abs() + steer.abs()) / this.max_output;
+        if saturation > 1.0 {
+            throttle /= saturation;
+            steer /= saturation;
+        }
+
+        drop(
+            this.drivetrain
+                .model
+                .drive_arcade(throttle * direction, steer),
+        );
+
+        state.sleep = sleep(UPDATE_INTERVAL);
+        state.prev_time = Instant::now();
+        state.prev_position = position;
+
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+// MARK: Generic Modifiers
+
+impl<M, L, T> PursuitFuture<'_, M, L, T>
+where
+    M: Arcade,
+    L: Feedback<State = f64, Signal = f64> + Unpin,
+    T: TracksPosition + TracksHeading,
+{
+    /// Follows the path backwards, with the back of the robot leading.
+    pub const fn reverse(&mut self) -> &mut Self {
+        self.reverse = true;
+        self
+    }
+
+    /// Modifies the range the lookahead distance varies over with speed.
+    pub const fn with_lookahead(&mut self, min: f64, max: f64) -> &mut Self {
+        self.min_lookahead = min;
+        self.max_lookahead = max;
+        self
+    }
+
+    motion_modifiers!();
+
+    linear_modifiers!(L);
+}
+
+// MARK: Linear PID Modifiers
+
+impl<M, T> PursuitFuture<'_, M, Pid, T>
+where
+    M: Arcade,
+    T: TracksPosition + TracksHeading,
+{
+    linear_pid_modifiers!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    fn straight() -> Vec<Vec2<f64>> {
+        vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(20.0, 0.0),
+        ]
+    }
+
+    #[test]
+    fn intersect_finds_furthest_crossing() {
+        let points = straight();
+
+        assert_near(
+            intersect(&points, Vec2::new(0.0, 0.0), 5.0, 0.0).unwrap(),
+            0.5,
+        );
+        assert_near(
+            intersect(&points, Vec2::new(12.0, 0.0), 5.0, 0.0).unwrap(),
+            1.7,
+        );
+    }
+
+    #[test]
+    fn intersect_never_moves_backwards() {
+        let points = straight();
+
+        assert_eq!(intersect(&points, Vec2::new(0.0, 0.0), 5.0, 1.0), None);
+        assert_eq!(intersect(&points, Vec2::new(0.0, 50.0), 5.0, 0.0), None);
+    }
+
+    #[test]
+    fn closest_ignores_points_past_the_window() {
+        // Out along y = 0 and back along y = 2.
+        let path = Path::new([
+            (0.0, 0.0),
+            (5.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 2.0),
+            (5.0, 2.0),
+            (0.0, 2.0),
+        ]);
+        let position = Vec2::new(0.0, 1.5);
+
+        assert_eq!(closest(&path, position, 0, 12.0), 0);
+        assert_eq!(closest(&path, position, 0, 30.0), 5);
+    }
+
+    #[test]
+    fn closest_only_moves_forward() {
+        let path = Path::new(straight());
+
+        assert_eq!(closest(&path, Vec2::new(0.0, 0.0), 1, 30.0), 1);
+        assert_eq!(closest(&path, Vec2::new(19.0, 0.0), 2, 0.0), 2);
+    }
+}
@@ -0,0 +1,89 @@
+//! Pure pursuit path following.
+
+use std::time::Duration;
+
+use evian::{
+    control::loops::Feedback,
+    prelude::{Arcade, Drivetrain, Tolerances, TracksHeading, TracksPosition},
+};
+
+pub use path::Path;
+
+use crate::motion::pursuit::future::PursuitFuture;
+
+mod future;
+mod path;
+
+/// Follows paths by steering towards a point a set distance ahead along them.
+///
+/// Lookahead grows with the path's target speed, so that the robot cuts
+/// smoothly through fast, straight sections but tracks tight turns closely.
+/// Speed is limited by the path's curvature, and once the end of the path is
+/// within reach the robot stops pursuing and settles onto the final point with
+/// the linear controller.
+#[derive(PartialEq)]
+pub struct PurePursuit<L>
+where
+    L: Feedback<State = f64, Signal = f64> + Unpin + Clone,
+{
+    /// Linear feedback controller used to settle at the end of the path.
+    pub linear_controller: L,
+
+    /// Settling conditions at the end of the path.
+    pub linear_tolerances: Tolerances,
+
+    /// Lookahead distance in inches when the path's target speed is zero.
+    pub min_lookahead: f64,
+
+    /// Lookahead distance in inches at `max_output`.
+    pub max_lookahead: f64,
+
+    /// Fastest the robot may drive, as a fraction of full output.
+    pub max_output: f64,
+
+    /// Output allowed through a turn with a one-inch radius, scaling up with
+    /// the radius.
+    pub curvature_gain: f64,
+
+    /// Largest drop in output per inch when slowing for a turn.
+    pub max_deceleration: f64,
+
+    /// Distance between the left and right wheels in inches.
+    pub track_width: f64,
+
+    /// Maximum duration the motion can take before being cancelled.
+    pub timeout: Option<Duration>,
+}
+
+impl<L> PurePursuit<L>
+where
+    L: Feedback<State = f64, Signal = f64> + Unpin + Clone,
+{
+    /// Follows a path from wherever the robot currently is.
+    pub fn follow<'a, M: Arcade, T: TracksPosition + TracksHeading>(
+        &mut self,
+        drivetrain: &'a mut Drivetrain<M, T>,
+        path: &'a Path,
+    ) -> PursuitFuture<'a, M, L, T> {
+        PursuitFuture {
+            velocities: path.velocities(
+                self.max_output,
+                self.curvature_gain,
+                self.max_deceleration,
+            ),
+            path,
+            min_lookahead: self.min_lookahead,
+            max_lookahead: self.max_lookahead,
+            max_output: self.max_output,
+            track_width: self.track_width,
+            reverse: false,
+            timeout: self.timeout,
+            linear_tolerances: self.linear_tolerances,
+            linear_controller: self.linear_controller.clone(),
+            drivetrain,
+            cancel: None,
+            report: None,
+            state: None,
+        }
+    }
+}
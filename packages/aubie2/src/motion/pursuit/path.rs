@@ -0,0 +1,230 @@
+use evian::math::Vec2;
+
+/// A path for [`PurePursuit`](super::PurePursuit) to follow.
+///
+/// Alongside its points, a path stores the distance along it and its curvature
+/// at each point, which the follower uses to slow down through turns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    pub(crate) points: Vec<Vec2<f64>>,
+
+    /// Distance along the path to each point, in inches.
+    pub(crate) distances: Vec<f64>,
+
+    /// Curvature at each point, in inverse inches.
+    pub(crate) curvatures: Vec<f64>,
+}
+
+fn distance(a: Vec2<f64>, b: Vec2<f64>) -> f64 {
+    (b.x - a.x).hypot(b.y - a.y)
+}
+
+/// Curvature of the circle through three points, or zero if they're in a line.
+fn curvature(a: Vec2<f64>, b: Vec2<f64>, c: Vec2<f64>) -> f64 {
+    let sides = distance(a, b) * distance(b, c) * distance(c, a);
+    if sides < f64::EPSILON {
+        return 0.0;
+    }
+
+    // Twice the triangle's area over the product of its sides.
+    let cross = (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+    2.0 * cross.abs() / sides
+}
+
+/// Catmull-Rom spline through `p1` and `p2`, with `p0` and `p3` as neighbors.
+fn catmull_rom(p0: Vec2<f64>, p1: Vec2<f64>, p2: Vec2<f64>, p3: Vec2<f64>, t: f64) -> Vec2<f64> {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let blend = |a: f64, b: f64, c: f64, d: f64| {
+        0.5 * (2.0 * b
+            + (c - a) * t
+            + (2.0 * a - 5.0 * b + 4.0 * c - d) * t2
+            + (3.0 * b - a - 3.0 * c + d) * t3)
+    };
+
+    Vec2::new(blend(p0.x, p1.x, p2.x, p3.x), blend(p0.y, p1.y, p2.y, p3.y))
+}
+
+impl Path {
+    /// Creates a path of straight lines between waypoints.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no waypoints are given.
+    pub fn new(waypoints: impl IntoIterator<Item = impl Into<Vec2<f64>>>) -> Self {
+        let points: Vec<Vec2<f64>> = waypoints.into_iter().map(Into::into).collect();
+        assert!(!points.is_empty(), "A path needs at least one waypoint.");
+
+        let mut distances = Vec::with_capacity(points.len());
+        let mut travelled = 0.0;
+        for (index, point) in points.iter().enumerate() {
+            if index > 0 {
+                travelled += distance(points[index - 1], *point);
+            }
+            distances.push(travelled);
+        }
+
+        let curvatures = (0..points.len())
+            .map(|index| {
+                if index == 0 || index == points.len() - 1 {
+                    0.0
+                } else {
+                    curvature(points[index - 1], points[index], points[index + 1])
+                }
+            })
+            .collect();
+
+        Self {
+            points,
+            distances,
+            curvatures,
+        }
+    }
+
+    /// Creates a smooth path through waypoints, sampled every `spacing` inches.
+    ///
+    /// The curve is a Catmull-Rom spline, so it passes through every waypoint
+    /// rather than cutting corners near them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no waypoints are given.
+    pub fn spline(waypoints: impl IntoIterator<Item = impl Into<Vec2<f64>>>, spacing: f64) -> Self {
+        let waypoints: Vec<Vec2<f64>> = waypoints.into_iter().map(Into::into).collect();
+        assert!(!waypoints.is_empty(), "A path needs at least one waypoint.");
+
+        let mut points = vec![waypoints[0]];
+        for index in 0..waypoints.len() - 1 {
+            let p0 = waypoints[index.saturating_sub(1)];
+            let p1 = waypoints[index];
+            let p2 = waypoints[index + 1];
+            let p3 = waypoints[(index + 2).min(waypoints.len() - 1)];
+
+            let samples = (distance(p1, p2) / spacing).ceil().max(1.0) as usize;
+            for sample in 1..=samples {
+                points.push(catmull_rom(p0, p1, p2, p3, sample as f64 / samples as f64));
+            }
+        }
+
+        Self::new(points)
+    }
+
+    /// Returns the points making up the path.
+    pub fn points(&self) -> &[Vec2<f64>] {
+        &self.points
+    }
+
+    /// Returns the path's total length in inches.
+    pub fn length(&self) -> f64 {
+        self.distances.last().copied().unwrap_or_default()
+    }
+
+    /// Returns the target speed at each point, as a fraction of full output.
+    ///
+    /// Each point is limited to `curvature_gain / curvature`, then points are
+    /// slowed further so that reaching the next point never needs a drop of more
+    /// than `max_deceleration` per inch.
+    pub(crate) fn velocities(
+        &self,
+        max_output: f64,
+        curvature_gain: f64,
+        max_deceleration: f64,
+    ) -> Vec<f64> {
+        let mut velocities: Vec<f64> = self
+            .curvatures
+            .iter()
+            .map(|curvature| (curvature_gain / curvature).min(max_output))
+            .collect();
+
+        for index in (0..velocities.len().saturating_sub(1)).rev() {
+            let gap = self.distances[index + 1] - self.distances[index];
+            velocities[index] =
+                velocities[index].min(velocities[index + 1] + max_deceleration * gap);
+        }
+
+        velocities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn curvature_of_circle_through_points() {
+        // The circle through these points has a radius of sqrt(2) / 2.
+        assert_near(
+            curvature(
+                Vec2::new(0.0, 0.0),
+                Vec2::new(1.0, 0.0),
+                Vec2::new(1.0, 1.0),
+            ),
+            2.0_f64.sqrt(),
+        );
+    }
+
+    #[test]
+    fn curvature_of_line_is_zero() {
+        let a = Vec2::new(0.0, 0.0);
+        let b = Vec2::new(1.0, 1.0);
+
+        assert_near(curvature(a, b, Vec2::new(2.0, 2.0)), 0.0);
+        assert_near(curvature(a, a, b), 0.0);
+    }
+
+    #[test]
+    fn catmull_rom_passes_through_control_points() {
+        let (p0, p1, p2, p3) = (
+            Vec2::new(-3.0, 2.0),
+            Vec2::new(0.0, 0.0),
+            Vec2::new(4.0, 1.0),
+            Vec2::new(5.0, 6.0),
+        );
+
+        assert_eq!(catmull_rom(p0, p1, p2, p3, 0.0), p1);
+
+        let end = catmull_rom(p0, p1, p2, p3, 1.0);
+        assert_near(end.x, p2.x);
+        assert_near(end.y, p2.y);
+    }
+
+    #[test]
+    fn catmull_rom_is_linear_for_evenly_spaced_points() {
+        let point = catmull_rom(
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(3.0, 3.0),
+            0.25,
+        );
+
+        assert_near(point.x, 1.25);
+        assert_near(point.y, 1.25);
+    }
+
+    #[test]
+    fn velocities_are_capped_on_straights() {
+        let path = Path::new([(0.0, 0.0), (10.0, 0.0), (20.0, 0.0)]);
+
+        assert_eq!(path.velocities(0.8, 0.05, 0.02), vec![0.8, 0.8, 0.8]);
+    }
+
+    #[test]
+    fn velocities_slow_down_before_turns() {
+        let path = Path::new([(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]);
+        let velocities = path.velocities(1.0, 0.05, 0.02);
+
+        // The corner's curvature is sqrt(2) / 10.
+        let corner = 0.05 / (2.0_f64.sqrt() / 10.0);
+        assert_near(velocities[1], corner);
+        assert_near(velocities[0], corner + 0.02 * 10.0);
+        assert_near(velocities[2], 1.0);
+    }
+}
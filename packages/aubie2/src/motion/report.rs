@@ -0,0 +1,282 @@
+//! End-of-autonomous motion reports.
+
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use evian::{
+    control::Tolerances,
+    math::{Angle, Vec2},
+    prelude::{TracksForwardTravel, TracksHeading, TracksPosition},
+};
+use log::{info, warn};
+use vexide::controller::Controller;
+
+use crate::{
+    motion::{MotionOutcome, MotionResult},
+    time::Instant,
+};
+
+/// The result of a single motion.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotionRecord {
+    pub name: &'static str,
+    pub outcome: MotionOutcome,
+    pub elapsed: Duration,
+
+    /// Final linear error in inches, if known.
+    pub linear_error: Option<f64>,
+
+    /// Final angular error, if known.
+    pub angular_error: Option<Angle>,
+}
+
+impl MotionRecord {
+    /// Creates a record from the result of one of this crate's motions.
+    pub fn new(name: &'static str, result: MotionResult) -> Self {
+        Self {
+            name,
+            outcome: result.outcome,
+            elapsed: result.elapsed,
+            linear_error: (!result.linear_error.is_nan()).then_some(result.linear_error),
+            angular_error: Some(result.angular_error),
+        }
+    }
+}
+
+/// What a motion was trying to reach, used to compute its final error.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    /// Turning to a heading.
+    Heading(Angle),
+
+    /// Driving a distance while holding a heading.
+    DistanceAtHeading(f64, Angle),
+
+    /// Driving to a point.
+    Point(Vec2<f64>),
+}
+
+/// Collects [`MotionRecord`]s over the course of a route.
+///
+/// Cloning an `AutonReport` produces another handle to the same set of records.
+#[derive(Debug, Clone)]
+pub struct AutonReport {
+    records: Rc<RefCell<Vec<MotionRecord>>>,
+    start: Rc<RefCell<Instant>>,
+}
+
+impl Default for AutonReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AutonReport {
+    pub fn new() -> Self {
+        Self {
+            records: Rc::new(RefCell::new(Vec::new())),
+            start: Rc::new(RefCell::new(Instant::now())),
+        }
+    }
+
+    /// Removes all records and restarts the report's clock.
+    pub fn clear(&self) {
+        self.records.borrow_mut().clear();
+        *self.start.borrow_mut() = Instant::now();
+    }
+
+    /// Adds a record to the report.
+    pub fn record(&self, record: MotionRecord) {
+        self.records.borrow_mut().push(record);
+    }
+
+    /// Returns a copy of every record in the report.
+    pub fn records(&self) -> Vec<MotionRecord> {
+        self.records.borrow().clone()
+    }
+
+    /// Starts timing a motion that doesn't report its own outcome, such as
+    /// evian's [`Basic`](evian::motion::Basic) and [`Seeking`](evian::motion::Seeking)
+    /// motions.
+    ///
+    /// Call [`MotionTimer::finish`] after the motion completes to record it. Motions
+    /// that resolve to a [`MotionResult`] should use `with_report` instead.
+    pub fn begin<T: TracksForwardTravel>(
+        &self,
+        name: &'static str,
+        tracking: &T,
+        target: Target,
+    ) -> MotionTimer {
+        MotionTimer {
+            report: self.clone(),
+            name,
+            target,
+            start: Instant::now(),
+            start_travel: tracking.forward_travel(),
+        }
+    }
+
+    /// Logs every record followed by a summary.
+    pub fn log(&self) {
+        let records = self.records.borrow();
+
+        info!("Autonomous report:");
+        for (index, record) in records.iter().enumerate() {
+            let linear = record
+                .linear_error
+                .map_or_else(|| "-".to_string(), |error| format!("{error:.2}\""));
+            let angular = record.angular_error.map_or_else(
+                || "-".to_string(),
+                |error| format!("{:.2}°", error.as_degrees()),
+            );
+
+            if record.outcome == MotionOutcome::Settled {
+                info!(
+                    "  {index:>2} {:<16} {:?} in {:?} (linear {linear}, angular {angular})",
+                    record.name, record.outcome, record.elapsed
+                );
+            } else {
+                warn!(
+                    "  {index:>2} {:<16} {:?} in {:?} (linear {linear}, angular {angular})",
+                    record.name, record.outcome, record.elapsed
+                );
+            }
+        }
+
+        info!(
+            "{}/{} motions settled in {:?}.",
+            self.settled_count(),
+            records.len(),
+            self.start.borrow().elapsed()
+        );
+    }
+
+    /// Shows a summary of the report on the controller screen.
+    pub async fn show(&self, controller: &mut Controller) {
+        let total = self.records.borrow().len();
+        let failed = total - self.settled_count();
+
+        _ = controller
+            .set_text(format!("{}/{} settled    ", total - failed, total), 1, 1)
+            .await;
+        _ = controller
+            .set_text(
+                format!(
+                    "{:.1}s total    ",
+                    self.start.borrow().elapsed().as_secs_f64()
+                ),
+                2,
+                1,
+            )
+            .await;
+
+        let failure = self
+            .records
+            .borrow()
+            .iter()
+            .find(|record| record.outcome != MotionOutcome::Settled)
+            .copied();
+        if let Some(record) = failure {
+            _ = controller
+                .set_text(format!("{:.14} {:?}", record.name, record.outcome), 3, 1)
+                .await;
+        }
+    }
+
+    fn settled_count(&self) -> usize {
+        self.records
+            .borrow()
+            .iter()
+            .filter(|record| record.outcome == MotionOutcome::Settled)
+            .count()
+    }
+}
+
+/// Times a motion started with [`AutonReport::begin`].
+#[must_use = "the motion is only recorded once `finish` is called"]
+pub struct MotionTimer {
+    report: AutonReport,
+    name: &'static str,
+    target: Target,
+    start: Instant,
+    start_travel: f64,
+}
+
+impl MotionTimer {
+    /// Records the motion's outcome and final error.
+    ///
+    /// evian's motions only stop once they settle or time out, so a motion that
+    /// finished outside the error tolerances it was run with is recorded as
+    /// having timed out.
+    pub fn finish<T: TracksPosition + TracksHeading + TracksForwardTravel>(
+        self,
+        tracking: &T,
+        linear_tolerances: Tolerances,
+        angular_tolerances: Tolerances,
+    ) -> MotionRecord {
+        let elapsed = self.start.elapsed();
+        let heading = tracking.heading();
+
+        let (linear_error, angular_error) = match self.target {
+            Target::Heading(target) => (None, Some((target - heading).wrapped_half())),
+            Target::DistanceAtHeading(distance, target) => (
+                Some(distance - (tracking.forward_travel() - self.start_travel)),
+                Some((target - heading).wrapped_half()),
+            ),
+            Target::Point(point) => {
+                let error = point - tracking.position();
+                (Some(error.x.hypot(error.y)), None)
+            }
+        };
+
+        let settled = within(linear_tolerances, linear_error)
+            && within(
+                angular_tolerances,
+                angular_error.map(|error| error.as_radians()),
+            );
+
+        let record = MotionRecord {
+            name: self.name,
+            outcome: if settled {
+                MotionOutcome::Settled
+            } else {
+                MotionOutcome::TimedOut
+            },
+            elapsed,
+            linear_error,
+            angular_error,
+        };
+
+        self.report.record(record);
+        record
+    }
+}
+
+/// Returns `true` if a final error is within a motion's error tolerance, or if
+/// either is unknown.
+fn within(tolerances: Tolerances, error: Option<f64>) -> bool {
+    error.is_none_or(|error| {
+        tolerances
+            .error_tolerance
+            .is_none_or(|tolerance| error.abs() <= tolerance)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn within_checks_error_magnitude() {
+        let tolerances = Tolerances::new().error(1.0);
+
+        assert!(within(tolerances, Some(0.5)));
+        assert!(within(tolerances, Some(-1.0)));
+        assert!(!within(tolerances, Some(-1.5)));
+    }
+
+    #[test]
+    fn within_passes_unknown_errors_and_tolerances() {
+        assert!(within(Tolerances::new().error(1.0), None));
+        assert!(within(Tolerances::new(), Some(100.0)));
+    }
+}
@@ -0,0 +1,607 @@
+//! Declarative autonomous routes.
+//!
+//! A [`RoutePlan`] is a list of [`Step`]s that can be logged, timed, and resumed or
+//! skipped from named checkpoints, rather than a long chain of awaited motions.
+
+use std::{fmt, time::Duration};
+
+use evian::{
+    control::{
+        Tolerances,
+        loops::{AngularPid, Pid},
+    },
+    math::{Angle, Vec2},
+    motion::{Basic, Seeking},
+    prelude::{
+        Arcade, Drivetrain, TracksForwardTravel, TracksHeading, TracksPosition, TracksVelocity,
+    },
+};
+use futures::future::join;
+use log::{info, warn};
+
+use crate::{
+    localization::SetPosition,
+    motion::{
+        basic::BasicExt,
+        report::{AutonReport, Target},
+    },
+    time::{Instant, sleep},
+};
+
+/// A robot that can run a [`RoutePlan`].
+pub trait RouteRobot {
+    type Model: Arcade;
+    type Tracking: TracksPosition
+        + TracksForwardTravel
+        + TracksHeading
+        + TracksVelocity
+        + SetPosition;
+
+    /// Everything on the robot other than the drivetrain that steps can actuate.
+    type Mechanisms;
+
+    /// Returns the motion parameters used for driving and turning steps.
+    fn basic(&self) -> Basic<Pid, AngularPid>;
+
+    /// Returns the motion parameters used for point-seeking steps.
+    fn seeking(&self) -> Seeking<Pid, Pid>;
+
+    /// Splits the robot into its drivetrain and mechanisms so that both can be
+    /// used at once by parallel steps.
+    fn parts(
+        &mut self,
+    ) -> (
+        &mut Drivetrain<Self::Model, Self::Tracking>,
+        &mut Self::Mechanisms,
+    );
+
+    /// Returns the report that motion steps should be recorded into, if any.
+    fn report(&self) -> Option<AutonReport> {
+        None
+    }
+}
+
+/// An action performed on a robot's mechanisms.
+pub type Action<M> = fn(&mut M);
+
+/// A drivetrain motion.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MotionKind {
+    /// Drive a distance while holding a heading.
+    Drive { distance: f64, heading: Angle },
+
+    /// Turn in place to a heading.
+    Turn { heading: Angle },
+
+    /// Drive to a point, ignoring final heading.
+    SeekPoint { point: Vec2<f64> },
+}
+
+/// A drivetrain motion along with overrides for its default parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Motion {
+    pub kind: MotionKind,
+    pub timeout: Option<Duration>,
+    pub linear_output_limit: Option<f64>,
+    pub angular_output_limit: Option<f64>,
+    pub linear_tolerances: Option<Tolerances>,
+    pub angular_tolerances: Option<Tolerances>,
+}
+
+impl Motion {
+    /// Drives a distance while holding a heading.
+    pub const fn drive(distance: f64, heading: Angle) -> Self {
+        Self::new(MotionKind::Drive { distance, heading })
+    }
+
+    /// Turns in place to a heading.
+    pub const fn turn(heading: Angle) -> Self {
+        Self::new(MotionKind::Turn { heading })
+    }
+
+    /// Drives to a point.
+    pub fn seek(point: impl Into<Vec2<f64>>) -> Self {
+        Self::new(MotionKind::SeekPoint {
+            point: point.into(),
+        })
+    }
+
+    const fn new(kind: MotionKind) -> Self {
+        Self {
+            kind,
+            timeout: None,
+            linear_output_limit: None,
+            angular_output_limit: None,
+            linear_tolerances: None,
+            angular_tolerances: None,
+        }
+    }
+
+    /// Modifies this motion's timeout duration.
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Modifies this motion's linear output limit.
+    pub const fn with_linear_output_limit(mut self, limit: f64) -> Self {
+        self.linear_output_limit = Some(limit);
+        self
+    }
+
+    /// Modifies this motion's angular output limit.
+    pub const fn with_angular_output_limit(mut self, limit: f64) -> Self {
+        self.angular_output_limit = Some(limit);
+        self
+    }
+
+    /// Modifies this motion's linear tolerances.
+    ///
+    /// For point-seeking motions, these replace the seeking tolerances.
+    pub const fn with_linear_tolerances(mut self, tolerances: Tolerances) -> Self {
+        self.linear_tolerances = Some(tolerances);
+        self
+    }
+
+    /// Modifies this motion's angular tolerances.
+    pub const fn with_angular_tolerances(mut self, tolerances: Tolerances) -> Self {
+        self.angular_tolerances = Some(tolerances);
+        self
+    }
+
+    async fn run<M, T>(
+        self,
+        drivetrain: &mut Drivetrain<M, T>,
+        mut basic: Basic<Pid, AngularPid>,
+        mut seeking: Seeking<Pid, Pid>,
+        report: Option<(AutonReport, &'static str)>,
+    ) where
+        M: Arcade,
+        T: TracksPosition + TracksForwardTravel + TracksHeading + TracksVelocity,
+    {
+        if let Some(timeout) = self.timeout {
+            basic.timeout = Some(timeout);
+            seeking.timeout = Some(timeout);
+        }
+        if let Some(limit) = self.linear_output_limit {
+            basic.linear_controller.set_output_limit(Some(limit));
+            seeking.linear_controller.set_output_limit(Some(limit));
+        }
+        if let Some(limit) = self.angular_output_limit {
+            basic.angular_controller.set_output_limit(Some(limit));
+            seeking.lateral_controller.set_output_limit(Some(limit));
+        }
+        if let Some(tolerances) = self.linear_tolerances {
+            basic.linear_tolerances = tolerances;
+            seeking.tolerances = tolerances;
+        }
+        if let Some(tolerances) = self.angular_tolerances {
+            basic.angular_tolerances = tolerances;
+        }
+
+        match self.kind {
+            MotionKind::Drive { distance, heading } => {
+                let mut motion = basic.drive_at_heading(drivetrain, distance, heading);
+                motion.report = report;
+                _ = motion.await;
+            }
+            MotionKind::Turn { heading } => {
+                let mut motion = basic.turn_to(drivetrain, heading);
+                motion.report = report;
+                _ = motion.await;
+            }
+            MotionKind::SeekPoint { point } => {
+                // evian's seeking motion doesn't report its own outcome.
+                let timer = report.map(|(report, name)| {
+                    report.begin(name, &drivetrain.tracking, Target::Point(point))
+                });
+                seeking.move_to_point(drivetrain, point).await;
+
+                if let Some(timer) = timer {
+                    timer.finish(&drivetrain.tracking, seeking.tolerances, Tolerances::new());
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Motion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            MotionKind::Drive { distance, heading } => {
+                write!(f, "drive {distance}\" at {}°", heading.as_degrees())
+            }
+            MotionKind::Turn { heading } => write!(f, "turn to {}°", heading.as_degrees()),
+            MotionKind::SeekPoint { point } => write!(f, "seek {point}"),
+        }
+    }
+}
+
+/// A single step of a [`RoutePlan`].
+pub enum Step<M> {
+    /// Marks a point in the route that can be resumed from or skipped.
+    Checkpoint(&'static str),
+
+    /// Runs a drivetrain motion.
+    Motion(Motion),
+
+    /// Runs a named action on the robot's mechanisms.
+    Actuate(&'static str, Action<M>),
+
+    /// Overwrites the tracked position, such as after squaring up against a
+    /// known wall.
+    SetPosition(Vec2<f64>),
+
+    /// Waits for a duration.
+    Wait(Duration),
+
+    /// Runs a motion while performing actions at fixed delays from its start.
+    ///
+    /// The step finishes once the motion and all actions are complete.
+    Parallel {
+        motion: Motion,
+        actions: Vec<(Duration, &'static str, Action<M>)>,
+    },
+}
+
+impl<M> From<Motion> for Step<M> {
+    fn from(motion: Motion) -> Self {
+        Self::Motion(motion)
+    }
+}
+
+impl<M> fmt::Display for Step<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Checkpoint(name) => write!(f, "checkpoint \"{name}\""),
+            Self::Motion(motion) => write!(f, "{motion}"),
+            Self::Actuate(name, _) => write!(f, "{name}"),
+            Self::SetPosition(position) => write!(f, "set position to {position}"),
+            Self::Wait(duration) => write!(f, "wait {duration:?}"),
+            Self::Parallel { motion, actions } => {
+                write!(f, "{motion} while")?;
+                for (delay, name, _) in actions {
+                    write!(f, " [{name} @ {delay:?}]")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A named list of route steps.
+pub struct RoutePlan<M> {
+    pub name: &'static str,
+    pub steps: Vec<Step<M>>,
+}
+
+impl<M> RoutePlan<M> {
+    /// Creates an empty route.
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Adds a step to the end of the route.
+    pub fn then(mut self, step: impl Into<Step<M>>) -> Self {
+        self.steps.push(step.into());
+        self
+    }
+
+    /// Adds a checkpoint to the end of the route.
+    pub fn checkpoint(self, name: &'static str) -> Self {
+        self.then(Step::Checkpoint(name))
+    }
+
+    /// Adds an action to the end of the route.
+    pub fn actuate(self, name: &'static str, action: Action<M>) -> Self {
+        self.then(Step::Actuate(name, action))
+    }
+
+    /// Adds a position reset to the end of the route.
+    pub fn set_position(self, position: impl Into<Vec2<f64>>) -> Self {
+        self.then(Step::SetPosition(position.into()))
+    }
+
+    /// Adds a wait to the end of the route.
+    pub fn wait(self, duration: Duration) -> Self {
+        self.then(Step::Wait(duration))
+    }
+
+    /// Adds a motion with actions running alongside it to the end of the route.
+    pub fn parallel(
+        self,
+        motion: Motion,
+        actions: Vec<(Duration, &'static str, Action<M>)>,
+    ) -> Self {
+        self.then(Step::Parallel { motion, actions })
+    }
+
+    /// Returns the index of the step with the given checkpoint name.
+    pub fn checkpoint_index(&self, name: &str) -> Option<usize> {
+        self.steps
+            .iter()
+            .position(|step| matches!(step, Step::Checkpoint(checkpoint) if *checkpoint == name))
+    }
+
+    /// Runs every step of the route.
+    pub async fn run<R: RouteRobot<Mechanisms = M>>(&self, robot: &mut R) {
+        self.run_steps(robot, 0, &[]).await;
+    }
+
+    /// Runs the route starting at a checkpoint.
+    ///
+    /// If the checkpoint doesn't exist, nothing is run.
+    pub async fn run_from<R: RouteRobot<Mechanisms = M>>(&self, robot: &mut R, checkpoint: &str) {
+        if let Some(start) = self.checkpoint_index(checkpoint) {
+            self.run_steps(robot, start, &[]).await;
+        } else {
+            warn!("[{}] No checkpoint named \"{}\".", self.name, checkpoint);
+        }
+    }
+
+    /// Runs the route, skipping the sections that begin at the given checkpoints.
+    ///
+    /// A skipped section ends at the next checkpoint.
+    pub async fn run_skipping<R: RouteRobot<Mechanisms = M>>(
+        &self,
+        robot: &mut R,
+        checkpoints: &[&str],
+    ) {
+        self.run_steps(robot, 0, checkpoints).await;
+    }
+
+    async fn run_steps<R: RouteRobot<Mechanisms = M>>(
+        &self,
+        robot: &mut R,
+        start: usize,
+        skipped: &[&str],
+    ) {
+        let route_start = Instant::now();
+        let mut skipping = false;
+        let mut section = self.name;
+
+        for (index, step) in self.steps.iter().enumerate().skip(start) {
+            if let Step::Checkpoint(name) = step {
+                skipping = skipped.contains(name);
+                section = name;
+                info!(
+                    "[{}] {} checkpoint \"{}\" at {:?}.",
+                    self.name,
+                    if skipping { "Skipping" } else { "Reached" },
+                    name,
+                    route_start.elapsed()
+                );
+                continue;
+            }
+
+            if skipping {
+                continue;
+            }
+
+            let step_start = Instant::now();
+            Self::run_step(robot, step, section).await;
+            info!(
+                "[{}] Step {}: {} ({:?})",
+                self.name,
+                index,
+                step,
+                step_start.elapsed()
+            );
+        }
+
+        info!("[{}] Finished in {:?}.", self.name, route_start.elapsed());
+    }
+
+    async fn run_step<R: RouteRobot<Mechanisms = M>>(
+        robot: &mut R,
+        step: &Step<M>,
+        section: &'static str,
+    ) {
+        let basic = robot.basic();
+        let seeking = robot.seeking();
+        let report = robot.report().map(|report| (report, section));
+        let (drivetrain, mechanisms) = robot.parts();
+
+        match step {
+            Step::Checkpoint(_) => {}
+            Step::Motion(motion) => motion.run(drivetrain, basic, seeking, report).await,
+            Step::Actuate(_, action) => action(mechanisms),
+            Step::SetPosition(position) => drivetrain.tracking.set_position(*position),
+            Step::Wait(duration) => sleep(*duration).await,
+            Step::Parallel { motion, actions } => {
+                let start = Instant::now();
+
+                let mut actions: Vec<_> = actions.iter().collect();
+                actions.sort_by_key(|(delay, _, _)| *delay);
+
+                join(motion.run(drivetrain, basic, seeking, report), async {
+                    for (delay, _, action) in actions {
+                        if let Some(remaining) = delay.checked_sub(start.elapsed()) {
+                            sleep(remaining).await;
+                        }
+                        action(mechanisms);
+                    }
+                })
+                .await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use evian::prelude::*;
+
+    use super::*;
+    use crate::{
+        motion::MotionOutcome,
+        sim::{SimConfig, SimModel, SimTracking, block_on, simulated_drivetrain},
+    };
+
+    type Log = Vec<&'static str>;
+
+    struct MockRobot {
+        drivetrain: Drivetrain<SimModel, SimTracking>,
+        log: Log,
+        report: AutonReport,
+    }
+
+    impl MockRobot {
+        fn new() -> Self {
+            Self {
+                drivetrain: simulated_drivetrain(
+                    SimConfig::new(6.0, 12.0, 3.25),
+                    (0.0, 0.0),
+                    0.0.deg(),
+                ),
+                log: Vec::new(),
+                report: AutonReport::new(),
+            }
+        }
+    }
+
+    impl RouteRobot for MockRobot {
+        type Model = SimModel;
+        type Tracking = SimTracking;
+        type Mechanisms = Log;
+
+        fn basic(&self) -> Basic<Pid, AngularPid> {
+            Basic {
+                linear_controller: Pid::new(0.1, 0.001, 0.0101, Some(3.0)),
+                angular_controller: AngularPid::new(
+                    3.0,
+                    0.1,
+                    0.125,
+                    Some(Angle::from_degrees(5.0)),
+                ),
+                linear_tolerances: Tolerances::new()
+                    .error(1.0)
+                    .velocity(0.25)
+                    .duration(Duration::from_millis(15)),
+                angular_tolerances: Tolerances::new()
+                    .error(f64::to_radians(8.0))
+                    .velocity(0.05)
+                    .duration(Duration::from_millis(15)),
+                timeout: Some(Duration::from_secs(3)),
+            }
+        }
+
+        fn seeking(&self) -> Seeking<Pid, Pid> {
+            Seeking {
+                linear_controller: Pid::new(0.1, 0.001, 0.0101, Some(3.0)),
+                lateral_controller: Pid::new(0.09, 0.001, 0.004, Some(2.0)),
+                tolerances: Tolerances::new()
+                    .error(1.0)
+                    .duration(Duration::from_millis(100)),
+                timeout: Some(Duration::from_secs(3)),
+            }
+        }
+
+        fn parts(&mut self) -> (&mut Drivetrain<SimModel, SimTracking>, &mut Log) {
+            (&mut self.drivetrain, &mut self.log)
+        }
+
+        fn report(&self) -> Option<AutonReport> {
+            Some(self.report.clone())
+        }
+    }
+
+    fn plan() -> RoutePlan<Log> {
+        RoutePlan::<Log>::new("Test")
+            .actuate("start", |log| log.push("start"))
+            .checkpoint("first")
+            .actuate("a", |log| log.push("a"))
+            .checkpoint("second")
+            .actuate("b", |log| log.push("b"))
+            .wait(Duration::from_millis(1))
+            .actuate("c", |log| log.push("c"))
+            .checkpoint("third")
+            .actuate("d", |log| log.push("d"))
+    }
+
+    #[test]
+    fn run_executes_every_step() {
+        let mut robot = MockRobot::new();
+
+        block_on(plan().run(&mut robot));
+
+        assert_eq!(robot.log, ["start", "a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn run_from_starts_at_checkpoint() {
+        let mut robot = MockRobot::new();
+
+        block_on(plan().run_from(&mut robot, "second"));
+
+        assert_eq!(robot.log, ["b", "c", "d"]);
+    }
+
+    #[test]
+    fn run_from_unknown_checkpoint_runs_nothing() {
+        let mut robot = MockRobot::new();
+
+        block_on(plan().run_from(&mut robot, "missing"));
+
+        assert!(robot.log.is_empty());
+    }
+
+    #[test]
+    fn run_skipping_skips_until_next_checkpoint() {
+        let mut robot = MockRobot::new();
+
+        block_on(plan().run_skipping(&mut robot, &["second"]));
+
+        assert_eq!(robot.log, ["start", "a", "d"]);
+    }
+
+    #[test]
+    fn run_skipping_several_sections() {
+        let mut robot = MockRobot::new();
+
+        block_on(plan().run_skipping(&mut robot, &["first", "third"]));
+
+        assert_eq!(robot.log, ["start", "b", "c"]);
+    }
+
+    #[test]
+    fn motions_are_recorded_under_their_section() {
+        let mut robot = MockRobot::new();
+        let plan = RoutePlan::<Log>::new("Test")
+            .checkpoint("turn")
+            .then(Motion::turn(90.0.deg()))
+            .checkpoint("reset")
+            .set_position((10.0, 20.0));
+
+        block_on(plan.run(&mut robot));
+
+        let records = robot.report.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].name, "turn");
+        assert_eq!(records[0].outcome, MotionOutcome::Settled);
+        assert_eq!(robot.drivetrain.tracking.position(), Vec2::new(10.0, 20.0));
+    }
+
+    #[test]
+    fn motions_that_run_out_of_time_are_recorded_as_timed_out() {
+        let mut robot = MockRobot::new();
+        let plan = RoutePlan::<Log>::new("Test")
+            .checkpoint("drive")
+            .then(Motion::drive(100.0, 0.0.deg()).with_timeout(Duration::from_millis(200)))
+            .checkpoint("seek")
+            .then(Motion::seek((200.0, 0.0)).with_timeout(Duration::from_millis(200)));
+
+        block_on(plan.run(&mut robot));
+
+        let records = robot.report.records();
+        assert_eq!(records.len(), 2);
+        assert!(
+            records
+                .iter()
+                .all(|record| record.outcome == MotionOutcome::TimedOut)
+        );
+    }
+}
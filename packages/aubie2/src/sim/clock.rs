@@ -0,0 +1,113 @@
+use std::{
+    cell::Cell,
+    future::Future,
+    ops::{Add, Sub},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+thread_local! {
+    static NOW: Cell<Duration> = const { Cell::new(Duration::ZERO) };
+}
+
+/// Moves simulated time forward.
+///
+/// [`block_on`](super::block_on) does this while its future is pending, so
+/// this is only needed when stepping a simulation by hand.
+pub fn advance(duration: Duration) {
+    NOW.with(|now| now.set(now.get() + duration));
+}
+
+/// A point in simulated time.
+///
+/// Stands in for [`std::time::Instant`] in motions and routes run by the
+/// simulator. Simulated time starts at zero on each thread and only moves when
+/// it is [advanced](advance).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant(Duration);
+
+impl Instant {
+    /// The start of simulated time.
+    pub const EPOCH: Self = Self(Duration::ZERO);
+
+    pub fn now() -> Self {
+        Self(NOW.with(Cell::get))
+    }
+
+    /// Returns the simulated time passed since this instant.
+    pub fn elapsed(&self) -> Duration {
+        Self::now().duration_since(*self)
+    }
+
+    /// Returns the time from `earlier` to this instant, or zero if `earlier` is
+    /// later.
+    pub fn duration_since(&self, earlier: Self) -> Duration {
+        self.0.saturating_sub(earlier.0)
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Self;
+
+    fn add(self, rhs: Duration) -> Self {
+        Self(self.0 + rhs)
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, rhs: Instant) -> Duration {
+        self.duration_since(rhs)
+    }
+}
+
+/// Future returned by [`sleep`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Sleep(Instant);
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.0 {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Waits until `duration` of simulated time has passed.
+pub fn sleep(duration: Duration) -> Sleep {
+    Sleep(Instant::now() + duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sleep_finishes_once_time_is_advanced() {
+        let mut sleep = sleep(Duration::from_millis(5));
+        let mut cx = Context::from_waker(std::task::Waker::noop());
+
+        advance(Duration::from_millis(4));
+        assert!(Pin::new(&mut sleep).poll(&mut cx).is_pending());
+
+        advance(Duration::from_millis(1));
+        assert!(Pin::new(&mut sleep).poll(&mut cx).is_ready());
+    }
+
+    #[test]
+    fn elapsed_follows_simulated_time() {
+        let start = Instant::now();
+
+        advance(Duration::from_secs(2));
+
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+        assert_eq!(start - Instant::now(), Duration::ZERO);
+    }
+}
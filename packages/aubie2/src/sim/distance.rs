@@ -0,0 +1,58 @@
+use std::{cell::RefCell, rc::Rc};
+
+use evian::{
+    drivetrain::Drivetrain,
+    math::{Angle, Vec2},
+};
+
+use crate::{
+    motion::distance_sensor::ObjectDistance,
+    sim::{SimModel, SimState, SimTracking, synced},
+};
+
+/// Simulated distance sensor that measures the distance to the field perimeter.
+pub struct SimDistanceSensor {
+    state: Rc<RefCell<SimState>>,
+    offset: Vec2<f64>,
+    angle: Angle,
+
+    /// Maximum range of the sensor in inches.
+    pub max_range: f64,
+}
+
+impl SimDistanceSensor {
+    /// Creates a distance sensor mounted on a simulated drivetrain.
+    ///
+    /// `offset` is the sensor's position relative to the center of the robot
+    /// (x forwards, y left) and `angle` is the direction it faces relative to
+    /// the front of the robot.
+    pub fn new(
+        drivetrain: &Drivetrain<SimModel, SimTracking>,
+        offset: impl Into<Vec2<f64>>,
+        angle: Angle,
+    ) -> Self {
+        Self {
+            state: drivetrain.model.state.clone(),
+            offset: offset.into(),
+            angle,
+            max_range: 2000.0 / 25.4,
+        }
+    }
+}
+
+impl ObjectDistance for SimDistanceSensor {
+    fn object_distance(&self) -> Option<f64> {
+        let state = synced(&self.state);
+
+        let (sin, cos) = (state.heading.sin(), state.heading.cos());
+        let origin = state.position
+            + Vec2::new(
+                self.offset.x * cos - self.offset.y * sin,
+                self.offset.x * sin + self.offset.y * cos,
+            );
+        let direction = state.heading + self.angle;
+
+        let (_, distance) = state.config.field.raycast(origin, direction)?;
+        (distance <= self.max_range).then_some(distance)
+    }
+}
@@ -0,0 +1,247 @@
+//! Host-side drivetrain simulation.
+//!
+//! Provides a simulated differential drivetrain and tracking implementation so
+//! that motions and routes can be run on a host machine without a robot.
+//!
+//! Simulated time is independent of the wall clock. It only moves forward while
+//! [`block_on`] waits on a future, and this crate's motions and routes read it
+//! through [`crate::time`], so they produce the same trajectory and outcome no
+//! matter how fast the host runs them. evian's own motions still time
+//! themselves with the wall clock, so runs of those only approximately repeat.
+//!
+//! Only built for host targets, for tests or with the `sim` feature enabled.
+//! Enable `mock-sdk` as well to link without VEXos.
+
+use std::{
+    cell::{Ref, RefCell},
+    future::Future,
+    pin::pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use evian::{
+    drivetrain::Drivetrain,
+    math::{Angle, Vec2},
+};
+
+use crate::{localization::FieldWalls, sim::clock::Instant};
+
+pub mod clock;
+mod distance;
+mod model;
+#[cfg(feature = "mock-sdk")]
+mod sdk;
+mod tracking;
+
+#[cfg(test)]
+mod tests;
+
+pub use distance::SimDistanceSensor;
+pub use model::SimModel;
+pub use tracking::SimTracking;
+
+/// Physical parameters of a simulated drivetrain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimConfig {
+    /// Robot mass in kilograms.
+    pub mass: f64,
+
+    /// Distance between the left and right wheels in inches.
+    pub track_width: f64,
+
+    /// Drive wheel diameter in inches.
+    pub wheel_diameter: f64,
+
+    /// Free speed of the wheels in RPM (after gearing).
+    pub free_speed: f64,
+
+    /// Combined stall torque of one side of the drivetrain at the wheels in N·m.
+    pub stall_torque: f64,
+
+    /// Coulomb friction acceleration opposing motion in in/s².
+    pub friction: f64,
+
+    /// Field perimeter seen by simulated distance sensors.
+    pub field: FieldWalls,
+}
+
+impl SimConfig {
+    /// Creates a configuration for a drivetrain with four 600 RPM motors per
+    /// side geared down to 450 RPM at the wheels, with the given mass, track
+    /// width and wheel diameter.
+    pub const fn new(mass: f64, track_width: f64, wheel_diameter: f64) -> Self {
+        Self {
+            mass,
+            track_width,
+            wheel_diameter,
+            free_speed: 450.0,
+            stall_torque: 4.0 * 0.35 * (600.0 / 450.0),
+            friction: 5.0,
+            field: FieldWalls::STANDARD,
+        }
+    }
+
+    /// Maximum linear velocity of one side of the drivetrain in in/s.
+    pub fn max_velocity(&self) -> f64 {
+        self.free_speed / 60.0 * core::f64::consts::PI * self.wheel_diameter
+    }
+
+    /// Acceleration of one side of the drivetrain at stall in in/s².
+    pub fn stall_acceleration(&self) -> f64 {
+        const METERS_PER_INCH: f64 = 0.0254;
+
+        let wheel_radius = self.wheel_diameter / 2.0 * METERS_PER_INCH;
+        let force = self.stall_torque / wheel_radius;
+
+        // Each side carries half of the robot.
+        force / (self.mass / 2.0) / METERS_PER_INCH
+    }
+}
+
+/// Internal state shared between the simulated model and tracking.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SimState {
+    pub config: SimConfig,
+    pub position: Vec2<f64>,
+    pub heading: Angle,
+    pub left_velocity: f64,
+    pub right_velocity: f64,
+    pub left_travel: f64,
+    pub right_travel: f64,
+    pub left_voltage: f64,
+    pub right_voltage: f64,
+
+    /// When the drivetrain was created.
+    pub created: Instant,
+
+    /// Simulated time since the drivetrain was created that the physics have
+    /// been stepped up to.
+    pub elapsed: Duration,
+}
+
+impl SimState {
+    const STEP: Duration = Duration::from_millis(1);
+
+    /// Steps the physics up to the current simulated time.
+    pub fn sync(&mut self) {
+        let now = self.created.elapsed();
+        if let Some(behind) = now.checked_sub(self.elapsed) {
+            self.advance(behind);
+        }
+    }
+
+    /// Advances the simulation by `duration`, in fixed steps.
+    pub fn advance(&mut self, duration: Duration) {
+        let end = self.elapsed + duration;
+
+        while self.elapsed + Self::STEP <= end {
+            self.step(Self::STEP.as_secs_f64());
+            self.elapsed += Self::STEP;
+        }
+    }
+
+    fn side_acceleration(config: &SimConfig, voltage: f64, velocity: f64) -> f64 {
+        let mut acceleration =
+            config.stall_acceleration() * (voltage / 12.0 - velocity / config.max_velocity());
+
+        if velocity != 0.0 {
+            acceleration -= config.friction * velocity.signum();
+        } else if acceleration.abs() <= config.friction {
+            acceleration = 0.0;
+        } else {
+            acceleration -= config.friction * acceleration.signum();
+        }
+
+        acceleration
+    }
+
+    fn step(&mut self, dt: f64) {
+        let config = self.config;
+
+        for (velocity, voltage, travel) in [
+            (
+                &mut self.left_velocity,
+                self.left_voltage,
+                &mut self.left_travel,
+            ),
+            (
+                &mut self.right_velocity,
+                self.right_voltage,
+                &mut self.right_travel,
+            ),
+        ] {
+            let next = *velocity + Self::side_acceleration(&config, voltage, *velocity) * dt;
+
+            // Friction should stop the wheel rather than reverse it.
+            *velocity = if next.signum() != velocity.signum() && voltage == 0.0 {
+                0.0
+            } else {
+                next
+            };
+            *travel += *velocity * dt;
+        }
+
+        let linear = (self.left_velocity + self.right_velocity) / 2.0;
+        let angular = (self.right_velocity - self.left_velocity) / config.track_width;
+
+        let mid_heading = self.heading + Angle::from_radians(angular * dt / 2.0);
+        self.position += Vec2::new(mid_heading.cos(), mid_heading.sin()) * (linear * dt);
+        self.heading += Angle::from_radians(angular * dt);
+    }
+}
+
+/// Creates a simulated drivetrain starting at `origin` facing `heading`.
+pub fn simulated_drivetrain(
+    config: SimConfig,
+    origin: impl Into<Vec2<f64>>,
+    heading: Angle,
+) -> Drivetrain<SimModel, SimTracking> {
+    let state = Rc::new(RefCell::new(SimState {
+        config,
+        position: origin.into(),
+        heading,
+        left_velocity: 0.0,
+        right_velocity: 0.0,
+        left_travel: 0.0,
+        right_travel: 0.0,
+        left_voltage: 0.0,
+        right_voltage: 0.0,
+        created: Instant::now(),
+        elapsed: Duration::ZERO,
+    }));
+
+    Drivetrain::new(
+        SimModel {
+            state: state.clone(),
+        },
+        SimTracking::new(state),
+    )
+}
+
+/// Steps a simulation up to the current simulated time and borrows it.
+pub(crate) fn synced(state: &RefCell<SimState>) -> Ref<'_, SimState> {
+    state.borrow_mut().sync();
+    state.borrow()
+}
+
+/// Runs a future to completion on the host.
+///
+/// Rather than relying on wakers, this polls the future once per millisecond of
+/// simulated time until it is ready. It also sleeps for a millisecond of wall
+/// clock time between polls, which keeps evian's motions, timed by the wall
+/// clock, roughly in step with the simulation.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+
+        clock::advance(SimState::STEP);
+        std::thread::sleep(SimState::STEP);
+    }
+}
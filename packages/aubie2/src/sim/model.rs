@@ -0,0 +1,56 @@
+use std::{cell::RefCell, convert::Infallible, rc::Rc, time::Duration};
+
+use evian::drivetrain::model::{Arcade, Tank};
+
+use crate::sim::{SimState, synced};
+
+/// Simulated differential drivetrain motors.
+pub struct SimModel {
+    pub(crate) state: Rc<RefCell<SimState>>,
+}
+
+impl SimModel {
+    /// Returns the simulated time since the drivetrain was created.
+    pub fn elapsed(&self) -> Duration {
+        synced(&self.state).elapsed
+    }
+
+    /// Returns the voltages currently applied to the left and right sides.
+    pub fn voltages(&self) -> (f64, f64) {
+        let state = self.state.borrow();
+        (state.left_voltage, state.right_voltage)
+    }
+}
+
+impl Tank for SimModel {
+    type Error = Infallible;
+
+    fn drive_tank(&mut self, left: f64, right: f64) -> Result<(), Self::Error> {
+        let mut state = self.state.borrow_mut();
+
+        // The previous command held until now, and this one holds until the next.
+        state.sync();
+        state.left_voltage = left.clamp(-1.0, 1.0) * 12.0;
+        state.right_voltage = right.clamp(-1.0, 1.0) * 12.0;
+
+        Ok(())
+    }
+}
+
+impl Arcade for SimModel {
+    type Error = Infallible;
+
+    fn drive_arcade(&mut self, throttle: f64, steer: f64) -> Result<(), Self::Error> {
+        let mut left = throttle - steer;
+        let mut right = throttle + steer;
+
+        // Desaturate so that the ratio between sides is preserved.
+        let max = left.abs().max(right.abs());
+        if max > 1.0 {
+            left /= max;
+            right /= max;
+        }
+
+        self.drive_tank(left, right)
+    }
+}
@@ -0,0 +1,32 @@
+//! Stand-ins for the VEX SDK functions reached from host builds.
+//!
+//! On the robot these symbols are provided by VEXos. Host builds have nothing to
+//! link them against, so the `mock-sdk` feature defines them here, reporting no
+//! connected devices and simulated time.
+
+use vex_sdk::V5_DeviceType;
+
+use crate::sim::clock::Instant;
+
+#[unsafe(no_mangle)]
+extern "C" fn vexSystemTimeGet() -> u32 {
+    Instant::now().duration_since(Instant::EPOCH).as_millis() as u32
+}
+
+#[unsafe(no_mangle)]
+extern "C" fn vexSystemHighResTimeGet() -> u64 {
+    Instant::now().duration_since(Instant::EPOCH).as_micros() as u64
+}
+
+#[unsafe(no_mangle)]
+extern "C" fn vexSystemPowerupTimeGet() -> u64 {
+    vexSystemHighResTimeGet()
+}
+
+#[unsafe(no_mangle)]
+extern "C" fn vexTasksRun() {}
+
+#[unsafe(no_mangle)]
+extern "C" fn vexDeviceGetStatus(_devices: *mut V5_DeviceType) -> i32 {
+    0
+}
@@ -0,0 +1,242 @@
+use std::time::Duration;
+
+use evian::{
+    control::loops::{AngularPid, Pid},
+    drivetrain::model::Tank,
+    motion::{Basic, Seeking},
+    prelude::*,
+};
+
+use crate::{
+    motion::{
+        MotionOutcome,
+        basic::BasicExt,
+        distance_sensor::DistanceSensorDriving,
+        report::{AutonReport, MotionRecord},
+    },
+    sim::{
+        SimConfig, SimDistanceSensor, SimModel, SimTracking, block_on, clock, simulated_drivetrain,
+    },
+};
+
+const CONFIG: SimConfig = SimConfig::new(6.0, 12.0, 3.25);
+
+const LINEAR_PID: Pid = Pid::new(0.1, 0.001, 0.0101, Some(3.0));
+const LATERAL_PID: Pid = Pid::new(0.09, 0.001, 0.004, Some(2.0));
+const ANGULAR_PID: AngularPid = AngularPid::new(3.0, 0.1, 0.125, Some(Angle::from_degrees(5.0)));
+
+const LINEAR_TOLERANCES: Tolerances = Tolerances::new()
+    .error(1.0)
+    .velocity(0.25)
+    .duration(Duration::from_millis(15));
+const ANGULAR_TOLERANCES: Tolerances = Tolerances::new()
+    .error(f64::to_radians(8.0))
+    .velocity(0.05)
+    .duration(Duration::from_millis(15));
+
+fn basic() -> Basic<Pid, AngularPid> {
+    Basic {
+        linear_controller: LINEAR_PID,
+        angular_controller: ANGULAR_PID,
+        linear_tolerances: LINEAR_TOLERANCES,
+        angular_tolerances: ANGULAR_TOLERANCES,
+        timeout: Some(Duration::from_secs(5)),
+    }
+}
+
+fn assert_near(actual: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "expected {expected} ± {tolerance}, got {actual}"
+    );
+}
+
+fn assert_heading_near(actual: Angle, expected: Angle, tolerance: f64) {
+    let error = (expected - actual).wrapped_half().as_degrees().abs();
+    assert!(
+        error <= tolerance,
+        "expected {:.1}° ± {tolerance}°, got {:.1}°",
+        expected.as_degrees(),
+        actual.as_degrees()
+    );
+}
+
+fn drivetrain() -> Drivetrain<SimModel, SimTracking> {
+    simulated_drivetrain(CONFIG, (0.0, 0.0), 0.0.deg())
+}
+
+#[test]
+fn commands_hold_until_simulated_time_advances() {
+    let mut dt = drivetrain();
+
+    _ = dt.model.drive_tank(1.0, 1.0);
+    assert_eq!(dt.tracking.true_position().x, 0.0);
+
+    clock::advance(Duration::from_secs(1));
+
+    assert_eq!(dt.model.elapsed(), Duration::from_secs(1));
+    assert!(dt.tracking.true_position().x > 0.0);
+    assert_near(
+        dt.tracking.position().x,
+        dt.tracking.true_position().x,
+        0.01,
+    );
+}
+
+#[test]
+fn basic_drives_distance() {
+    let mut dt = drivetrain();
+
+    block_on(basic().drive_distance_at_heading(&mut dt, 24.0, 0.0.deg()));
+
+    let position = dt.tracking.true_position();
+    assert_near(position.x, 24.0, 1.5);
+    assert_near(position.y, 0.0, 1.0);
+    assert_heading_near(dt.tracking.true_heading(), 0.0.deg(), 8.0);
+}
+
+#[test]
+fn basic_turns_to_heading() {
+    let mut dt = drivetrain();
+
+    block_on(basic().turn_to_heading(&mut dt, 90.0.deg()));
+
+    assert_heading_near(dt.tracking.true_heading(), 90.0.deg(), 8.0);
+    assert_near(dt.tracking.true_position().x, 0.0, 1.0);
+    assert_near(dt.tracking.true_position().y, 0.0, 1.0);
+}
+
+#[test]
+fn seeking_moves_to_point() {
+    let mut dt = drivetrain();
+    let mut seeking = Seeking {
+        linear_controller: LINEAR_PID,
+        lateral_controller: LATERAL_PID,
+        tolerances: Tolerances::new()
+            .error(1.0)
+            .duration(Duration::from_millis(100)),
+        timeout: Some(Duration::from_secs(3)),
+    };
+
+    block_on(seeking.move_to_point(&mut dt, (24.0, 12.0)));
+
+    let position = dt.tracking.true_position();
+    assert_near(position.x, 24.0, 2.0);
+    assert_near(position.y, 12.0, 2.0);
+}
+
+#[test]
+fn drive_at_heading_reports_outcome() {
+    let mut dt = drivetrain();
+    let report = AutonReport::new();
+
+    let result = block_on(
+        basic()
+            .drive_at_heading(&mut dt, 24.0, 0.0.deg())
+            .with_report(&report, "drive"),
+    );
+
+    assert!(result.is_settled(), "{result:?}");
+    assert_near(dt.tracking.true_position().x, 24.0, 1.5);
+    assert_eq!(report.records(), [MotionRecord::new("drive", result)]);
+}
+
+#[test]
+fn turn_to_reports_outcome() {
+    let mut dt = drivetrain();
+    let report = AutonReport::new();
+
+    let result = block_on(
+        basic()
+            .turn_to(&mut dt, 90.0.deg())
+            .with_report(&report, "turn"),
+    );
+
+    assert!(result.is_settled(), "{result:?}");
+    assert!(result.linear_error.is_nan());
+    assert_heading_near(dt.tracking.true_heading(), 90.0.deg(), 8.0);
+    assert_eq!(report.records(), [MotionRecord::new("turn", result)]);
+}
+
+#[test]
+fn drive_at_heading_times_out() {
+    let mut dt = drivetrain();
+
+    let result = block_on(
+        basic()
+            .drive_at_heading(&mut dt, 100.0, 0.0.deg())
+            .with_timeout(Duration::from_millis(200)),
+    );
+
+    assert_eq!(result.outcome, MotionOutcome::TimedOut);
+}
+
+#[test]
+fn motions_repeat_exactly() {
+    let run = || {
+        let mut dt = drivetrain();
+        let result = block_on(basic().drive_to_x(&mut dt, 30.0, 0.0.deg()));
+        (result, dt.tracking.true_position())
+    };
+
+    assert_eq!(run(), run());
+}
+
+#[test]
+fn drive_to_x_settles_on_coordinate() {
+    let mut dt = drivetrain();
+
+    let result = block_on(basic().drive_to_x(&mut dt, 30.0, 0.0.deg()));
+
+    assert!(result.is_settled(), "{result:?}");
+    assert_near(dt.tracking.true_position().x, 30.0, 1.5);
+    assert_heading_near(dt.tracking.true_heading(), 0.0.deg(), 8.0);
+}
+
+#[test]
+fn boomerang_does_not_settle_facing_across_target() {
+    // Facing straight up with the target off to the right, the error along the
+    // robot's heading starts out near zero.
+    let mut dt = simulated_drivetrain(CONFIG, (0.0, 0.0), 90.0.deg());
+
+    let result = block_on(basic().move_to_pose(&mut dt, (24.0, 0.0), 0.0.deg()));
+
+    let position = dt.tracking.true_position();
+    assert!(result.is_settled(), "{result:?}");
+    assert!(result.linear_error <= 2.0, "{result:?}");
+    assert_near(position.x, 24.0, 2.0);
+    assert_near(position.y, 0.0, 2.0);
+}
+
+#[test]
+#[should_panic]
+fn arc_rejects_zero_radius() {
+    let mut dt = drivetrain();
+
+    _ = basic().arc_to_heading(&mut dt, 90.0.deg(), 0.0);
+}
+
+#[test]
+fn distance_sensor_driving_stops_at_wall_distance() {
+    let mut dt = drivetrain();
+
+    // Facing backwards towards the wall at the minimum x-coordinate, so
+    // reducing the measured distance drives the robot backwards.
+    let sensor = SimDistanceSensor::new(&dt, (0.0, 0.0), 180.0.deg());
+    let mut driving = DistanceSensorDriving {
+        linear_controller: LINEAR_PID,
+        angular_controller: ANGULAR_PID,
+        linear_tolerances: LINEAR_TOLERANCES,
+        angular_tolerances: ANGULAR_TOLERANCES,
+        timeout: Some(Duration::from_secs(5)),
+    };
+
+    let result = block_on(driving.drive_to_distance(&mut dt, &sensor, 20.0, 0.0.deg()));
+
+    assert!(result.is_settled(), "{result:?}");
+    assert_near(
+        dt.tracking.true_position().x,
+        CONFIG.field.min.x + 20.0,
+        1.5,
+    );
+}
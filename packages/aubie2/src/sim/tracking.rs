@@ -0,0 +1,115 @@
+use std::{cell::RefCell, rc::Rc};
+
+use evian::{
+    math::{Angle, Vec2},
+    tracking::{TracksForwardTravel, TracksHeading, TracksPosition, TracksVelocity},
+};
+
+use crate::{
+    localization::SetPosition,
+    sim::{SimState, synced},
+};
+
+struct OdometryState {
+    position: Vec2<f64>,
+    heading_offset: Angle,
+    prev_forward_travel: f64,
+}
+
+/// Simulated wheel odometry.
+///
+/// Position is integrated from the simulated wheel travel the same way
+/// `WheeledTracking` integrates encoder readings, so it can be reset
+/// independently of the robot's true pose in the simulation.
+pub struct SimTracking {
+    state: Rc<RefCell<SimState>>,
+    odometry: RefCell<OdometryState>,
+}
+
+impl SimTracking {
+    pub(crate) fn new(state: Rc<RefCell<SimState>>) -> Self {
+        let position = state.borrow().position;
+
+        Self {
+            state,
+            odometry: RefCell::new(OdometryState {
+                position,
+                heading_offset: Angle::from_radians(0.0),
+                prev_forward_travel: 0.0,
+            }),
+        }
+    }
+
+    /// Integrates wheel travel since the last update into the tracked position.
+    fn update(&self) {
+        let state = synced(&self.state);
+        let mut odometry = self.odometry.borrow_mut();
+        let forward_travel = (state.left_travel + state.right_travel) / 2.0;
+        let heading = state.heading + odometry.heading_offset;
+
+        let delta = forward_travel - odometry.prev_forward_travel;
+        odometry.position += Vec2::new(heading.cos(), heading.sin()) * delta;
+        odometry.prev_forward_travel = forward_travel;
+    }
+
+    /// Sets the tracked position without moving the simulated robot.
+    pub fn set_position(&mut self, position: impl Into<Vec2<f64>>) {
+        self.update();
+        self.odometry.borrow_mut().position = position.into();
+    }
+
+    /// Sets the tracked heading without rotating the simulated robot.
+    pub fn set_heading(&mut self, heading: Angle) {
+        self.update();
+        let true_heading = synced(&self.state).heading;
+        self.odometry.borrow_mut().heading_offset = heading - true_heading;
+    }
+
+    /// Returns the robot's true position in the simulated field.
+    pub fn true_position(&self) -> Vec2<f64> {
+        synced(&self.state).position
+    }
+
+    /// Returns the robot's true heading in the simulated field.
+    pub fn true_heading(&self) -> Angle {
+        synced(&self.state).heading
+    }
+}
+
+impl TracksPosition for SimTracking {
+    fn position(&self) -> Vec2<f64> {
+        self.update();
+        self.odometry.borrow().position
+    }
+}
+
+impl TracksHeading for SimTracking {
+    fn heading(&self) -> Angle {
+        (synced(&self.state).heading + self.odometry.borrow().heading_offset).wrapped_full()
+    }
+}
+
+impl TracksVelocity for SimTracking {
+    fn linear_velocity(&self) -> f64 {
+        let state = synced(&self.state);
+        (state.left_velocity + state.right_velocity) / 2.0
+    }
+
+    fn angular_velocity(&self) -> f64 {
+        let state = synced(&self.state);
+        (state.right_velocity - state.left_velocity) / state.config.track_width
+    }
+}
+
+impl TracksForwardTravel for SimTracking {
+    fn forward_travel(&self) -> f64 {
+        let state = synced(&self.state);
+        (state.left_travel + state.right_travel) / 2.0
+    }
+}
+
+impl SetPosition for SimTracking {
+    fn set_position(&mut self, position: Vec2<f64>) {
+        SimTracking::set_position(self, position);
+    }
+}
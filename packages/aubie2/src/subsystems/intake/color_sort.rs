@@ -0,0 +1,660 @@
+//! Optical color sorting.
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+use vexide::{
+    controller::Controller, math::Angle, prelude::OpticalSensor, smart::SmartDevice, time::sleep,
+};
+
+use super::{ElementColor, IntakeStage, STAGES, StageMotors, occupancy::Occupancy};
+use crate::hardware::pneumatics::Piston;
+
+/// A range of hues in degrees.
+///
+/// If `start` is greater than `end`, the range wraps through 0°.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HueRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl HueRange {
+    pub const fn new(start: f64, end: f64) -> Self {
+        Self { start, end }
+    }
+
+    /// Creates a range spanning `half_width` degrees either side of `center`.
+    pub fn around(center: f64, half_width: f64) -> Self {
+        Self {
+            start: (center - half_width).rem_euclid(360.0),
+            end: (center + half_width).rem_euclid(360.0),
+        }
+    }
+
+    pub fn contains(&self, hue: f64) -> bool {
+        if self.start <= self.end {
+            (self.start..=self.end).contains(&hue)
+        } else {
+            hue >= self.start || hue <= self.end
+        }
+    }
+}
+
+/// Tuning for the intake's color sort.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorSortConfig {
+    pub red_hue: HueRange,
+    pub blue_hue: HueRange,
+
+    /// Proximity above which a block is considered to be in front of the sensor.
+    pub proximity_threshold: f64,
+
+    /// How long after a block is first seen that its hue is trusted.
+    pub proximity_window: Duration,
+
+    /// The stage that carries blocks from the sensor to the ejector.
+    pub conveyor: IntakeStage,
+
+    /// Conveyor rotation between a block passing the sensor and reaching the ejector.
+    pub eject_offset: Angle,
+
+    /// Conveyor rotation the ejector is held open for.
+    ///
+    /// This is negative if the conveyor's encoder counts down while it carries
+    /// blocks towards the ejector.
+    pub eject_travel: Angle,
+
+    /// Longest the ejector is held open if the conveyor stops partway through an
+    /// ejection.
+    pub eject_timeout: Duration,
+
+    /// How long the ejector is held open after a rejected block is seen when the
+    /// conveyor's position can't be read.
+    pub eject_duration: Duration,
+}
+
+impl Default for ColorSortConfig {
+    fn default() -> Self {
+        Self {
+            red_hue: HueRange::new(340.0, 20.0),
+            blue_hue: HueRange::new(200.0, 250.0),
+            proximity_threshold: 0.3,
+            proximity_window: Duration::from_millis(20),
+            conveyor: IntakeStage::BACK_BOTTOM,
+            eject_offset: Angle::from_degrees(0.0),
+            eject_travel: Angle::from_degrees(720.0),
+            eject_timeout: Duration::from_millis(1500),
+            eject_duration: Duration::from_millis(1000),
+        }
+    }
+}
+
+/// What the color sort decided about a block.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SortDecision {
+    /// The block is the alliance color and is kept.
+    Keep,
+
+    /// The block is the rejected color and is ejected.
+    Reject,
+
+    /// The hue matched neither color.
+    Unknown,
+}
+
+/// What the color sort did with the ejector in response to a detection.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum EjectorAction {
+    /// The ejector was left alone.
+    None,
+
+    /// An ejection was scheduled (or rescheduled) for this block.
+    Scheduled,
+
+    /// A pending ejection was cancelled because a kept block followed it.
+    Cancelled,
+}
+
+/// A single color sort detection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SortEvent {
+    pub timestamp: Instant,
+
+    /// Hue in degrees.
+    pub hue: f64,
+    pub proximity: f64,
+    pub reject_color: ElementColor,
+    pub decision: SortDecision,
+    pub action: EjectorAction,
+}
+
+impl SortEvent {
+    /// Header matching the columns written by [`SortEvent::to_csv`].
+    pub const CSV_HEADER: &str = "uptime_ms,hue,proximity,reject_color,decision,action";
+
+    /// Formats the event as a CSV row.
+    ///
+    /// Timestamps are converted to milliseconds of program uptime so they line up
+    /// with [`RobotLogger`](crate::logger::RobotLogger) output.
+    pub fn to_csv(&self) -> String {
+        let uptime = vexide::time::user_uptime().saturating_sub(self.timestamp.elapsed());
+
+        format!(
+            "{},{:.1},{:.3},{:?},{:?},{:?}",
+            uptime.as_millis(),
+            self.hue,
+            self.proximity,
+            self.reject_color,
+            self.decision,
+            self.action
+        )
+    }
+}
+
+#[derive(Default)]
+pub(super) struct SortLog {
+    pub(super) events: VecDeque<SortEvent>,
+}
+
+impl SortLog {
+    const CAPACITY: usize = 128;
+
+    fn push(&mut self, event: SortEvent) {
+        if self.events.len() == Self::CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+}
+
+/// Returns the average position of a stage's motors in degrees.
+fn conveyor_position(stages: &RefCell<[StageMotors; 4]>, conveyor: IntakeStage) -> Option<f64> {
+    let stages = stages.borrow();
+    let (_, motors) = STAGES
+        .iter()
+        .zip(stages.iter())
+        .find(|(flag, _)| conveyor.contains(**flag))?;
+
+    let mut position = 0.0;
+    for motor in &motors.motors {
+        position += motor.position().ok()?.as_degrees();
+    }
+
+    (!motors.motors.is_empty()).then(|| position / motors.motors.len() as f64)
+}
+
+/// A pending ejection.
+///
+/// The window is measured in conveyor degrees travelled forwards since the block
+/// was detected, so running the conveyor backwards (e.g. to clear a jam) moves
+/// the block away from the ejector rather than towards it. If the conveyor's
+/// position can't be read, the ejector is instead held open for a fixed time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct EjectWindow {
+    /// When the block was detected.
+    detected: Instant,
+
+    /// Conveyor position when the block was detected.
+    start: Option<f64>,
+
+    /// When the ejector was first opened for this window.
+    opened_at: Option<Instant>,
+
+    /// Rejected blocks that this window will eject, including any carried over
+    /// from a window it replaced.
+    blocks: usize,
+}
+
+/// How far along an [`EjectWindow`] is.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum EjectProgress {
+    /// The block hasn't reached the ejector yet.
+    Waiting,
+
+    /// The ejector should be open.
+    Open,
+
+    /// The conveyor carried the block all the way past the ejector.
+    Ejected,
+
+    /// The conveyor stalled before the block passed the ejector.
+    Abandoned,
+}
+
+impl EjectWindow {
+    /// Returns how far the conveyor has carried the block forwards since it was
+    /// detected, in degrees.
+    fn travel(&self, position: f64, config: &ColorSortConfig) -> Option<f64> {
+        let direction = config.eject_travel.as_degrees().signum();
+        self.start.map(|start| (position - start) * direction)
+    }
+
+    /// Advances the window to the conveyor's current position.
+    fn progress(
+        &mut self,
+        position: Option<f64>,
+        config: &ColorSortConfig,
+        now: Instant,
+    ) -> EjectProgress {
+        let Some(travel) = position.and_then(|position| self.travel(position, config)) else {
+            return if now.duration_since(self.detected) < config.eject_duration {
+                self.opened_at.get_or_insert(now);
+                EjectProgress::Open
+            } else {
+                EjectProgress::Ejected
+            };
+        };
+
+        let open = config.eject_offset.as_degrees().abs();
+        let close = open + config.eject_travel.as_degrees().abs();
+
+        if travel >= close {
+            EjectProgress::Ejected
+        } else if self
+            .opened_at
+            .is_some_and(|opened_at| now.duration_since(opened_at) >= config.eject_timeout)
+        {
+            EjectProgress::Abandoned
+        } else if travel >= open {
+            self.opened_at.get_or_insert(now);
+            EjectProgress::Open
+        } else {
+            EjectProgress::Waiting
+        }
+    }
+}
+
+pub(super) async fn task(
+    mut optical: OpticalSensor,
+    mut ejector: Piston,
+    config: ColorSortConfig,
+    stages: Rc<RefCell<[StageMotors; 4]>>,
+    reject_color: Rc<RefCell<Option<ElementColor>>>,
+    emergency_override: Rc<RefCell<bool>>,
+    occupancy: Rc<RefCell<Occupancy>>,
+    log: Rc<RefCell<SortLog>>,
+) {
+    _ = optical.set_integration_time(Duration::from_millis(2));
+    _ = optical.set_led_brightness(1.0);
+
+    let mut prox_timestamp = Instant::now();
+    let mut in_prox = false;
+    let mut was_near = false;
+    let mut proximity = 0.0;
+
+    // Whether the block currently in front of the sensor has an ejection scheduled.
+    let mut scheduled_block = false;
+
+    // The ejection scheduled for the most recent rejected block.
+    let mut eject_window: Option<EjectWindow> = None;
+
+    loop {
+        if let Ok(prox) = optical.proximity() {
+            proximity = prox;
+            let near = prox > config.proximity_threshold;
+
+            if near && !was_near {
+                prox_timestamp = Instant::now();
+                in_prox = true;
+                scheduled_block = false;
+                occupancy.borrow_mut().count.entered += 1;
+            }
+
+            if in_prox && prox_timestamp.elapsed() > config.proximity_window {
+                in_prox = false;
+            }
+
+            was_near = near;
+        }
+
+        if let Some(reject_color) = *reject_color.borrow()
+            && let Ok(hue) = optical.hue()
+            && in_prox
+        {
+            let is_seeing_blue = config.blue_hue.contains(hue);
+            let is_seeing_red = config.red_hue.contains(hue);
+
+            let (is_seeing_bad, is_seeing_good) = match reject_color {
+                ElementColor::Blue => (is_seeing_blue, is_seeing_red),
+                ElementColor::Red => (is_seeing_red, is_seeing_blue),
+            };
+
+            let mut action = EjectorAction::None;
+
+            if is_seeing_bad {
+                action = EjectorAction::Scheduled;
+
+                // Blocks still waiting on the previous window are ejected by this one.
+                let blocks =
+                    eject_window.map_or(0, |window| window.blocks) + usize::from(!scheduled_block);
+                scheduled_block = true;
+
+                eject_window = Some(EjectWindow {
+                    detected: Instant::now(),
+                    start: conveyor_position(&stages, config.conveyor),
+                    opened_at: None,
+                    blocks,
+                });
+            }
+
+            if is_seeing_good && eject_window.is_some() {
+                action = EjectorAction::Cancelled;
+                eject_window = None;
+                _ = ejector.retract();
+            }
+
+            log.borrow_mut().push(SortEvent {
+                timestamp: Instant::now(),
+                hue,
+                proximity,
+                reject_color,
+                decision: if is_seeing_bad {
+                    SortDecision::Reject
+                } else if is_seeing_good {
+                    SortDecision::Keep
+                } else {
+                    SortDecision::Unknown
+                },
+                action,
+            });
+        }
+
+        if *emergency_override.borrow() {
+            if !ejector.is_extended() {
+                _ = ejector.extend();
+            }
+        } else if let Some(window) = &mut eject_window {
+            let blocks = window.blocks;
+
+            match window.progress(
+                conveyor_position(&stages, config.conveyor),
+                &config,
+                Instant::now(),
+            ) {
+                EjectProgress::Waiting => {}
+                EjectProgress::Open => _ = ejector.extend(),
+                EjectProgress::Ejected => {
+                    occupancy.borrow_mut().count.ejected += blocks;
+                    eject_window = None;
+                    _ = ejector.retract();
+                }
+                EjectProgress::Abandoned => {
+                    warn!("Ejection abandoned before the conveyor reached the ejector.");
+                    eject_window = None;
+                    _ = ejector.retract();
+                }
+            }
+        } else if ejector.is_extended() {
+            _ = ejector.retract();
+        }
+
+        sleep(OpticalSensor::UPDATE_INTERVAL).await;
+    }
+}
+
+/// Collects hue samples of a block held in front of the sensor.
+async fn sample_hues(
+    controller: &mut Controller,
+    optical: &mut OpticalSensor,
+    prompt: &str,
+    proximity_threshold: f64,
+) -> Vec<f64> {
+    const SAMPLES: usize = 50;
+    const TIMEOUT: Duration = Duration::from_secs(15);
+
+    info!("{prompt}");
+    _ = controller.set_text(format!("{prompt:<16}"), 1, 1).await;
+
+    let start = Instant::now();
+    let mut hues = Vec::with_capacity(SAMPLES);
+
+    while hues.len() < SAMPLES && start.elapsed() < TIMEOUT {
+        if optical
+            .proximity()
+            .is_ok_and(|prox| prox > proximity_threshold)
+            && let Ok(hue) = optical.hue()
+        {
+            hues.push(hue);
+        }
+
+        sleep(OpticalSensor::UPDATE_INTERVAL).await;
+    }
+
+    hues
+}
+
+/// Fits a hue range to a set of samples, wrapping through 0° if needed.
+fn fit_range(hues: &[f64], margin: f64) -> Option<HueRange> {
+    if hues.is_empty() {
+        return None;
+    }
+
+    // Circular mean, so that samples either side of 0° average correctly.
+    let (sin, cos) = hues.iter().fold((0.0, 0.0), |(sin, cos), hue| {
+        (sin + hue.to_radians().sin(), cos + hue.to_radians().cos())
+    });
+    let mean = f64::atan2(sin, cos).to_degrees().rem_euclid(360.0);
+
+    let spread = hues
+        .iter()
+        .map(|hue| ((hue - mean + 540.0).rem_euclid(360.0) - 180.0).abs())
+        .fold(0.0, f64::max);
+
+    Some(HueRange::around(mean, spread + margin))
+}
+
+/// Derives hue ranges by sampling a red and a blue block held in front of the
+/// optical sensor, prompting for each on the controller.
+///
+/// Ranges that couldn't be sampled are left unchanged. This should be run before
+/// the sensor is handed to [`Intake::with_color_sort`](super::Intake::with_color_sort).
+pub async fn calibrate_color_sort(
+    controller: &mut Controller,
+    optical: &mut OpticalSensor,
+    mut config: ColorSortConfig,
+) -> ColorSortConfig {
+    const MARGIN: f64 = 8.0;
+
+    _ = optical.set_integration_time(Duration::from_millis(2));
+    _ = optical.set_led_brightness(1.0);
+
+    let red = sample_hues(
+        controller,
+        optical,
+        "Show red block",
+        config.proximity_threshold,
+    )
+    .await;
+    match fit_range(&red, MARGIN) {
+        Some(range) => config.red_hue = range,
+        None => warn!("No red samples collected, keeping {:?}.", config.red_hue),
+    }
+
+    sleep(Duration::from_secs(1)).await;
+
+    let blue = sample_hues(
+        controller,
+        optical,
+        "Show blue block",
+        config.proximity_threshold,
+    )
+    .await;
+    match fit_range(&blue, MARGIN) {
+        Some(range) => config.blue_hue = range,
+        None => warn!("No blue samples collected, keeping {:?}.", config.blue_hue),
+    }
+
+    info!(
+        "Color sort calibrated: red {:.0}°-{:.0}°, blue {:.0}°-{:.0}°.",
+        config.red_hue.start, config.red_hue.end, config.blue_hue.start, config.blue_hue.end
+    );
+    _ = controller.set_text("Sort calibrated ", 1, 1).await;
+
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Opens 90° after detection and closes 720° later.
+    fn config(eject_travel: f64) -> ColorSortConfig {
+        ColorSortConfig {
+            eject_offset: Angle::from_degrees(90.0),
+            eject_travel: Angle::from_degrees(eject_travel),
+            ..Default::default()
+        }
+    }
+
+    fn window(start: Option<f64>, detected: Instant) -> EjectWindow {
+        EjectWindow {
+            detected,
+            start,
+            opened_at: None,
+            blocks: 1,
+        }
+    }
+
+    #[test]
+    fn travel_counts_forward_rotation() {
+        let config = config(720.0);
+        let window = window(Some(100.0), Instant::now());
+
+        assert_eq!(window.travel(100.0, &config), Some(0.0));
+        assert_eq!(window.travel(190.0, &config), Some(90.0));
+        assert_eq!(window.travel(910.0, &config), Some(810.0));
+    }
+
+    #[test]
+    fn travel_counts_reversed_encoders() {
+        let config = config(-720.0);
+        let window = window(Some(100.0), Instant::now());
+
+        assert_eq!(window.travel(10.0, &config), Some(90.0));
+        assert_eq!(window.travel(-710.0, &config), Some(810.0));
+    }
+
+    #[test]
+    fn reversing_the_conveyor_moves_blocks_away_from_the_ejector() {
+        let config = config(720.0);
+        let now = Instant::now();
+        let mut window = window(Some(100.0), now);
+
+        assert_eq!(window.travel(-500.0, &config), Some(-600.0));
+        assert_eq!(
+            window.progress(Some(-500.0), &config, now),
+            EjectProgress::Waiting
+        );
+        assert_eq!(
+            window.progress(Some(50.0), &config, now),
+            EjectProgress::Waiting
+        );
+        assert_eq!(
+            window.progress(Some(190.0), &config, now),
+            EjectProgress::Open
+        );
+    }
+
+    #[test]
+    fn windows_eject_once_the_conveyor_passes_the_ejector() {
+        let config = config(720.0);
+        let now = Instant::now();
+        let mut window = window(Some(100.0), now);
+
+        assert_eq!(
+            window.progress(Some(150.0), &config, now),
+            EjectProgress::Waiting
+        );
+        assert_eq!(
+            window.progress(Some(190.0), &config, now),
+            EjectProgress::Open
+        );
+        assert_eq!(window.opened_at, Some(now));
+        assert_eq!(
+            window.progress(Some(910.0), &config, now + Duration::from_millis(500)),
+            EjectProgress::Ejected
+        );
+    }
+
+    #[test]
+    fn stalled_windows_are_abandoned() {
+        let config = config(720.0);
+        let now = Instant::now();
+        let mut window = window(Some(100.0), now);
+
+        assert_eq!(
+            window.progress(Some(400.0), &config, now),
+            EjectProgress::Open
+        );
+        assert_eq!(
+            window.progress(Some(400.0), &config, now + config.eject_timeout),
+            EjectProgress::Abandoned
+        );
+    }
+
+    #[test]
+    fn windows_without_a_conveyor_position_are_timed() {
+        let config = config(720.0);
+        let now = Instant::now();
+
+        for mut window in [window(None, now), window(Some(100.0), now)] {
+            assert_eq!(window.progress(None, &config, now), EjectProgress::Open);
+            assert_eq!(
+                window.progress(None, &config, now + config.eject_duration),
+                EjectProgress::Ejected
+            );
+        }
+    }
+
+    #[test]
+    fn hue_ranges_wrap_past_360() {
+        let red = HueRange::new(340.0, 20.0);
+        assert!(red.contains(350.0));
+        assert!(red.contains(0.0));
+        assert!(red.contains(10.0));
+        assert!(!red.contains(180.0));
+
+        let blue = HueRange::new(200.0, 250.0);
+        assert!(blue.contains(225.0));
+        assert!(!blue.contains(10.0));
+        assert!(!blue.contains(350.0));
+    }
+
+    #[test]
+    fn around_wraps_through_zero() {
+        let range = HueRange::around(5.0, 10.0);
+        assert_eq!(range.start, 355.0);
+        assert_eq!(range.end, 15.0);
+        assert!(range.contains(359.0) && range.contains(0.0) && range.contains(12.0));
+    }
+
+    #[test]
+    fn fitted_ranges_cover_their_samples() {
+        let range = fit_range(&[210.0, 220.0, 230.0], 8.0).unwrap();
+        assert!((range.start - 202.0).abs() < 1e-9);
+        assert!((range.end - 238.0).abs() < 1e-9);
+        assert!(!range.contains(245.0));
+    }
+
+    #[test]
+    fn fitted_ranges_wrap_through_zero() {
+        let hues = [350.0, 355.0, 5.0, 10.0];
+        let range = fit_range(&hues, 8.0).unwrap();
+
+        assert!(range.start > range.end);
+        assert!(hues.iter().all(|&hue| range.contains(hue)));
+        assert!(range.contains(0.0));
+        assert!(!range.contains(180.0));
+    }
+
+    #[test]
+    fn nothing_is_fitted_without_samples() {
+        assert_eq!(fit_range(&[], 8.0), None);
+    }
+}
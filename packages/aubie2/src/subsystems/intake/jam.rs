@@ -0,0 +1,198 @@
+//! Intake jam detection and recovery.
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use log::warn;
+use vexide::time::sleep;
+
+use super::{IntakeMode, IntakeStage, STAGES, StageCommand, StageMotors};
+
+/// Thresholds for detecting a jammed stage and how to recover from it.
+///
+/// A stage is considered jammed when it has been commanded at least
+/// `min_voltage`, yet has spun slower than `max_velocity` while drawing at least
+/// `min_current` or producing at least `min_torque` for `detection_time`. It is
+/// then reversed at `reverse_voltage` for `reverse_duration` before resuming.
+///
+/// Detection is paused while the intake is in one of `ignored_modes`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JamConfig {
+    /// Stages that are monitored.
+    pub stages: IntakeStage,
+
+    /// Modes in which stages are expected to stall, such as pushing stored
+    /// blocks against a closed hood.
+    pub ignored_modes: &'static [IntakeMode],
+
+    /// Smallest commanded voltage magnitude a stage is monitored at.
+    pub min_voltage: f64,
+
+    /// Velocity in RPM below which a stage is considered stalled.
+    pub max_velocity: f64,
+
+    /// Current in amps above which a stalled stage is considered jammed.
+    pub min_current: f64,
+
+    /// Torque in Nm above which a stalled stage is considered jammed.
+    pub min_torque: f64,
+
+    /// How long a stage must be stalled before it is considered jammed.
+    pub detection_time: Duration,
+
+    /// Voltage magnitude to reverse a jammed stage at.
+    pub reverse_voltage: f64,
+
+    /// How long to reverse a jammed stage for.
+    pub reverse_duration: Duration,
+}
+
+impl JamConfig {
+    /// Creates the default thresholds, usable in `const` robot configs.
+    pub const fn new() -> Self {
+        Self {
+            stages: IntakeStage::FRONT_BOTTOM.union(IntakeStage::BACK_BOTTOM),
+            ignored_modes: &[IntakeMode::Store, IntakeMode::Idle],
+            min_voltage: 6.0,
+            max_velocity: 20.0,
+            min_current: 2.0,
+            min_torque: 0.6,
+            detection_time: Duration::from_millis(150),
+            reverse_voltage: 12.0,
+            reverse_duration: Duration::from_millis(200),
+        }
+    }
+}
+
+impl Default for JamConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A detected jam.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JamEvent {
+    pub stage: IntakeStage,
+    pub timestamp: Instant,
+
+    /// Voltage the stage was commanded at when it jammed.
+    pub voltage: f64,
+
+    /// Average velocity of the stage's motors in RPM.
+    pub velocity: f64,
+
+    /// Average current of the stage's motors in amps.
+    pub current: f64,
+
+    /// Average torque of the stage's motors in Nm.
+    pub torque: f64,
+}
+
+#[derive(Default)]
+pub(super) struct JamLog {
+    pub(super) events: VecDeque<JamEvent>,
+    pub(super) count: usize,
+}
+
+impl JamLog {
+    const CAPACITY: usize = 16;
+
+    fn push(&mut self, event: JamEvent) {
+        if self.events.len() == Self::CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+        self.count += 1;
+    }
+}
+
+/// Averages the velocity, current and torque of a stage's motors.
+fn sample(motors: &StageMotors) -> Option<(f64, f64, f64)> {
+    let mut velocity = 0.0;
+    let mut current = 0.0;
+    let mut torque = 0.0;
+
+    for motor in &motors.motors {
+        velocity += motor.velocity().ok()?;
+        current += motor.current().ok()?;
+        torque += motor.torque().ok()?;
+    }
+
+    let count = motors.motors.len() as f64;
+    (count > 0.0).then(|| (velocity / count, current / count, torque / count))
+}
+
+pub(super) async fn task(
+    stages: Rc<RefCell<[StageMotors; 4]>>,
+    jams: Rc<RefCell<JamLog>>,
+    mode: Rc<RefCell<IntakeMode>>,
+    config: JamConfig,
+) {
+    loop {
+        let ignored = config.ignored_modes.contains(&*mode.borrow());
+
+        for (flag, motors) in STAGES.iter().zip(stages.borrow_mut().iter_mut()) {
+            if !config.stages.contains(*flag) {
+                continue;
+            }
+
+            if let Some(until) = motors.recovering_until {
+                if Instant::now() >= until {
+                    motors.recovering_until = None;
+                    motors.stalled_since = None;
+                    _ = motors.apply();
+                }
+                continue;
+            }
+
+            let StageCommand::Voltage(voltage) = motors.command else {
+                motors.stalled_since = None;
+                continue;
+            };
+            if ignored || voltage.abs() < config.min_voltage {
+                motors.stalled_since = None;
+                continue;
+            }
+
+            let Some((velocity, current, torque)) = sample(motors) else {
+                continue;
+            };
+            let stalled = velocity.abs() < config.max_velocity
+                && (current >= config.min_current || torque >= config.min_torque);
+
+            if !stalled {
+                motors.stalled_since = None;
+                continue;
+            }
+
+            let stalled_since = *motors.stalled_since.get_or_insert_with(Instant::now);
+            if stalled_since.elapsed() >= config.detection_time {
+                warn!(
+                    "Intake stage {:?} jammed at {:.1} V ({:.0} RPM, {:.2} A, {:.2} Nm), reversing.",
+                    flag, voltage, velocity, current, torque
+                );
+
+                jams.borrow_mut().push(JamEvent {
+                    stage: *flag,
+                    timestamp: Instant::now(),
+                    voltage,
+                    velocity,
+                    current,
+                    torque,
+                });
+
+                _ = motors.write(StageCommand::Voltage(
+                    -voltage.signum() * config.reverse_voltage,
+                ));
+                motors.recovering_until = Some(Instant::now() + config.reverse_duration);
+            }
+        }
+
+        sleep(Duration::from_millis(10)).await;
+    }
+}
@@ -0,0 +1,580 @@
+use std::{
+    cell::RefCell,
+    fs::File,
+    io::{self, Write},
+    path::Path,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+pub use color_sort::{
+    ColorSortConfig, EjectorAction, HueRange, SortDecision, SortEvent, calibrate_color_sort,
+};
+pub use jam::{JamConfig, JamEvent};
+pub use occupancy::{BlockCount, BlockSensor, BlockSensorRole};
+
+use log::info;
+use vexide::{
+    prelude::OpticalSensor,
+    smart::{
+        PortError,
+        motor::{BrakeMode, Motor},
+    },
+    task::{Task, spawn},
+    time::sleep,
+};
+
+use crate::hardware::pneumatics::Piston;
+
+mod color_sort;
+mod jam;
+mod occupancy;
+
+#[derive(Default, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum HoodPosition {
+    High,
+    Half,
+    #[default]
+    Closed,
+}
+
+/// Game element color
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ElementColor {
+    /// Reject blue rings
+    Blue,
+
+    /// Reject red rings
+    Red,
+}
+
+bitflags::bitflags! {
+    /// Intake stages for controlling different parts of the intake separately
+    #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+    pub struct IntakeStage: u8 {
+        const FRONT_BOTTOM = 1 << 0;
+        const BACK_BOTTOM = 1 << 1;
+        const BACK_TOP = 1 << 2;
+        const FRONT_TOP = 1 << 3;
+    }
+}
+
+/// Voltage applied to each stage of the intake.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StageVoltages {
+    pub front_bottom: f64,
+    pub back_bottom: f64,
+    pub back_top: f64,
+    pub front_top: f64,
+}
+
+impl StageVoltages {
+    pub const fn new(front_bottom: f64, back_bottom: f64, back_top: f64, front_top: f64) -> Self {
+        Self {
+            front_bottom,
+            back_bottom,
+            back_top,
+            front_top,
+        }
+    }
+
+    /// Applies the same voltage to every stage.
+    pub const fn uniform(voltage: f64) -> Self {
+        Self::new(voltage, voltage, voltage, voltage)
+    }
+}
+
+/// What the intake is currently doing with blocks.
+#[derive(Default, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum IntakeMode {
+    /// Intake blocks and hold them inside the robot.
+    Store,
+
+    /// Score stored blocks into a long goal.
+    ScoreLong,
+
+    /// Score stored blocks into the upper center goal.
+    ScoreMiddle,
+
+    /// Score blocks out of the front of the robot into the lower center goal.
+    ScoreLow,
+
+    /// Run every stage in reverse to spit blocks out of the robot.
+    Outtake,
+
+    /// Let every stage coast to a stop.
+    #[default]
+    Idle,
+}
+
+/// Per-stage voltages for each [`IntakeMode`].
+///
+/// Every robot routes blocks through its stages differently, so these are
+/// defined once per robot and passed to [`Intake::with_voltages`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntakeVoltages {
+    pub store: StageVoltages,
+    pub score_long: StageVoltages,
+    pub score_middle: StageVoltages,
+    pub score_low: StageVoltages,
+    pub outtake: StageVoltages,
+}
+
+impl IntakeVoltages {
+    /// Returns the voltages for a mode, or `None` for [`IntakeMode::Idle`].
+    pub const fn get(&self, mode: IntakeMode) -> Option<StageVoltages> {
+        match mode {
+            IntakeMode::Store => Some(self.store),
+            IntakeMode::ScoreLong => Some(self.score_long),
+            IntakeMode::ScoreMiddle => Some(self.score_middle),
+            IntakeMode::ScoreLow => Some(self.score_low),
+            IntakeMode::Outtake => Some(self.outtake),
+            IntakeMode::Idle => None,
+        }
+    }
+}
+
+impl Default for IntakeVoltages {
+    fn default() -> Self {
+        Self {
+            store: StageVoltages::new(12.0, 12.0, 0.0, 0.0),
+            score_long: StageVoltages::uniform(12.0),
+            score_middle: StageVoltages::new(12.0, 12.0, 12.0, -12.0),
+            score_low: StageVoltages::new(-12.0, 0.0, 0.0, 0.0),
+            outtake: StageVoltages::uniform(-12.0),
+        }
+    }
+}
+
+/// Every stage, in the order their motors are stored.
+const STAGES: [IntakeStage; 4] = [
+    IntakeStage::FRONT_BOTTOM,
+    IntakeStage::BACK_BOTTOM,
+    IntakeStage::BACK_TOP,
+    IntakeStage::FRONT_TOP,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StageCommand {
+    Voltage(f64),
+    Brake(BrakeMode),
+}
+
+/// The motors of a single stage and what they were last told to do.
+struct StageMotors {
+    motors: Vec<Motor>,
+    command: StageCommand,
+
+    /// Set while the stage is being driven by jam recovery instead of `command`.
+    recovering_until: Option<Instant>,
+    stalled_since: Option<Instant>,
+}
+
+impl StageMotors {
+    fn new(motors: Vec<Motor>) -> Self {
+        Self {
+            motors,
+            command: StageCommand::Brake(BrakeMode::Coast),
+            recovering_until: None,
+            stalled_since: None,
+        }
+    }
+
+    /// Writes the commanded output to the motors unless the stage is recovering
+    /// from a jam.
+    fn apply(&mut self) -> Result<(), PortError> {
+        if self.recovering_until.is_some() {
+            return Ok(());
+        }
+
+        self.write(self.command)
+    }
+
+    fn write(&mut self, command: StageCommand) -> Result<(), PortError> {
+        let mut rtn = Ok(());
+
+        for motor in self.motors.iter_mut() {
+            let result = match command {
+                StageCommand::Voltage(voltage) => motor.set_voltage(voltage),
+                StageCommand::Brake(mode) => motor.brake(mode),
+            };
+
+            if result.is_err() {
+                rtn = result;
+            }
+        }
+
+        rtn
+    }
+}
+
+enum Hood {
+    /// A single piston that opens the hood fully.
+    Single(Piston),
+
+    /// Two pistons that can open the hood halfway or fully.
+    Double { low: Piston, high: Piston },
+}
+
+pub struct Intake {
+    _task: Option<Task<()>>,
+    _jam_task: Option<Task<()>>,
+    _sensor_tasks: Vec<Task<()>>,
+    reject_color: Rc<RefCell<Option<ElementColor>>>,
+    emergency_override: Rc<RefCell<bool>>,
+
+    stages: Rc<RefCell<[StageMotors; 4]>>,
+    jams: Rc<RefCell<jam::JamLog>>,
+    sort_log: Rc<RefCell<color_sort::SortLog>>,
+    occupancy: Rc<RefCell<occupancy::Occupancy>>,
+    pub voltages: IntakeVoltages,
+    mode: Rc<RefCell<IntakeMode>>,
+
+    hood: Option<Hood>,
+    pub lift: Option<Piston>,
+    pub grabber: Option<Piston>,
+    hood_position: HoodPosition,
+}
+
+impl Intake {
+    /// Creates an intake with no pneumatics or color sorting.
+    ///
+    /// Any stage may be given no motors if the robot doesn't have it.
+    pub fn new(
+        front_bottom_motors: Vec<Motor>,
+        back_bottom_motors: Vec<Motor>,
+        back_top_motors: Vec<Motor>,
+        front_top_motors: Vec<Motor>,
+    ) -> Self {
+        Self {
+            _task: None,
+            _jam_task: None,
+            _sensor_tasks: Vec::new(),
+            reject_color: Rc::new(RefCell::new(None)),
+            emergency_override: Rc::new(RefCell::new(false)),
+            stages: Rc::new(RefCell::new([
+                StageMotors::new(front_bottom_motors),
+                StageMotors::new(back_bottom_motors),
+                StageMotors::new(back_top_motors),
+                StageMotors::new(front_top_motors),
+            ])),
+            jams: Rc::new(RefCell::new(jam::JamLog::default())),
+            sort_log: Rc::new(RefCell::new(color_sort::SortLog::default())),
+            occupancy: Rc::new(RefCell::new(occupancy::Occupancy::default())),
+            voltages: IntakeVoltages::default(),
+            mode: Rc::new(RefCell::new(IntakeMode::Idle)),
+            hood: None,
+            lift: None,
+            grabber: None,
+            hood_position: HoodPosition::Closed,
+        }
+    }
+
+    /// Modifies the voltages used by the intake's high-level actions.
+    pub fn with_voltages(mut self, voltages: IntakeVoltages) -> Self {
+        self.voltages = voltages;
+        self
+    }
+
+    /// Adds a hood actuated by a single piston.
+    ///
+    /// [`HoodPosition::Half`] opens the hood fully on a single-piston hood.
+    pub fn with_hood(mut self, hood: Piston) -> Self {
+        self.hood = Some(Hood::Single(hood));
+        self
+    }
+
+    /// Adds a hood actuated by two pistons, allowing it to be opened halfway.
+    pub fn with_two_stage_hood(mut self, hood_low: Piston, hood_high: Piston) -> Self {
+        self.hood = Some(Hood::Double {
+            low: hood_low,
+            high: hood_high,
+        });
+        self
+    }
+
+    /// Adds a lift piston.
+    pub fn with_lift(mut self, lift: Piston) -> Self {
+        self.lift = Some(lift);
+        self
+    }
+
+    /// Adds a grabber piston.
+    pub fn with_grabber(mut self, grabber: Piston) -> Self {
+        self.grabber = Some(grabber);
+        self
+    }
+
+    /// Enables color sorting using an optical sensor and an ejector piston.
+    ///
+    /// See [`ColorSortConfig`] for how blocks are sorted, and [`calibrate_color_sort`]
+    /// for deriving hue ranges from real blocks. Every block that passes the sensor
+    /// is counted as entering the robot, and every rejected block as ejected once the
+    /// conveyor has carried it past the ejector.
+    pub fn with_color_sort(
+        mut self,
+        optical: OpticalSensor,
+        ejector: Piston,
+        config: ColorSortConfig,
+    ) -> Self {
+        self._task = Some(spawn(color_sort::task(
+            optical,
+            ejector,
+            config,
+            self.stages.clone(),
+            self.reject_color.clone(),
+            self.emergency_override.clone(),
+            self.occupancy.clone(),
+            self.sort_log.clone(),
+        )));
+        self
+    }
+
+    /// Adds a sensor that counts blocks or reports whether a stage is holding one.
+    ///
+    /// See [`BlockSensorRole`] for what each mounting position is used for.
+    pub fn with_block_sensor(mut self, role: BlockSensorRole, sensor: BlockSensor) -> Self {
+        self._sensor_tasks
+            .push(spawn(occupancy::task(sensor, role, self.occupancy.clone())));
+        self
+    }
+
+    /// Enables jam detection and automatic recovery.
+    ///
+    /// See [`JamConfig`] for how jams are detected.
+    pub fn with_jam_detection(mut self, config: JamConfig) -> Self {
+        self._jam_task = Some(spawn(jam::task(
+            self.stages.clone(),
+            self.jams.clone(),
+            self.mode.clone(),
+            config,
+        )));
+        self
+    }
+
+    pub fn set_reject_color(&mut self, reject_color: Option<ElementColor>) {
+        *self.reject_color.borrow_mut() = reject_color;
+    }
+
+    pub fn reject_color(&self) -> Option<ElementColor> {
+        *self.reject_color.borrow()
+    }
+
+    pub fn set_emergency_override(&mut self, ov: bool) {
+        *self.emergency_override.borrow_mut() = ov;
+    }
+
+    pub fn set_hood_position(&mut self, position: HoodPosition) -> Result<(), PortError> {
+        let mut result = Ok(());
+
+        match &mut self.hood {
+            Some(Hood::Single(hood)) => {
+                result = hood.set_extended(position != HoodPosition::Closed);
+            }
+            Some(Hood::Double { low, high }) => {
+                let (low_extended, high_extended) = match position {
+                    HoodPosition::Closed => (false, false),
+                    HoodPosition::Half => (true, false),
+                    HoodPosition::High => (false, true),
+                };
+
+                if let Err(error) = low.set_extended(low_extended) {
+                    result = Err(error);
+                }
+                if let Err(error) = high.set_extended(high_extended) {
+                    result = Err(error);
+                }
+            }
+            None => {}
+        }
+
+        self.hood_position = position;
+
+        result
+    }
+
+    pub fn hood_position(&self) -> HoodPosition {
+        self.hood_position
+    }
+
+    fn command(&mut self, stage: IntakeStage, command: StageCommand) -> Result<(), PortError> {
+        let mut rtn = Ok(());
+
+        for (flag, motors) in STAGES.iter().zip(self.stages.borrow_mut().iter_mut()) {
+            if stage.contains(*flag) {
+                motors.command = command;
+                let result = motors.apply();
+
+                if result.is_err() {
+                    rtn = result;
+                }
+            }
+        }
+
+        rtn
+    }
+
+    pub fn set_voltage(&mut self, stage: IntakeStage, voltage: f64) -> Result<(), PortError> {
+        self.command(stage, StageCommand::Voltage(voltage))
+    }
+
+    /// Sets the voltage of every stage at once.
+    pub fn set_stage_voltages(&mut self, voltages: StageVoltages) -> Result<(), PortError> {
+        let mut rtn = Ok(());
+
+        for (stage, voltage) in [
+            (IntakeStage::FRONT_BOTTOM, voltages.front_bottom),
+            (IntakeStage::BACK_BOTTOM, voltages.back_bottom),
+            (IntakeStage::BACK_TOP, voltages.back_top),
+            (IntakeStage::FRONT_TOP, voltages.front_top),
+        ] {
+            let result = self.set_voltage(stage, voltage);
+
+            if result.is_err() {
+                rtn = result;
+            }
+        }
+
+        rtn
+    }
+
+    /// Brakes every motor in the given stages.
+    pub fn brake(&mut self, stage: IntakeStage, mode: BrakeMode) -> Result<(), PortError> {
+        self.command(stage, StageCommand::Brake(mode))
+    }
+
+    /// Runs every stage at the voltages configured for a mode.
+    pub fn set_mode(&mut self, mode: IntakeMode) -> Result<(), PortError> {
+        *self.mode.borrow_mut() = mode;
+
+        match self.voltages.get(mode) {
+            Some(voltages) => self.set_stage_voltages(voltages),
+            None => self.brake(IntakeStage::all(), BrakeMode::Coast),
+        }
+    }
+
+    /// Returns the most recently set mode.
+    ///
+    /// Stages may have been overridden with [`Intake::set_voltage`] since.
+    pub fn mode(&self) -> IntakeMode {
+        *self.mode.borrow()
+    }
+
+    /// Shorthand for [`IntakeMode::Store`].
+    pub fn store(&mut self) -> Result<(), PortError> {
+        self.set_mode(IntakeMode::Store)
+    }
+
+    /// Shorthand for [`IntakeMode::ScoreLong`].
+    pub fn score_high(&mut self) -> Result<(), PortError> {
+        self.set_mode(IntakeMode::ScoreLong)
+    }
+
+    /// Shorthand for [`IntakeMode::ScoreMiddle`].
+    pub fn score_middle(&mut self) -> Result<(), PortError> {
+        self.set_mode(IntakeMode::ScoreMiddle)
+    }
+
+    /// Shorthand for [`IntakeMode::ScoreLow`].
+    pub fn score_low(&mut self) -> Result<(), PortError> {
+        self.set_mode(IntakeMode::ScoreLow)
+    }
+
+    /// Shorthand for [`IntakeMode::Outtake`].
+    pub fn outtake(&mut self) -> Result<(), PortError> {
+        self.set_mode(IntakeMode::Outtake)
+    }
+
+    /// Shorthand for [`IntakeMode::Idle`].
+    pub fn stop(&mut self) -> Result<(), PortError> {
+        self.set_mode(IntakeMode::Idle)
+    }
+
+    /// Returns the number of jams detected since the intake was created.
+    pub fn jam_count(&self) -> usize {
+        self.jams.borrow().count
+    }
+
+    /// Returns the most recent jams, oldest first.
+    pub fn jam_events(&self) -> Vec<JamEvent> {
+        self.jams.borrow().events.iter().copied().collect()
+    }
+
+    /// Returns `true` if any of the given stages are currently reversing to clear
+    /// a jam.
+    pub fn is_unjamming(&self, stage: IntakeStage) -> bool {
+        STAGES
+            .iter()
+            .zip(self.stages.borrow().iter())
+            .any(|(flag, motors)| stage.contains(*flag) && motors.recovering_until.is_some())
+    }
+
+    /// Returns the most recent color sort detections, oldest first.
+    pub fn sort_events(&self) -> Vec<SortEvent> {
+        self.sort_log.borrow().events.iter().copied().collect()
+    }
+
+    /// Prints the most recent color sort detections through the logger.
+    pub fn log_sort_events(&self) {
+        info!("{}", SortEvent::CSV_HEADER);
+        for event in self.sort_log.borrow().events.iter() {
+            info!("{}", event.to_csv());
+        }
+    }
+
+    /// Writes the most recent color sort detections to a CSV file, e.g. on the
+    /// SD card after a match.
+    pub fn write_sort_events(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = File::create(path)?;
+
+        writeln!(file, "{}", SortEvent::CSV_HEADER)?;
+        for event in self.sort_log.borrow().events.iter() {
+            writeln!(file, "{}", event.to_csv())?;
+        }
+
+        file.flush()
+    }
+
+    /// Returns every block counted since the intake was created or last reset.
+    pub fn block_count(&self) -> BlockCount {
+        self.occupancy.borrow().count
+    }
+
+    /// Returns the estimated number of blocks currently held in the robot.
+    pub fn stored_blocks(&self) -> usize {
+        self.occupancy.borrow().count.stored()
+    }
+
+    /// Overrides the stored block estimate, e.g. for a preload or after the
+    /// count has drifted.
+    pub fn set_stored_blocks(&mut self, count: usize) {
+        self.occupancy.borrow_mut().count = BlockCount {
+            entered: count,
+            ejected: 0,
+            exited: 0,
+        };
+    }
+
+    /// Returns the stages currently holding a block, as reported by stage sensors.
+    pub fn occupied_stages(&self) -> IntakeStage {
+        self.occupancy.borrow().stages
+    }
+
+    /// Waits until at least `count` blocks are stored or `timeout` elapses.
+    ///
+    /// Returns `false` if the timeout elapsed first. This is meant to be raced
+    /// against a motion, e.g. to end a matchload as soon as the robot is full.
+    pub async fn wait_for_stored(&self, count: usize, timeout: Duration) -> bool {
+        let start = Instant::now();
+
+        while self.stored_blocks() < count {
+            if start.elapsed() >= timeout {
+                return false;
+            }
+
+            sleep(occupancy::POLL_INTERVAL).await;
+        }
+
+        true
+    }
+}
@@ -0,0 +1,197 @@
+//! Block counting and occupancy tracking.
+
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use vexide::{
+    prelude::{AdiLineTracker, DistanceSensor},
+    time::sleep,
+};
+
+use super::IntakeStage;
+use crate::motion::distance_sensor::ObjectDistance;
+
+/// A sensor that can tell whether a block is in front of it.
+pub enum BlockSensor {
+    /// A distance sensor that sees a block closer than `max_distance` inches.
+    Distance {
+        sensor: DistanceSensor,
+        max_distance: f64,
+    },
+
+    /// A line tracker that sees a block when its reflectivity exceeds
+    /// `min_reflectivity`.
+    Line {
+        sensor: AdiLineTracker,
+        min_reflectivity: f64,
+    },
+}
+
+impl BlockSensor {
+    /// Returns `true` if a block is in front of the sensor.
+    pub fn is_block_present(&self) -> bool {
+        match self {
+            Self::Distance {
+                sensor,
+                max_distance,
+            } => sensor
+                .object_distance()
+                .is_some_and(|distance| distance < *max_distance),
+            Self::Line {
+                sensor,
+                min_reflectivity,
+            } => sensor
+                .reflectivity()
+                .is_ok_and(|reflectivity| reflectivity > *min_reflectivity),
+        }
+    }
+}
+
+/// Where a [`BlockSensor`] is mounted in the intake.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BlockSensorRole {
+    /// Counts blocks entering the robot.
+    ///
+    /// Intakes with color sorting already count entering blocks with their
+    /// optical sensor, so this is only needed on intakes without one.
+    Entry,
+
+    /// Reports whether a stage is holding a block.
+    Stage(IntakeStage),
+
+    /// Counts blocks leaving the robot into a goal.
+    Exit,
+}
+
+/// Running totals of blocks seen by the intake's sensors.
+#[derive(Default, Debug, Clone, Copy, Eq, PartialEq)]
+pub struct BlockCount {
+    /// Blocks that entered the robot.
+    pub entered: usize,
+
+    /// Blocks removed by color sorting.
+    pub ejected: usize,
+
+    /// Blocks that left the robot past an exit sensor.
+    pub exited: usize,
+}
+
+impl BlockCount {
+    /// Returns the estimated number of blocks currently in the robot.
+    pub const fn stored(&self) -> usize {
+        self.entered.saturating_sub(self.ejected + self.exited)
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct Occupancy {
+    pub(super) count: BlockCount,
+    pub(super) stages: IntakeStage,
+}
+
+impl Occupancy {
+    /// Updates counts and stage flags from a sensor reading.
+    ///
+    /// Entering and exiting blocks are counted once, when they first appear in
+    /// front of the sensor.
+    pub(super) fn sense(&mut self, role: BlockSensorRole, was_present: bool, present: bool) {
+        if present && !was_present {
+            match role {
+                BlockSensorRole::Entry => self.count.entered += 1,
+                BlockSensorRole::Exit => self.count.exited += 1,
+                BlockSensorRole::Stage(_) => {}
+            }
+        }
+
+        if let BlockSensorRole::Stage(stage) = role {
+            self.stages.set(stage, present);
+        }
+    }
+}
+
+impl Default for Occupancy {
+    fn default() -> Self {
+        Self {
+            count: BlockCount::default(),
+            stages: IntakeStage::empty(),
+        }
+    }
+}
+
+pub(super) const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+pub(super) async fn task(
+    sensor: BlockSensor,
+    role: BlockSensorRole,
+    occupancy: Rc<RefCell<Occupancy>>,
+) {
+    let mut was_present = false;
+
+    loop {
+        let present = sensor.is_block_present();
+
+        occupancy.borrow_mut().sense(role, was_present, present);
+
+        was_present = present;
+        sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds a sequence of readings from one sensor into `occupancy`.
+    fn sense_all(occupancy: &mut Occupancy, role: BlockSensorRole, readings: &[bool]) {
+        let mut was_present = false;
+        for &present in readings {
+            occupancy.sense(role, was_present, present);
+            was_present = present;
+        }
+    }
+
+    #[test]
+    fn blocks_are_counted_once_per_appearance() {
+        let mut occupancy = Occupancy::default();
+        sense_all(
+            &mut occupancy,
+            BlockSensorRole::Entry,
+            &[false, true, true, true, false, true, false, false],
+        );
+
+        assert_eq!(occupancy.count.entered, 2);
+        assert_eq!(occupancy.count.stored(), 2);
+    }
+
+    #[test]
+    fn exits_are_removed_from_storage() {
+        let mut occupancy = Occupancy::default();
+        sense_all(&mut occupancy, BlockSensorRole::Entry, &[true, false, true]);
+        sense_all(&mut occupancy, BlockSensorRole::Exit, &[true, true, false]);
+
+        assert_eq!(occupancy.count.exited, 1);
+        assert_eq!(occupancy.count.stored(), 1);
+    }
+
+    #[test]
+    fn stored_never_goes_negative() {
+        let count = BlockCount {
+            entered: 1,
+            ejected: 1,
+            exited: 1,
+        };
+        assert_eq!(count.stored(), 0);
+    }
+
+    #[test]
+    fn stage_sensors_follow_the_latest_reading() {
+        let mut occupancy = Occupancy::default();
+        let role = BlockSensorRole::Stage(IntakeStage::BACK_TOP);
+
+        sense_all(&mut occupancy, role, &[true, true]);
+        assert_eq!(occupancy.stages, IntakeStage::BACK_TOP);
+        assert_eq!(occupancy.count, BlockCount::default());
+
+        sense_all(&mut occupancy, role, &[true, false]);
+        assert!(occupancy.stages.is_empty());
+    }
+}
@@ -1 +1,2 @@
-pub mod intake;
\ No newline at end of file
+pub mod intake;
+pub mod selector;
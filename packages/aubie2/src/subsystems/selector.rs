@@ -0,0 +1,356 @@
+//! Autonomous route selection on the Brain display.
+
+use std::{cell::RefCell, future::Future, pin::Pin, rc::Rc, time::Duration};
+
+use log::info;
+use vexide::{
+    color::Color,
+    display::{Alignment, Display, Font, FontFamily, FontSize, Rect, Text, TouchState},
+    math::Point2,
+    task::{Task, spawn},
+    time::sleep,
+};
+
+use crate::{
+    hardware::health::HealthReport,
+    image::Image,
+    theme::{WAR_EAGLE_BLUE, WAR_EAGLE_ORANGE},
+};
+
+/// Boxed future returned by a registered route.
+pub type RouteFuture<'a> = Pin<Box<dyn Future<Output = ()> + 'a>>;
+
+/// Function that runs a route on a robot of type `R`.
+pub type RouteFn<R> = for<'a> fn(&'a mut R) -> RouteFuture<'a>;
+
+/// Alliance the robot is playing on.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Alliance {
+    Red,
+    Blue,
+}
+
+impl Alliance {
+    /// Returns the alliance selected at compile time with `--cfg color="..."`.
+    ///
+    /// Defaults to red if no color was specified.
+    pub const fn from_cfg() -> Self {
+        if cfg!(color = "blue") {
+            Self::Blue
+        } else {
+            Self::Red
+        }
+    }
+
+    /// Returns the opposing alliance.
+    pub const fn opponent(self) -> Self {
+        match self {
+            Self::Red => Self::Blue,
+            Self::Blue => Self::Red,
+        }
+    }
+}
+
+/// A named autonomous route.
+pub struct Route<R> {
+    pub name: &'static str,
+    pub run: RouteFn<R>,
+}
+
+impl<R> Route<R> {
+    pub const fn new(name: &'static str, run: RouteFn<R>) -> Self {
+        Self { name, run }
+    }
+}
+
+impl<R> Clone for Route<R> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<R> Copy for Route<R> {}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+struct Selection {
+    route: usize,
+    alliance: Alliance,
+}
+
+/// Touchscreen autonomous selector.
+///
+/// Renders a button for every registered route along with an alliance toggle,
+/// and keeps track of the most recent choice so that it can be read back from
+/// `autonomous()`.
+///
+/// A failed health check given to [`AutonSelector::with_health_report`] covers
+/// the selector until the screen is tapped.
+pub struct AutonSelector<R: 'static> {
+    _task: Task<()>,
+    routes: Rc<[Route<R>]>,
+    selection: Rc<RefCell<Selection>>,
+    health: Rc<RefCell<Option<HealthReport>>>,
+}
+
+impl<R: 'static> AutonSelector<R> {
+    const COLUMNS: usize = 2;
+    const BUTTON_HEIGHT: i16 = 40;
+    const PADDING: i16 = 8;
+    const LOGO_WIDTH: i16 = 160;
+
+    /// Creates a selector for the given routes and starts drawing it to the display.
+    ///
+    /// The first route and the alliance given by [`Alliance::from_cfg`] are selected
+    /// by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `routes` is empty.
+    pub fn new(display: Display, routes: Vec<Route<R>>) -> Self {
+        Self::spawn(display, routes, None)
+    }
+
+    /// Creates a selector with a logo drawn to the left of the route buttons.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `routes` is empty.
+    pub fn with_logo<const WIDTH: u32, const HEIGHT: u32>(
+        display: Display,
+        routes: Vec<Route<R>>,
+        logo: Image<WIDTH, HEIGHT>,
+    ) -> Self {
+        Self::spawn(
+            display,
+            routes,
+            Some(Box::new(move |display: &mut Display| {
+                logo.draw(
+                    display,
+                    [
+                        (Self::LOGO_WIDTH - WIDTH as i16) / 2,
+                        (Self::content_height() - HEIGHT as i16) / 2,
+                    ],
+                );
+            })),
+        )
+    }
+
+    fn spawn(
+        display: Display,
+        routes: Vec<Route<R>>,
+        logo: Option<Box<dyn Fn(&mut Display)>>,
+    ) -> Self {
+        assert!(!routes.is_empty(), "At least one route must be registered.");
+
+        let routes: Rc<[Route<R>]> = routes.into();
+        let selection = Rc::new(RefCell::new(Selection {
+            route: 0,
+            alliance: Alliance::from_cfg(),
+        }));
+        let health = Rc::new(RefCell::new(None));
+
+        Self {
+            _task: spawn(Self::task(
+                display,
+                routes.clone(),
+                selection.clone(),
+                health.clone(),
+                logo,
+            )),
+            routes,
+            selection,
+            health,
+        }
+    }
+
+    /// Shows a health report in place of the selector until the screen is
+    /// tapped, so that port issues can't go unnoticed.
+    ///
+    /// Healthy reports are ignored.
+    pub fn with_health_report(self, report: HealthReport) -> Self {
+        if !report.is_healthy() {
+            *self.health.borrow_mut() = Some(report);
+        }
+        self
+    }
+
+    /// Returns the currently selected route.
+    pub fn selected(&self) -> Route<R> {
+        self.routes[self.selection.borrow().route]
+    }
+
+    /// Returns the currently selected alliance.
+    pub fn alliance(&self) -> Alliance {
+        self.selection.borrow().alliance
+    }
+
+    /// Selects a route by name, returning `false` if no route has that name.
+    pub fn select(&mut self, name: &str) -> bool {
+        if let Some(index) = self.routes.iter().position(|route| route.name == name) {
+            self.selection.borrow_mut().route = index;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Selects the alliance.
+    pub fn set_alliance(&mut self, alliance: Alliance) {
+        self.selection.borrow_mut().alliance = alliance;
+    }
+
+    const fn content_height() -> i16 {
+        Display::VERTICAL_RESOLUTION - Display::HEADER_HEIGHT
+    }
+
+    fn route_rect(index: usize, has_logo: bool) -> Rect {
+        let left = if has_logo { Self::LOGO_WIDTH } else { 0 };
+        let width = (Display::HORIZONTAL_RESOLUTION - left - Self::PADDING) / Self::COLUMNS as i16
+            - Self::PADDING;
+
+        let column = (index % Self::COLUMNS) as i16;
+        let row = (index / Self::COLUMNS) as i16;
+
+        let x = left + Self::PADDING + column * (width + Self::PADDING);
+        let y = Self::PADDING + row * (Self::BUTTON_HEIGHT + Self::PADDING);
+
+        Rect::new([x, y], [x + width, y + Self::BUTTON_HEIGHT])
+    }
+
+    fn alliance_rect(has_logo: bool) -> Rect {
+        let left = if has_logo { Self::LOGO_WIDTH } else { 0 };
+        let bottom = Self::content_height() - Self::PADDING;
+
+        Rect::new(
+            [left + Self::PADDING, bottom - Self::BUTTON_HEIGHT],
+            [Display::HORIZONTAL_RESOLUTION - Self::PADDING, bottom],
+        )
+    }
+
+    fn contains(rect: &Rect, point: Point2<i16>) -> bool {
+        (rect.start.x..=rect.end.x).contains(&point.x)
+            && (rect.start.y..=rect.end.y).contains(&point.y)
+    }
+
+    fn draw_button(display: &mut Display, rect: &Rect, label: &str, fill: Color) {
+        display.fill(rect, fill);
+        display.draw_text(
+            &Text::from_string_aligned(
+                label.to_string(),
+                Font::new(FontSize::MEDIUM, FontFamily::Proportional),
+                [
+                    (rect.start.x + rect.end.x) / 2,
+                    (rect.start.y + rect.end.y) / 2,
+                ],
+                Alignment::Center,
+                Alignment::Center,
+            ),
+            Color::new(255, 255, 255),
+            None,
+        );
+    }
+
+    fn draw(
+        display: &mut Display,
+        routes: &[Route<R>],
+        selection: Selection,
+        logo: Option<&dyn Fn(&mut Display)>,
+    ) {
+        display.erase(Color::new(0, 0, 0));
+
+        if let Some(logo) = logo {
+            logo(display);
+        }
+
+        for (index, route) in routes.iter().enumerate() {
+            Self::draw_button(
+                display,
+                &Self::route_rect(index, logo.is_some()),
+                route.name,
+                if index == selection.route {
+                    WAR_EAGLE_ORANGE
+                } else {
+                    WAR_EAGLE_BLUE
+                },
+            );
+        }
+
+        let (label, fill) = match selection.alliance {
+            Alliance::Red => ("Red Alliance", Color::new(200, 30, 30)),
+            Alliance::Blue => ("Blue Alliance", Color::new(30, 60, 200)),
+        };
+        Self::draw_button(display, &Self::alliance_rect(logo.is_some()), label, fill);
+    }
+
+    fn draw_health(display: &mut Display, report: &HealthReport) {
+        report.draw(display);
+        display.draw_text(
+            &Text::new(
+                "Tap to continue",
+                Font::new(FontSize::MEDIUM, FontFamily::Proportional),
+                [
+                    Self::PADDING,
+                    Self::content_height() - Self::PADDING - Self::BUTTON_HEIGHT / 2,
+                ],
+            ),
+            WAR_EAGLE_ORANGE,
+            None,
+        );
+    }
+
+    async fn task(
+        mut display: Display,
+        routes: Rc<[Route<R>]>,
+        selection: Rc<RefCell<Selection>>,
+        health: Rc<RefCell<Option<HealthReport>>>,
+        logo: Option<Box<dyn Fn(&mut Display)>>,
+    ) {
+        let has_logo = logo.is_some();
+        let mut drawn = None;
+        let mut health_drawn = false;
+        let mut was_pressed = false;
+
+        loop {
+            let touch = display.touch_status();
+            let pressed = touch.state != TouchState::Released;
+
+            if pressed && !was_pressed && health.borrow_mut().take().is_some() {
+                info!("Health report acknowledged.");
+                drawn = None;
+            } else if pressed && !was_pressed {
+                let point = Point2 {
+                    x: touch.point.x,
+                    y: touch.point.y - Display::HEADER_HEIGHT,
+                };
+                let mut selection = selection.borrow_mut();
+
+                if let Some(index) = (0..routes.len())
+                    .find(|&index| Self::contains(&Self::route_rect(index, has_logo), point))
+                {
+                    selection.route = index;
+                    info!("Selected route: {}", routes[index].name);
+                } else if Self::contains(&Self::alliance_rect(has_logo), point) {
+                    selection.alliance = selection.alliance.opponent();
+                    info!("Selected alliance: {:?}", selection.alliance);
+                }
+            }
+            was_pressed = pressed;
+
+            if let Some(report) = health.borrow().as_ref() {
+                if !health_drawn {
+                    Self::draw_health(&mut display, report);
+                    health_drawn = true;
+                }
+            } else {
+                // Only redraw when something changed (or was changed externally).
+                let current = *selection.borrow();
+                if drawn != Some(current) {
+                    Self::draw(&mut display, &routes, current, logo.as_deref());
+                    drawn = Some(current);
+                }
+            }
+
+            sleep(Duration::from_millis(20)).await;
+        }
+    }
+}
@@ -1,4 +1,10 @@
-use vexide::startup::banner::themes::BannerTheme;
+use vexide::{color::Color, startup::banner::themes::BannerTheme};
+
+/// Auburn orange, used for highlights on the Brain display.
+pub const WAR_EAGLE_ORANGE: Color = Color::new(246, 88, 12);
+
+/// Auburn blue, used for backgrounds on the Brain display.
+pub const WAR_EAGLE_BLUE: Color = Color::new(45, 105, 194);
 
 #[expect(
     edition_2024_expr_fragment_specifier,
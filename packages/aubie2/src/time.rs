@@ -0,0 +1,14 @@
+//! Clock used by motions and routes.
+//!
+//! On the robot these are the usual system clock types. Host builds with the
+//! simulator swap in [simulated time](crate::sim::clock), so that simulated
+//! motions time out and settle the same way however fast the host runs them.
+
+#[cfg(not(all(any(test, feature = "sim"), not(target_os = "vexos"))))]
+pub use std::time::Instant;
+
+#[cfg(not(all(any(test, feature = "sim"), not(target_os = "vexos"))))]
+pub use vexide::time::{Sleep, sleep};
+
+#[cfg(all(any(test, feature = "sim"), not(target_os = "vexos")))]
+pub use crate::sim::clock::{Instant, Sleep, sleep};
@@ -1,43 +1,103 @@
 use std::time::{Duration, Instant};
 
-use aubie2::{hardware::calibration::calibrate_imu, logger::RobotLogger, theme::THEME_WAR_EAGLE};
+use aubie2::{
+    config::{DrivetrainConfig, ImuConfig, IntakeConfig, MotorConfig, PistonConfig, RobotConfig},
+    hardware::{
+        odometry::{OdometryCalibration, OdometryCalibrationConfig, calibration_requested},
+        pneumatics::Piston,
+    },
+    logger::RobotLogger,
+    motion::report::AutonReport,
+    subsystems::{
+        intake::{HoodPosition, Intake, IntakeMode, IntakeVoltages, StageVoltages},
+        selector::{AutonSelector, Route},
+    },
+    theme::THEME_WAR_EAGLE,
+};
 use evian::{
     control::loops::{AngularPid, Pid},
     drivetrain::model::Differential,
     math::Angle,
     prelude::*,
-    tracking::{
-        shared_motors,
-        wheeled::{TrackingWheel, WheeledTracking},
-    },
+    tracking::wheeled::WheeledTracking,
 };
-use log::{LevelFilter, info};
+use log::{LevelFilter, error, info};
 use vexide::prelude::*;
 
 pub mod routes;
 
 struct Dory {
     controller: Controller,
+    selector: AutonSelector<Dory>,
+    report: AutonReport,
     drivetrain: Drivetrain<Differential, WheeledTracking>,
-    intake_bottom: Motor,
-    intake_conveyor: Motor,
-    intake_hood: Motor,
-    intake_score: Motor,
-    hood: AdiDigitalOut,
-    snacky: AdiDigitalOut,
-    trapdoor: AdiDigitalOut,
-    matchloader: AdiDigitalOut,
-    aligner: AdiDigitalOut,
+    mechanisms: Mechanisms,
+}
+
+/// Everything on Dory other than the drivetrain.
+struct Mechanisms {
+    intake: Intake,
+    snacky: Piston,
+    trapdoor: Piston,
+    matchloader: Piston,
+    aligner: Piston,
 }
 
 impl Dory {
+    // Hardware
+    pub const CONFIG: RobotConfig<4, 4> = RobotConfig {
+        drivetrain: DrivetrainConfig {
+            left: [
+                MotorConfig::new(1, Gearset::Blue, Direction::Forward),
+                MotorConfig::new(2, Gearset::Blue, Direction::Reverse),
+                MotorConfig::new(3, Gearset::Blue, Direction::Reverse),
+                MotorConfig::new(4, Gearset::Blue, Direction::Forward),
+            ],
+            right: [
+                MotorConfig::new(5, Gearset::Blue, Direction::Forward),
+                MotorConfig::new(6, Gearset::Blue, Direction::Reverse),
+                MotorConfig::new(7, Gearset::Blue, Direction::Forward),
+                MotorConfig::new(8, Gearset::Blue, Direction::Reverse),
+            ],
+            wheel_diameter: 2.75,
+            track_width: 11.5,
+            gearing: None,
+        },
+        imus: &[ImuConfig::new(13)],
+        forward_wheel: None,
+        sideways_wheel: None,
+        intake: IntakeConfig {
+            front_bottom: &[MotorConfig::new(9, Gearset::Blue, Direction::Reverse)],
+            back_bottom: &[MotorConfig::new(20, Gearset::Blue, Direction::Forward)],
+            back_top: &[MotorConfig::new(10, Gearset::Blue, Direction::Forward)],
+            front_top: &[MotorConfig::new(11, Gearset::Blue, Direction::Reverse)],
+            voltages: Self::INTAKE_VOLTAGES,
+            hood: Some(PistonConfig::new("Hood", 'a')),
+            jam_detection: None,
+        },
+        pistons: [
+            PistonConfig::new("Snacky", 'b'),
+            PistonConfig::new("Trapdoor", 'd'),
+            PistonConfig::new("Matchloader", 'g'),
+            PistonConfig::new("Aligner", 'e'),
+        ],
+        air_tanks: 1,
+    };
+
     // Measurements
-    pub const TRACK_WIDTH: f64 = 11.5;
-    pub const WHEEL_DIAMETER: f64 = 2.75;
     pub const TRACKING_WHEEL_DIAMETER: f64 = 2.0;
 
     pub const SIDEWAYS_TRACKING_WHEEL_OFFSET: f64 = -2.5;
 
+    // Intake (bottom, conveyor, hood, score)
+    pub const INTAKE_VOLTAGES: IntakeVoltages = IntakeVoltages {
+        store: StageVoltages::new(12.0, 12.0, -2.0, 0.0),
+        score_long: StageVoltages::uniform(12.0),
+        score_middle: StageVoltages::new(12.0, 12.0, 12.0, -6.0),
+        score_low: StageVoltages::new(-12.0, 0.0, 0.0, 0.0),
+        outtake: StageVoltages::uniform(-12.0),
+    };
+
     // Control Loops
     pub const LINEAR_PID: Pid = Pid::new(0.1, 0.001, 0.01, Some(3.0));
     pub const LATERAL_PID: Pid = Pid::new(0.09, 0.001, 0.004, Some(2.0));
@@ -57,9 +117,17 @@ impl Dory {
 
 impl Compete for Dory {
     async fn autonomous(&mut self) {
+        let route = self.selector.selected();
+        info!(
+            "Running route {} ({:?} alliance).",
+            route.name,
+            self.selector.alliance()
+        );
+
         let start = Instant::now();
+        self.report.clear();
 
-        self.aura().await;
+        (route.run)(self).await;
 
         info!("Route completed successfully in {:?}.", start.elapsed());
         info!(
@@ -67,11 +135,13 @@ impl Compete for Dory {
             self.drivetrain.tracking.position(),
             self.drivetrain.tracking.heading().as_degrees(),
         );
+        self.report.log();
+        self.report.show(&mut self.controller).await;
     }
 
     async fn driver(&mut self) {
-        _ = self.hood.set_high();
-        _ = self.aligner.set_high();
+        _ = self.mechanisms.intake.set_hood_position(HoodPosition::High);
+        _ = self.mechanisms.aligner.extend();
 
         loop {
             let state = self.controller.state().unwrap_or_default();
@@ -83,49 +153,43 @@ impl Compete for Dory {
 
             // Intake controls
             if state.button_r1.is_pressed() {
-                _ = self.intake_bottom.set_voltage(12.0);
-                _ = self.intake_conveyor.set_voltage(12.0);
-
                 if state.button_a.is_pressed() {
-                    _ = self.intake_hood.set_voltage(12.0);
-                    _ = self.intake_score.set_voltage(12.0);
+                    _ = self.mechanisms.intake.set_mode(IntakeMode::ScoreLong);
                 } else if state.button_b.is_pressed() {
-                    _ = self.intake_hood.set_voltage(12.0);
-                    _ = self.intake_score.set_voltage(-6.0);
+                    _ = self.mechanisms.intake.set_mode(IntakeMode::ScoreMiddle);
                 } else {
-                    _ = self.intake_hood.set_voltage(-2.0);
+                    _ = self.mechanisms.intake.set_mode(IntakeMode::Store);
                 }
             } else if state.button_r2.is_pressed() {
-                _ = self.intake_bottom.set_voltage(-12.0);
-                _ = self.intake_conveyor.set_voltage(-12.0);
-                _ = self.intake_hood.set_voltage(-12.0);
-                _ = self.intake_score.set_voltage(-12.0);
+                _ = self.mechanisms.intake.set_mode(IntakeMode::Outtake);
             } else {
-                _ = self.intake_bottom.set_voltage(0.0);
-                _ = self.intake_conveyor.set_voltage(0.0);
-                _ = self.intake_hood.set_voltage(0.0);
-                _ = self.intake_score.set_voltage(0.0);
+                _ = self.mechanisms.intake.set_mode(IntakeMode::Idle);
             }
 
             if state.button_b.is_now_pressed() {
-                _ = self.trapdoor.set_high();
+                _ = self.mechanisms.trapdoor.extend();
             }
 
             if state.button_b.is_now_released() {
-                _ = self.trapdoor.set_low();
+                _ = self.mechanisms.trapdoor.retract();
             }
 
             if state.button_x.is_now_pressed() {
-                _ = self.snacky.toggle();
+                _ = self.mechanisms.snacky.toggle();
             }
 
             if state.button_l2.is_now_pressed() {
-                _ = self.matchloader.toggle();
+                _ = self.mechanisms.matchloader.toggle();
             }
 
             if state.button_l1.is_now_pressed() {
-                _ = self.hood.toggle();
-                _ = self.aligner.toggle();
+                _ = self.mechanisms.intake.set_hood_position(
+                    match self.mechanisms.intake.hood_position() {
+                        HoodPosition::Closed => HoodPosition::High,
+                        _ => HoodPosition::Closed,
+                    },
+                );
+                _ = self.mechanisms.aligner.toggle();
             }
 
             sleep(Motor::WRITE_INTERVAL).await;
@@ -134,52 +198,58 @@ impl Compete for Dory {
 }
 
 #[vexide::main(banner(theme = THEME_WAR_EAGLE))]
-async fn main(peripherals: Peripherals) {
+async fn main(mut peripherals: Peripherals) {
     RobotLogger.init(LevelFilter::Trace).unwrap();
 
-    let mut controller = peripherals.primary_controller;
-    let mut display = peripherals.display;
+    let config = Dory::CONFIG.with_saved_odometry(OdometryCalibration::DEFAULT_PATH);
+
+    // Holding Y as the program starts calibrates odometry instead.
+    if calibration_requested(&peripherals.primary_controller) {
+        if let Err(error) = config
+            .calibrate_odometry(peripherals, OdometryCalibrationConfig::default())
+            .await
+        {
+            error!("Odometry calibration failed: {error:?}");
+        }
+        return;
+    }
 
-    let mut imu = InertialSensor::new(peripherals.port_13);
+    // Smart ports are checked before IMU calibration, so that missing devices
+    // are reported straight away.
+    let mut health = config.check_devices();
+    health.log();
+    health.show(&mut peripherals.primary_controller).await;
 
-    calibrate_imu(&mut controller, &mut display, &mut imu).await;
+    let mut hardware = config.build(peripherals).await;
 
-    let left_motors = shared_motors![
-        Motor::new(peripherals.port_1, Gearset::Blue, Direction::Forward),
-        Motor::new(peripherals.port_2, Gearset::Blue, Direction::Reverse),
-        Motor::new(peripherals.port_3, Gearset::Blue, Direction::Reverse),
-        Motor::new(peripherals.port_4, Gearset::Blue, Direction::Forward),
-    ];
-    let right_motors = shared_motors![
-        Motor::new(peripherals.port_5, Gearset::Blue, Direction::Forward),
-        Motor::new(peripherals.port_6, Gearset::Blue, Direction::Reverse),
-        Motor::new(peripherals.port_7, Gearset::Blue, Direction::Forward),
-        Motor::new(peripherals.port_8, Gearset::Blue, Direction::Reverse),
-    ];
+    // ADI ports are only configured once they've been claimed. The selector holds
+    // failed reports on the display until they're tapped away.
+    let adi_health = config.check_adi_ports();
+    adi_health.log();
+    health.merge(adi_health);
+    health.show(&mut hardware.controller).await;
+    let [snacky, trapdoor, matchloader, aligner] = hardware.pistons;
 
     let robot = Dory {
-        controller,
-        drivetrain: Drivetrain::new(
-            Differential::from_shared(left_motors.clone(), right_motors.clone()),
-            WheeledTracking::forward_only(
-                (0.0, 0.0),
-                90.0.deg(),
-                [
-                    TrackingWheel::new(left_motors, 2.75, 0.0, None),
-                    TrackingWheel::new(right_motors, 2.75, 0.0, None),
-                ],
-                Some(imu),
-            ),
-        ),
-        intake_bottom: Motor::new(peripherals.port_9, Gearset::Blue, Direction::Reverse),
-        intake_conveyor: Motor::new(peripherals.port_20, Gearset::Blue, Direction::Forward),
-        intake_hood: Motor::new(peripherals.port_10, Gearset::Blue, Direction::Forward),
-        intake_score: Motor::new(peripherals.port_11, Gearset::Blue, Direction::Reverse),
-        hood: AdiDigitalOut::new(peripherals.adi_a),
-        snacky: AdiDigitalOut::new(peripherals.adi_b),
-        trapdoor: AdiDigitalOut::new(peripherals.adi_d),
-        matchloader: AdiDigitalOut::new(peripherals.adi_g),
-        aligner: AdiDigitalOut::new(peripherals.adi_e),
+        controller: hardware.controller,
+        selector: AutonSelector::new(
+            hardware.display,
+            vec![
+                Route::new("Aura", |robot| Box::pin(robot.aura())),
+                Route::new("Safe", |robot| Box::pin(robot.safe())),
+                Route::new("Skills", |robot| Box::pin(robot.skills())),
+            ],
+        )
+        .with_health_report(health),
+        report: AutonReport::new(),
+        drivetrain: hardware.drivetrain,
+        mechanisms: Mechanisms {
+            intake: hardware.intake,
+            snacky,
+            trapdoor,
+            matchloader,
+            aligner,
+        },
     };
 
     // skills : c
@@ -1,8 +1,8 @@
 use std::time::Duration;
 
 use aubie2::{
-    motion::basic::BasicExt,
-    subsystems::intake::{ElementColor, HoodPosition, IntakeStage},
+    motion::{basic::BasicExt, mirror::Mirror},
+    subsystems::intake::{ElementColor, HoodPosition, IntakeMode, IntakeStage},
 };
 use evian::{
     motion::{Basic, Seeking},
@@ -15,6 +15,7 @@ use crate::Dory;
 
 impl Dory {
     pub async fn aura(&mut self) {
+        let m = Mirror::new(self.selector.alliance());
         let dt = &mut self.drivetrain;
         let mut basic = Basic {
             linear_controller: Dory::LINEAR_PID,
@@ -32,87 +33,107 @@ impl Dory {
             timeout: Some(Duration::from_secs(3)),
         };
 
-        dt.tracking.set_heading(270.0.deg());
-        _ = self.aligner.set_high();
+        dt.tracking.set_heading(m.heading(270.0.deg()));
+        _ = self.mechanisms.aligner.extend();
 
         // drive to goal
         basic
-            .drive_distance_at_heading(dt, -31.0, 270.0.deg())
+            .drive_at_heading(dt, -31.0, m.heading(270.0.deg()))
             .with_linear_error_tolerance(20.0)
             .with_angular_output_limit(0.65)
             .without_angular_tolerance_duration()
             .without_linear_tolerance_duration()
+            .with_report(&self.report, "drive to goal")
             .await;
         basic
-            .drive_distance_at_heading(dt, -29.0, 225.0.deg())
+            .drive_at_heading(dt, -29.0, m.heading(225.0.deg()))
             .with_timeout(Duration::from_secs(1))
+            .with_report(&self.report, "line up goal")
             .await;
-        _ = self.hood.set_high(); // deploy
+        _ = self.mechanisms.intake.set_hood_position(HoodPosition::High); // deploy
         sleep(Duration::from_millis(50)).await;
-        _ = self.snacky.set_high();
-        _ = self.intake_score.set_voltage(-6.0);
+        _ = self.mechanisms.snacky.extend();
+        _ = self
+            .mechanisms
+            .intake
+            .set_voltage(IntakeStage::FRONT_TOP, -6.0);
         sleep(Duration::from_millis(350)).await;
 
         // drive back
-        println!("{}", dt.tracking.position());
-        basic.drive_distance_at_heading(dt, 54.0, 225.0.deg()).await;
-        basic.turn_to_heading(dt, 270.0.deg()).await;
+        basic
+            .drive_at_heading(dt, 54.0, m.heading(225.0.deg()))
+            .with_report(&self.report, "drive back")
+            .await;
+        basic
+            .turn_to(dt, m.heading(270.0.deg()))
+            .with_report(&self.report, "face wall")
+            .await;
 
         // Reset
         basic
-            .drive_distance_at_heading(dt, -100.0, 270.0.deg())
+            .drive_at_heading(dt, -100.0, m.heading(270.0.deg()))
             .with_linear_output_limit(0.5)
             .with_timeout(Duration::from_millis(1000))
+            .with_report(&self.report, "square up")
             .await;
         dt.tracking.set_position((0.0, 0.0));
 
         // Matchloader
-        _ = self.matchloader.set_high();
-        _ = self.intake_bottom.set_voltage(12.0);
-        _ = self.intake_conveyor.set_voltage(12.0);
-        _ = self.intake_score.set_voltage(0.0);
-        _ = self.intake_hood.set_voltage(-2.0);
+        _ = self.mechanisms.matchloader.extend();
+        _ = self.mechanisms.intake.set_mode(IntakeMode::Store);
         basic
-            .drive_distance_at_heading(dt, 100.0, 272.0.deg())
+            .drive_at_heading(dt, 100.0, m.heading(272.0.deg()))
             .with_timeout(Duration::from_millis(1800))
             .with_linear_output_limit(0.35)
+            .with_report(&self.report, "matchload")
             .await;
 
         // Score
         basic
-            .drive_distance_at_heading(dt, -100.0, 272.0.deg())
+            .drive_at_heading(dt, -100.0, m.heading(272.0.deg()))
             .with_linear_output_limit(0.5)
             .with_timeout(Duration::from_millis(1250))
+            .with_report(&self.report, "score")
             .await;
-        _ = self.intake_score.set_voltage(12.0);
-        _ = self.intake_hood.set_voltage(12.0);
+        _ = self.mechanisms.intake.set_mode(IntakeMode::ScoreLong);
         dt.tracking.set_position((0.0, 0.0)); // odom reset
         sleep(Duration::from_secs(1)).await;
 
         // snacky
-        _ = self.matchloader.set_low();
-        _ = self.snacky.set_low();
-        _ = self.intake_bottom.set_voltage(0.0);
-        _ = self.intake_conveyor.set_voltage(0.0);
-        _ = self.intake_score.set_voltage(0.0);
-        _ = self.intake_hood.set_voltage(0.0);
+        _ = self.mechanisms.matchloader.retract();
+        _ = self.mechanisms.snacky.retract();
+        _ = self.mechanisms.intake.set_mode(IntakeMode::Idle);
         basic
-            .drive_distance_at_heading(dt, 12.0, 270.0.deg())
+            .drive_at_heading(dt, 12.0, m.heading(270.0.deg()))
             .without_linear_tolerance_duration()
             .without_angular_tolerance_duration()
+            .with_report(&self.report, "leave goal")
             .await;
-        basic.turn_to_heading(dt, 0.0.deg()).await;
         basic
-            .drive_distance_at_heading(dt, 12.5, 0.0.deg())
+            .turn_to(dt, m.heading(0.0.deg()))
+            .with_report(&self.report, "turn across")
+            .await;
+        basic
+            .drive_at_heading(dt, 12.5, m.heading(0.0.deg()))
             .without_linear_tolerance_duration()
             .without_angular_tolerance_duration()
+            .with_report(&self.report, "cross over")
             .await;
-        basic.turn_to_heading(dt, 90.0.deg()).await;
         basic
-            .drive_distance_at_heading(dt, 38.0, 90.0.deg())
-            .without_timeout()
-            .with_linear_error_tolerance(0.0)
-            .without_linear_velocity_tolerance()
+            .turn_to(dt, m.heading(90.0.deg()))
+            .with_report(&self.report, "turn to push")
             .await;
+
+        // Holding only ends with autonomous, so report while it starts.
+        self.report.log();
+        join(
+            self.report.show(&mut self.controller),
+            basic
+                .drive_at_heading(dt, 38.0, m.heading(90.0.deg()))
+                .without_timeout()
+                .with_linear_error_tolerance(0.0)
+                .without_linear_velocity_tolerance(),
+        )
+        .await;
     }
 }
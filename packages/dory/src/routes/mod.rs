@@ -1,23 +1,68 @@
 use std::time::Duration;
 
 use aubie2::{
-    motion::basic::BasicExt,
-    subsystems::intake::{ElementColor, HoodPosition, IntakeStage},
+    motion::{basic::BasicExt, mirror::Mirror, report::AutonReport},
+    route::RouteRobot,
+    subsystems::intake::{ElementColor, HoodPosition, IntakeMode, IntakeStage},
 };
 use evian::{
+    control::loops::{AngularPid, Pid},
+    drivetrain::model::Differential,
     motion::{Basic, Seeking},
     prelude::*,
+    tracking::wheeled::WheeledTracking,
 };
 use futures::future::join;
 use vexide::time::sleep;
 
-use crate::Dory;
+use crate::{Dory, Mechanisms};
 
 pub mod aura;
 pub mod skills;
 
+impl RouteRobot for Dory {
+    type Model = Differential;
+    type Tracking = WheeledTracking;
+    type Mechanisms = Mechanisms;
+
+    fn basic(&self) -> Basic<Pid, AngularPid> {
+        Basic {
+            linear_controller: Dory::LINEAR_PID,
+            angular_controller: Dory::ANGUALR_PID,
+            linear_tolerances: Dory::LINEAR_TOLERANCES,
+            angular_tolerances: Dory::ANGULAR_TOLERANCES,
+            timeout: Some(Duration::from_secs(5)),
+        }
+    }
+
+    fn seeking(&self) -> Seeking<Pid, Pid> {
+        Seeking {
+            linear_controller: Dory::LINEAR_PID,
+            lateral_controller: Dory::LATERAL_PID,
+            tolerances: Tolerances::new()
+                .error(1.0)
+                .duration(Duration::from_millis(100)),
+            timeout: Some(Duration::from_secs(3)),
+        }
+    }
+
+    fn parts(
+        &mut self,
+    ) -> (
+        &mut Drivetrain<Differential, WheeledTracking>,
+        &mut Mechanisms,
+    ) {
+        (&mut self.drivetrain, &mut self.mechanisms)
+    }
+
+    fn report(&self) -> Option<AutonReport> {
+        Some(self.report.clone())
+    }
+}
+
 impl Dory {
     pub async fn safe(&mut self) {
+        let m = Mirror::new(self.selector.alliance());
         let dt = &mut self.drivetrain;
         let mut basic = Basic {
             linear_controller: Dory::LINEAR_PID,
@@ -35,59 +80,72 @@ impl Dory {
             timeout: Some(Duration::from_secs(3)),
         };
 
-        dt.tracking.set_heading(270.0.deg());
-        _ = self.aligner.set_high();
+        dt.tracking.set_heading(m.heading(270.0.deg()));
+        _ = self.mechanisms.aligner.extend();
 
         // drive to goal
         basic
-            .drive_distance_at_heading(dt, -34.0, 270.0.deg())
+            .drive_at_heading(dt, -34.0, m.heading(270.0.deg()))
+            .with_report(&self.report, "drive to goal")
+            .await;
+        basic
+            .turn_to(dt, m.heading(225.0.deg()))
+            .with_report(&self.report, "face goal")
             .await;
-        basic.turn_to_heading(dt, 225.0.deg()).await;
 
         basic
-            .drive_distance_at_heading(dt, -12.0, 225.0.deg())
+            .drive_at_heading(dt, -12.0, m.heading(225.0.deg()))
             .with_timeout(Duration::from_millis(1000))
+            .with_report(&self.report, "line up goal")
             .await;
-        _ = self.hood.set_high(); // deploy
+        _ = self.mechanisms.intake.set_hood_position(HoodPosition::High); // deploy
         sleep(Duration::from_millis(50)).await;
-        _ = self.snacky.set_high();
-        _ = self.intake_score.set_voltage(-6.0);
+        _ = self.mechanisms.snacky.extend();
+        _ = self
+            .mechanisms
+            .intake
+            .set_voltage(IntakeStage::FRONT_TOP, -6.0);
         sleep(Duration::from_millis(350)).await;
 
         // drive back
         println!("{}", dt.tracking.position());
-        basic.drive_distance_at_heading(dt, 55.0, 225.0.deg()).await;
-        basic.turn_to_heading(dt, 270.0.deg()).await;
+        basic
+            .drive_at_heading(dt, 55.0, m.heading(225.0.deg()))
+            .with_report(&self.report, "drive back")
+            .await;
+        basic
+            .turn_to(dt, m.heading(270.0.deg()))
+            .with_report(&self.report, "face wall")
+            .await;
 
         // Reset
         basic
-            .drive_distance_at_heading(dt, -100.0, 270.0.deg())
+            .drive_at_heading(dt, -100.0, m.heading(270.0.deg()))
             .with_linear_output_limit(0.5)
             .with_timeout(Duration::from_millis(1000))
+            .with_report(&self.report, "square up")
             .await;
         sleep(Duration::from_millis(500)).await; // ensure we're fully settled
         dt.tracking.set_position((0.0, 0.0));
 
         // Matchloader
-        _ = self.matchloader.set_high();
-        _ = self.intake_bottom.set_voltage(12.0);
-        _ = self.intake_conveyor.set_voltage(12.0);
-        _ = self.intake_score.set_voltage(0.0);
-        _ = self.intake_hood.set_voltage(-2.0);
+        _ = self.mechanisms.matchloader.extend();
+        _ = self.mechanisms.intake.set_mode(IntakeMode::Store);
         basic
-            .drive_distance_at_heading(dt, 100.0, 270.0.deg())
+            .drive_at_heading(dt, 100.0, m.heading(270.0.deg()))
             .with_timeout(Duration::from_millis(1650))
             .with_linear_output_limit(0.35)
+            .with_report(&self.report, "matchload")
             .await;
 
         // Score
         basic
-            .drive_distance_at_heading(dt, -100.0, 270.0.deg())
+            .drive_at_heading(dt, -100.0, m.heading(270.0.deg()))
             .with_linear_output_limit(0.5)
             .with_timeout(Duration::from_millis(1500))
+            .with_report(&self.report, "score")
             .await;
-        _ = self.intake_score.set_voltage(12.0);
-        _ = self.intake_hood.set_voltage(12.0);
+        _ = self.mechanisms.intake.set_mode(IntakeMode::ScoreLong);
         dt.tracking.set_position((0.0, 0.0)); // odom reset
         sleep(Duration::from_secs(2)).await;
 
@@ -95,61 +153,74 @@ impl Dory {
         join(
             async {
                 basic
-                    .drive_distance_at_heading(dt, 100.0, 270.0.deg())
+                    .drive_at_heading(dt, 100.0, m.heading(270.0.deg()))
                     .with_timeout(Duration::from_millis(6000))
                     .with_linear_output_limit(0.3)
+                    .with_report(&self.report, "matchload again")
                     .await;
             },
             async {
                 sleep(Duration::from_millis(3800)).await;
-                _ = self.intake_score.set_voltage(0.0);
-                _ = self.intake_hood.set_voltage(-2.0);
+                _ = self.mechanisms.intake.set_mode(IntakeMode::Store);
             },
         )
         .await;
 
         // Score again
         basic
-            .drive_distance_at_heading(dt, -100.0, 270.0.deg())
+            .drive_at_heading(dt, -100.0, m.heading(270.0.deg()))
             .with_linear_output_limit(0.5)
             .with_timeout(Duration::from_millis(2000))
+            .with_report(&self.report, "score again")
             .await;
-        _ = self.intake_score.set_voltage(12.0);
-        _ = self.intake_hood.set_voltage(12.0);
+        _ = self.mechanisms.intake.set_mode(IntakeMode::ScoreLong);
         dt.tracking.set_position((0.0, 0.0)); // odom reset
         sleep(Duration::from_secs(2)).await;
 
         //
-        _ = self.matchloader.set_low();
-        _ = self.snacky.set_low();
-        _ = self.intake_bottom.set_voltage(0.0);
-        _ = self.intake_conveyor.set_voltage(0.0);
-        _ = self.intake_score.set_voltage(0.0);
-        _ = self.intake_hood.set_voltage(0.0);
-        basic
-            .drive_distance_at_heading(dt, 12.0, 270.0.deg())
+        _ = self.mechanisms.matchloader.retract();
+        _ = self.mechanisms.snacky.retract();
+        _ = self.mechanisms.intake.set_mode(IntakeMode::Idle);
+        basic
+            .drive_at_heading(dt, 12.0, m.heading(270.0.deg()))
             .without_linear_tolerance_duration()
             .without_angular_tolerance_duration()
+            .with_report(&self.report, "leave goal")
+            .await;
+        basic
+            .turn_to(dt, m.heading(0.0.deg()))
+            .with_report(&self.report, "turn across")
             .await;
-        basic.turn_to_heading(dt, 0.0.deg()).await;
         basic
-            .drive_distance_at_heading(dt, 13.0, 0.0.deg())
+            .drive_at_heading(dt, 13.0, m.heading(0.0.deg()))
             .without_linear_tolerance_duration()
             .without_angular_tolerance_duration()
+            .with_report(&self.report, "cross over")
+            .await;
+        basic
+            .turn_to(dt, m.heading(90.0.deg()))
+            .with_report(&self.report, "turn to push")
             .await;
-        basic.turn_to_heading(dt, 90.0.deg()).await;
         basic
-            .drive_distance_at_heading(dt, 28.0, 90.0.deg())
+            .drive_at_heading(dt, 28.0, m.heading(90.0.deg()))
             .without_linear_tolerance_duration()
             .without_angular_tolerance_duration()
+            .with_report(&self.report, "push")
             .await;
         sleep(Duration::from_millis(500)).await;
-        _ = self.snacky.set_high();
+        _ = self.mechanisms.snacky.extend();
 
         basic
-            .drive_distance_at_heading(dt, -48.0, 90.0.deg())
+            .drive_at_heading(dt, -48.0, m.heading(90.0.deg()))
+            .with_report(&self.report, "back out")
+            .await;
+        basic
+            .turn_to(dt, m.heading(-28.0.deg()))
+            .with_report(&self.report, "face center")
+            .await;
+        basic
+            .drive_at_heading(dt, 44.0, m.heading(0.0.deg()))
+            .with_report(&self.report, "drive to center")
             .await;
-        basic.turn_to_heading(dt, -28.0.deg()).await;
-        basic.drive_distance_at_heading(dt, 44.0, 0.0.deg()).await;
     }
 }
@@ -1,113 +1,91 @@
 use std::time::Duration;
 
 use aubie2::{
-    motion::basic::BasicExt,
-    subsystems::intake::{ElementColor, HoodPosition, IntakeStage},
+    route::{Motion, RoutePlan},
+    subsystems::intake::{HoodPosition, IntakeMode, IntakeStage},
 };
-use evian::{
-    motion::{Basic, Seeking},
-    prelude::*,
-};
-use futures::future::join;
-use vexide::time::sleep;
+use evian::prelude::*;
 
-use crate::Dory;
+use crate::{Dory, Mechanisms};
 
 impl Dory {
     pub async fn skills(&mut self) {
-        let dt = &mut self.drivetrain;
-        let mut basic = Basic {
-            linear_controller: Dory::LINEAR_PID,
-            angular_controller: Dory::ANGUALR_PID,
-            linear_tolerances: Dory::LINEAR_TOLERANCES,
-            angular_tolerances: Dory::ANGULAR_TOLERANCES,
-            timeout: Some(Duration::from_secs(5)),
-        };
-        let mut seeking = Seeking {
-            linear_controller: Dory::LINEAR_PID,
-            lateral_controller: Dory::LATERAL_PID,
-            tolerances: Tolerances::new()
-                .error(1.0)
-                .duration(Duration::from_millis(100)),
-            timeout: Some(Duration::from_secs(3)),
-        };
-
-        dt.tracking.set_heading(180.0.deg());
-
-        basic.drive_distance_at_heading(dt, 33.5, 180.0.deg()).await;
-        basic.turn_to_heading(dt, 270.0.deg()).await;
-
-        _ = self.hood.set_high(); // deploy
-        sleep(Duration::from_millis(50)).await;
-        _ = self.snacky.set_high();
-        _ = self.matchloader.set_high();
-        _ = self.aligner.set_high();
-        sleep(Duration::from_millis(250)).await;
-
-        // matchloader
-        _ = self.intake_bottom.set_voltage(12.0);
-        _ = self.intake_conveyor.set_voltage(12.0);
-        _ = self.intake_score.set_voltage(0.0);
-        _ = self.intake_hood.set_voltage(-2.0);
-        basic
-            .drive_distance_at_heading(dt, 100.0, 270.0.deg())
-            .with_timeout(Duration::from_millis(2500))
-            .with_linear_output_limit(0.3)
-            .await;
-        sleep(Duration::from_secs(1)).await;
-        basic.drive_distance_at_heading(dt, -8.0, 272.0.deg()).await;
-        basic.drive_distance_at_heading(dt, 8.0, 272.0.deg()).await;
-        sleep(Duration::from_millis(1000)).await;
-        basic.drive_distance_at_heading(dt, -8.0, 268.0.deg()).await;
-        basic.drive_distance_at_heading(dt, 8.0, 268.0.deg()).await;
-        sleep(Duration::from_millis(1000)).await;
+        self.drivetrain.tracking.set_heading(180.0.deg());
 
-        // Score
-        basic
-            .drive_distance_at_heading(dt, -100.0, 269.5.deg())
-            .with_linear_output_limit(0.5)
-            .with_timeout(Duration::from_millis(3000))
+        RoutePlan::<Mechanisms>::new("Skills")
+            .then(Motion::drive(33.5, 180.0.deg()))
+            .then(Motion::turn(270.0.deg()))
+            .actuate("deploy", |mech| {
+                _ = mech.intake.set_hood_position(HoodPosition::High);
+            })
+            .wait(Duration::from_millis(50))
+            .actuate("extend snacky, matchloader and aligner", |mech| {
+                _ = mech.snacky.extend();
+                _ = mech.matchloader.extend();
+                _ = mech.aligner.extend();
+            })
+            .wait(Duration::from_millis(250))
+            .checkpoint("matchloader")
+            .actuate("store", |mech| {
+                _ = mech.intake.set_mode(IntakeMode::Store);
+            })
+            .then(
+                Motion::drive(100.0, 270.0.deg())
+                    .with_timeout(Duration::from_millis(2500))
+                    .with_linear_output_limit(0.3),
+            )
+            .wait(Duration::from_secs(1))
+            .then(Motion::drive(-8.0, 272.0.deg()))
+            .then(Motion::drive(8.0, 272.0.deg()))
+            .wait(Duration::from_millis(1000))
+            .then(Motion::drive(-8.0, 268.0.deg()))
+            .then(Motion::drive(8.0, 268.0.deg()))
+            .wait(Duration::from_millis(1000))
+            .checkpoint("score")
+            .then(
+                Motion::drive(-100.0, 269.5.deg())
+                    .with_linear_output_limit(0.5)
+                    .with_timeout(Duration::from_millis(3000)),
+            )
+            .actuate("score long", |mech| {
+                _ = mech.intake.set_mode(IntakeMode::ScoreLong);
+            })
+            .set_position((0.0, 0.0)) // odom reset
+            .wait(Duration::from_secs(3))
+            .actuate("back off conveyor", back_off_conveyor)
+            .wait(Duration::from_millis(150))
+            .actuate("score long", |mech| {
+                _ = mech.intake.set_mode(IntakeMode::ScoreLong);
+            })
+            .wait(Duration::from_secs(2))
+            .actuate("back off conveyor", back_off_conveyor)
+            .wait(Duration::from_millis(150))
+            .actuate("score long", |mech| {
+                _ = mech.intake.set_mode(IntakeMode::ScoreLong);
+            })
+            .wait(Duration::from_secs(2))
+            .checkpoint("park")
+            .actuate("stow", |mech| {
+                _ = mech.intake.set_mode(IntakeMode::ScoreLow);
+                _ = mech.matchloader.retract();
+                _ = mech.aligner.retract();
+                _ = mech.intake.set_hood_position(HoodPosition::Closed);
+            })
+            .then(Motion::seek((30.0, -34.0)).with_timeout(Duration::from_secs(1)))
+            .then(Motion::turn(-25.0.deg()))
+            .then(Motion::drive(28.0, 0.0.deg()))
+            .then(Motion::drive(20.0, 0.0.deg()).with_linear_output_limit(0.2))
+            .actuate("stop intake", |mech| {
+                _ = mech.intake.set_mode(IntakeMode::Idle);
+            })
+            .run(self)
             .await;
-        _ = self.intake_score.set_voltage(12.0);
-        _ = self.intake_hood.set_voltage(12.0);
-        dt.tracking.set_position((0.0, 0.0)); // odom reset
-        sleep(Duration::from_secs(3)).await;
-        _ = self.intake_score.set_voltage(-2.0);
-        _ = self.intake_hood.set_voltage(0.0);
-        _ = self.intake_conveyor.set_voltage(-6.0);
-        sleep(Duration::from_millis(150)).await;
-        _ = self.intake_score.set_voltage(12.0);
-        _ = self.intake_hood.set_voltage(12.0);
-        _ = self.intake_conveyor.set_voltage(12.0);
-        sleep(Duration::from_secs(2)).await;
-        _ = self.intake_score.set_voltage(-2.0);
-        _ = self.intake_hood.set_voltage(0.0);
-        _ = self.intake_conveyor.set_voltage(-6.0);
-        sleep(Duration::from_millis(150)).await;
-        _ = self.intake_score.set_voltage(12.0);
-        _ = self.intake_hood.set_voltage(12.0);
-        _ = self.intake_conveyor.set_voltage(12.0);
-        sleep(Duration::from_secs(2)).await;
-
-        _ = self.intake_bottom.set_voltage(-12.0);
-        _ = self.intake_conveyor.set_voltage(0.0);
-        _ = self.intake_score.set_voltage(0.0);
-        _ = self.intake_hood.set_voltage(0.0);
-        _ = self.matchloader.set_low();
-        _ = self.aligner.set_low();
-        _ = self.hood.set_low();
-        seeking
-            .move_to_point(dt, (30.0, -34.0))
-            .with_timeout(Duration::from_secs(1))
-            .await;
-        basic.turn_to_heading(dt, -25.0.deg()).await;
-        basic.drive_distance_at_heading(dt, 28.0, 0.0.deg()).await;
-        basic
-            .drive_distance_at_heading(dt, 20.0, 0.0.deg())
-            .with_linear_output_limit(0.2)
-            .await;
-        _ = self.intake_bottom.set_voltage(0.0);
-
-        return;
     }
 }
+
+/// Briefly runs the conveyor backwards to free blocks stuck under the hood.
+fn back_off_conveyor(mech: &mut Mechanisms) {
+    _ = mech.intake.set_voltage(IntakeStage::FRONT_TOP, -2.0);
+    _ = mech.intake.set_voltage(IntakeStage::BACK_TOP, 0.0);
+    _ = mech.intake.set_voltage(IntakeStage::BACK_BOTTOM, -6.0);
+}
@@ -1,42 +1,103 @@
 use std::time::{Duration, Instant};
 
-use aubie2::{hardware::calibration::calibrate_imu, logger::RobotLogger, theme::THEME_WAR_EAGLE};
+use aubie2::{
+    config::{DrivetrainConfig, ImuConfig, IntakeConfig, MotorConfig, PistonConfig, RobotConfig},
+    hardware::{
+        odometry::{OdometryCalibration, OdometryCalibrationConfig, calibration_requested},
+        pneumatics::Piston,
+    },
+    logger::RobotLogger,
+    motion::report::AutonReport,
+    subsystems::{
+        intake::{Intake, IntakeMode, IntakeStage, IntakeVoltages, JamConfig, StageVoltages},
+        selector::{AutonSelector, Route},
+    },
+    theme::THEME_WAR_EAGLE,
+};
 use evian::{
     control::loops::{AngularPid, Pid},
     drivetrain::model::Differential,
     math::Angle,
     prelude::*,
-    tracking::{
-        shared_motors,
-        wheeled::{TrackingWheel, WheeledTracking},
-    },
+    tracking::wheeled::WheeledTracking,
 };
 use futures::{future::join, select_biased};
 use futures_lite::FutureExt;
-use log::{LevelFilter, info};
-use vexide::{controller::ControllerId, prelude::*, smart::motor::BrakeMode};
+use log::{LevelFilter, error, info};
+use vexide::{controller::ControllerId, prelude::*};
 
 mod routes;
 
 struct Nemo {
     controller: Controller,
+    selector: AutonSelector<Nemo>,
+    report: AutonReport,
     drivetrain: Drivetrain<Differential, WheeledTracking>,
+    mechanisms: Mechanisms,
+}
 
-    intake_score: Motor,
-    intake_middle: Motor,
-    intake_front: Motor,
-    intake_hood: Motor,
+/// Everything on Nemo other than the drivetrain.
+struct Mechanisms {
+    intake: Intake,
 
-    snacky: AdiDigitalOut,
-    matchloader: AdiDigitalOut,
-    descore: AdiDigitalOut,
-    trapdoor: AdiDigitalOut,
+    snacky: Piston,
+    matchloader: Piston,
+    descore: Piston,
+    trapdoor: Piston,
 }
 
 impl Nemo {
-    // Measurements
-    pub const TRACK_WIDTH: f64 = 11.5;
-    pub const WHEEL_DIAMETER: f64 = 3.25;
+    // Hardware
+    pub const CONFIG: RobotConfig<4, 4> = RobotConfig {
+        drivetrain: DrivetrainConfig {
+            left: [
+                MotorConfig::new(1, Gearset::Blue, Direction::Reverse),
+                MotorConfig::new(2, Gearset::Blue, Direction::Reverse),
+                MotorConfig::new(3, Gearset::Blue, Direction::Reverse),
+                MotorConfig::new(4, Gearset::Blue, Direction::Forward),
+            ],
+            right: [
+                MotorConfig::new(5, Gearset::Blue, Direction::Forward),
+                MotorConfig::new(6, Gearset::Blue, Direction::Forward),
+                MotorConfig::new(7, Gearset::Blue, Direction::Forward),
+                MotorConfig::new(8, Gearset::Blue, Direction::Reverse),
+            ],
+            wheel_diameter: 3.25,
+            track_width: 11.5,
+            gearing: Some(36.0 / 48.0),
+        },
+        imus: &[ImuConfig::new(21)],
+        forward_wheel: None,
+        sideways_wheel: None,
+        intake: IntakeConfig {
+            front_bottom: &[MotorConfig::new(15, Gearset::Blue, Direction::Forward)],
+            back_bottom: &[MotorConfig::new(10, Gearset::Blue, Direction::Reverse)],
+            back_top: &[MotorConfig::new(13, Gearset::Blue, Direction::Reverse)],
+            front_top: &[MotorConfig::new(9, Gearset::Blue, Direction::Forward)],
+            voltages: Self::INTAKE_VOLTAGES,
+            hood: None,
+            jam_detection: Some(JamConfig::new()),
+        },
+        pistons: [
+            PistonConfig::new("Snacky", 'a'),
+            PistonConfig::new("Matchloader", 'c'),
+            PistonConfig::new("Descore", 'd'),
+            PistonConfig::new("Trapdoor", 'e'),
+        ],
+        air_tanks: 1,
+    };
+
+    // Intake (front, middle, hood, score)
+    pub const INTAKE_VOLTAGES: IntakeVoltages = IntakeVoltages {
+        store: StageVoltages::new(12.0, 12.0, -12.0, 1.0),
+        score_long: StageVoltages::uniform(12.0),
+        score_middle: StageVoltages::new(12.0, 12.0, 0.0, -12.0),
+        score_low: StageVoltages::new(-6.0, 0.0, 0.0, 0.0),
+        outtake: StageVoltages::uniform(-12.0),
+    };
+
+    /// Blocks in a full matchloader.
+    pub const MATCHLOAD_BLOCKS: usize = 6;
 
     // Control Loops
     pub const LINEAR_PID: Pid = Pid::new(0.1, 0.001, 0.0101, Some(3.0));
@@ -57,9 +118,17 @@ impl Nemo {
 
 impl Compete for Nemo {
     async fn autonomous(&mut self) {
+        let route = self.selector.selected();
+        info!(
+            "Running route {} ({:?} alliance).",
+            route.name,
+            self.selector.alliance()
+        );
+
         let start = Instant::now();
+        self.report.clear();
 
-        self.safe().await;
+        (route.run)(self).await;
 
         info!("Route completed successfully in {:?}.", start.elapsed());
         info!(
@@ -67,6 +136,8 @@ impl Compete for Nemo {
             self.drivetrain.tracking.position(),
             self.drivetrain.tracking.heading().as_degrees(),
         );
+        self.report.log();
+        self.report.show(&mut self.controller).await;
     }
 
     async fn driver(&mut self) {
@@ -95,56 +166,43 @@ impl Compete for Nemo {
                 .drive_tank(state.left_stick.y(), state.right_stick.y());
 
             if state.button_right.is_pressed() {
-                _ = self.intake_front.set_voltage(12.0);
+                _ = self.mechanisms.intake.set_mode(IntakeMode::ScoreMiddle);
                 if trapdoor_timestamp.elapsed() < Duration::from_millis(150) {
-                    _ = self.intake_middle.set_voltage(-12.0);
-                    _ = self.intake_hood.set_voltage(-12.0);
-                } else {
-                    _ = self.intake_middle.set_voltage(12.0);
-                    _ = self.intake_hood.brake(BrakeMode::Coast);
+                    // Briefly back blocks off the trapdoor as it opens.
+                    _ = self
+                        .mechanisms
+                        .intake
+                        .set_voltage(IntakeStage::BACK_BOTTOM | IntakeStage::BACK_TOP, -12.0);
                 }
-                _ = self.intake_score.set_voltage(-12.0);
             } else if state.button_l2.is_pressed() {
-                _ = self.intake_front.set_voltage(12.0);
-                _ = self.intake_middle.set_voltage(12.0);
-                _ = self.intake_score.set_voltage(1.0);
-                _ = self.intake_hood.set_voltage(-12.0);
+                _ = self.mechanisms.intake.set_mode(IntakeMode::Store);
             } else if state.button_r2.is_pressed() {
-                _ = self.intake_front.set_voltage(12.0);
-                _ = self.intake_middle.set_voltage(12.0);
-                _ = self.intake_score.set_voltage(12.0);
-                _ = self.intake_hood.set_voltage(12.0);
+                _ = self.mechanisms.intake.set_mode(IntakeMode::ScoreLong);
             } else if state.button_l1.is_pressed() {
-                _ = self.intake_front.set_voltage(-12.0);
-                _ = self.intake_middle.set_voltage(-12.0);
-                _ = self.intake_hood.set_voltage(-12.0);
-                _ = self.intake_score.set_voltage(-12.0);
+                _ = self.mechanisms.intake.set_mode(IntakeMode::Outtake);
             } else {
-                _ = self.intake_front.brake(BrakeMode::Coast);
-                _ = self.intake_middle.brake(BrakeMode::Coast);
-                _ = self.intake_hood.brake(BrakeMode::Coast);
-                _ = self.intake_score.brake(BrakeMode::Coast);
+                _ = self.mechanisms.intake.set_mode(IntakeMode::Idle);
             }
 
             if state.button_right.is_now_pressed() {
-                _ = self.trapdoor.set_high();
+                _ = self.mechanisms.trapdoor.extend();
                 trapdoor_timestamp = Instant::now();
             }
 
             if state.button_right.is_now_released() {
-                _ = self.trapdoor.set_low();
+                _ = self.mechanisms.trapdoor.retract();
             }
 
             if state.button_r1.is_now_pressed() {
-                _ = self.snacky.toggle();
+                _ = self.mechanisms.snacky.toggle();
             }
 
             if state.button_y.is_now_pressed() {
-                _ = self.matchloader.toggle();
+                _ = self.mechanisms.matchloader.toggle();
             }
 
             if state.button_b.is_now_pressed() {
-                _ = self.descore.toggle();
+                _ = self.mechanisms.descore.toggle();
             }
 
             sleep(Motor::WRITE_INTERVAL).await;
@@ -153,51 +211,58 @@ impl Compete for Nemo {
 }
 
 #[vexide::main(banner(theme = THEME_WAR_EAGLE))]
-async fn main(peripherals: Peripherals) {
+async fn main(mut peripherals: Peripherals) {
     RobotLogger.init(LevelFilter::Trace).unwrap();
 
-    let mut controller = peripherals.primary_controller;
-    let mut display = peripherals.display;
+    let config = Nemo::CONFIG.with_saved_odometry(OdometryCalibration::DEFAULT_PATH);
+
+    // Holding Y as the program starts calibrates odometry instead.
+    if calibration_requested(&peripherals.primary_controller) {
+        if let Err(error) = config
+            .calibrate_odometry(peripherals, OdometryCalibrationConfig::default())
+            .await
+        {
+            error!("Odometry calibration failed: {error:?}");
+        }
+        return;
+    }
 
-    let mut imu = InertialSensor::new(peripherals.port_21);
+    // Smart ports are checked before IMU calibration, so that missing devices
+    // are reported straight away.
+    let mut health = config.check_devices();
+    health.log();
+    health.show(&mut peripherals.primary_controller).await;
 
-    calibrate_imu(&mut controller, &mut display, &mut imu).await;
+    let mut hardware = config.build(peripherals).await;
 
-    let l = shared_motors![
-        Motor::new(peripherals.port_1, Gearset::Blue, Direction::Reverse),
-        Motor::new(peripherals.port_2, Gearset::Blue, Direction::Reverse),
-        Motor::new(peripherals.port_3, Gearset::Blue, Direction::Reverse),
-        Motor::new(peripherals.port_4, Gearset::Blue, Direction::Forward),
-    ];
-    let r = shared_motors![
-        Motor::new(peripherals.port_5, Gearset::Blue, Direction::Forward),
-        Motor::new(peripherals.port_6, Gearset::Blue, Direction::Forward),
-        Motor::new(peripherals.port_7, Gearset::Blue, Direction::Forward),
-        Motor::new(peripherals.port_8, Gearset::Blue, Direction::Reverse),
-    ];
+    // ADI ports are only configured once they've been claimed. The selector holds
+    // failed reports on the display until they're tapped away.
+    let adi_health = config.check_adi_ports();
+    adi_health.log();
+    health.merge(adi_health);
+    health.show(&mut hardware.controller).await;
+    let [snacky, matchloader, descore, trapdoor] = hardware.pistons;
 
     let robot = Nemo {
-        controller,
-        drivetrain: Drivetrain::new(
-            Differential::from_shared(l.clone(), r.clone()),
-            WheeledTracking::forward_only(
-                (0.0, 0.0),
-                90.0.deg(),
-                [
-                    TrackingWheel::new(l, Nemo::WHEEL_DIAMETER, 0.0, Some(36.0 / 48.0)),
-                    TrackingWheel::new(r, Nemo::WHEEL_DIAMETER, 0.0, Some(36.0 / 48.0)),
-                ],
-                Some(imu),
-            ),
-        ),
-        intake_front: Motor::new(peripherals.port_15, Gearset::Blue, Direction::Forward),
-        intake_hood: Motor::new(peripherals.port_13, Gearset::Blue, Direction::Reverse),
-        intake_middle: Motor::new(peripherals.port_10, Gearset::Blue, Direction::Reverse),
-        intake_score: Motor::new(peripherals.port_9, Gearset::Blue, Direction::Forward),
-        matchloader: AdiDigitalOut::new(peripherals.adi_c),
-        snacky: AdiDigitalOut::new(peripherals.adi_a),
-        trapdoor: AdiDigitalOut::new(peripherals.adi_e),
-        descore: AdiDigitalOut::new(peripherals.adi_d),
+        controller: hardware.controller,
+        selector: AutonSelector::new(
+            hardware.display,
+            vec![
+                Route::new("Safe", |robot| Box::pin(robot.safe())),
+                Route::new("Aura", |robot| Box::pin(robot.aura())),
+                Route::new("Skills", |robot| Box::pin(robot.skills())),
+            ],
+        )
+        .with_health_report(health),
+        report: AutonReport::new(),
+        drivetrain: hardware.drivetrain,
+        mechanisms: Mechanisms {
+            intake: hardware.intake,
+            snacky,
+            matchloader,
+            descore,
+            trapdoor,
+        },
     };
 
     robot.compete().await;
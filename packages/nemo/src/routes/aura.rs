@@ -1,15 +1,21 @@
 use std::time::Duration;
 
+use aubie2::{
+    motion::{basic::BasicExt, mirror::Mirror},
+    subsystems::intake::{IntakeMode, IntakeStage},
+};
 use evian::{
     motion::{Basic, Seeking},
     prelude::*,
 };
+use futures::future::join;
 use vexide::time::sleep;
 
 use crate::Nemo;
 
 impl Nemo {
     pub async fn aura(&mut self) {
+        let m = Mirror::new(self.selector.alliance());
         let dt = &mut self.drivetrain;
         let mut basic = Basic {
             linear_controller: Nemo::LINEAR_PID,
@@ -27,98 +33,115 @@ impl Nemo {
             timeout: Some(Duration::from_secs(3)),
         };
 
-        dt.tracking.set_heading(90.0.deg());
+        dt.tracking.set_heading(m.heading(90.0.deg()));
 
         // drift to middle goal
         basic
-            .drive_distance_at_heading(dt, 40.0, 135.0.deg())
+            .drive_at_heading(dt, 40.0, m.heading(135.0.deg()))
             .with_angular_output_limit(0.4)
             .with_timeout(Duration::from_secs(1))
+            .with_report(&self.report, "drift to goal")
             .await;
         basic
-            .drive_distance_at_heading(dt, 100.0, 135.0.deg())
+            .drive_at_heading(dt, 100.0, m.heading(135.0.deg()))
             .with_linear_output_limit(0.1)
             .with_timeout(Duration::from_millis(250))
+            .with_report(&self.report, "push into goal")
+            .await;
+        _ = self.mechanisms.intake.set_mode(IntakeMode::ScoreLow);
+        basic
+            .drive_at_heading(dt, -3.0, m.heading(135.0.deg()))
+            .with_report(&self.report, "back off goal")
             .await;
-        _ = self.intake_front.set_voltage(-6.0);
-        basic.drive_distance_at_heading(dt, -3.0, 135.0.deg()).await;
         dt.tracking.set_position((0.0, 0.0));
 
         // drift back
         basic
-            .drive_distance_at_heading(dt, -40.0, 270.0.deg())
+            .drive_at_heading(dt, -40.0, m.heading(270.0.deg()))
             .with_timeout(Duration::from_millis(1500))
             .with_angular_output_limit(0.3)
+            .with_report(&self.report, "drift back")
             .await;
         basic
-            .drive_distance_at_heading(dt, -100.0, 270.0.deg())
+            .drive_at_heading(dt, -100.0, m.heading(270.0.deg()))
             .with_linear_output_limit(0.2)
             .with_timeout(Duration::from_millis(200))
+            .with_report(&self.report, "square up")
             .await;
         dt.tracking.set_position((0.0, 0.0));
-        _ = self.matchloader.set_high();
+        _ = self.mechanisms.matchloader.extend();
 
         // matchload
-        _ = self.intake_front.set_voltage(12.0);
-        _ = self.intake_middle.set_voltage(12.0);
-        _ = self.intake_score.set_voltage(1.0);
-        _ = self.intake_hood.set_voltage(-12.0);
+        _ = self.mechanisms.intake.set_mode(IntakeMode::Store);
         basic
-            .drive_distance_at_heading(dt, 100.0, 268.0.deg())
+            .drive_at_heading(dt, 100.0, m.heading(268.0.deg()))
             .with_timeout(Duration::from_millis(350))
+            .with_report(&self.report, "approach matchloader")
             .await;
         basic
-            .drive_distance_at_heading(dt, 100.0, 268.0.deg())
+            .drive_at_heading(dt, 100.0, m.heading(268.0.deg()))
             .with_linear_output_limit(0.3)
             .with_timeout(Duration::from_millis(1200))
+            .with_report(&self.report, "matchload")
             .await;
 
         // Score
         basic
-            .drive_distance_at_heading(dt, -100.0, 268.0.deg())
+            .drive_at_heading(dt, -100.0, m.heading(268.0.deg()))
             .with_linear_output_limit(0.5)
             .with_timeout(Duration::from_millis(2000))
+            .with_report(&self.report, "score")
             .await;
-        _ = self.intake_front.set_voltage(-12.0);
-        _ = self.intake_middle.set_voltage(12.0);
-        _ = self.intake_score.set_voltage(12.0);
-        _ = self.intake_hood.set_voltage(12.0);
+        _ = self
+            .mechanisms
+            .intake
+            .set_voltage(IntakeStage::FRONT_BOTTOM, -12.0);
+        _ = self.mechanisms.intake.set_voltage(
+            IntakeStage::BACK_BOTTOM | IntakeStage::FRONT_TOP | IntakeStage::BACK_TOP,
+            12.0,
+        );
         dt.tracking.set_position((0.0, 0.0)); // odom reset
         sleep(Duration::from_millis(400)).await;
 
         // Snacky
-        _ = self.matchloader.set_low();
+        _ = self.mechanisms.matchloader.retract();
 
         basic
-            .drive_distance_at_heading(dt, 10.0, 268.0.deg())
+            .drive_at_heading(dt, 10.0, m.heading(268.0.deg()))
             .without_tolerance_duration()
             .without_angular_error_tolerance()
+            .with_report(&self.report, "leave goal")
             .await;
         basic
-            .drive_distance_at_heading(dt, 28.0, 90.0.deg())
+            .drive_at_heading(dt, 28.0, m.heading(90.0.deg()))
             .with_linear_output_limit(0.55)
             .with_angular_output_limit(1.0)
+            .with_report(&self.report, "snacky")
             .await;
-        _ = self.intake_front.set_voltage(0.0);
-        _ = self.intake_middle.set_voltage(0.0);
-        _ = self.intake_score.set_voltage(0.0);
-        _ = self.intake_hood.set_voltage(0.0);
+        _ = self.mechanisms.intake.set_mode(IntakeMode::Idle);
 
         basic
-            .drive_distance_at_heading(dt, 19.0, 90.0.deg())
+            .drive_at_heading(dt, 19.0, m.heading(90.0.deg()))
             .with_linear_kp(2.0)
             .with_timeout(Duration::from_millis(500))
+            .with_report(&self.report, "push")
             .await;
-        basic
-            .drive_distance_at_heading(dt, 0.0, 90.0.deg())
-            .without_timeout()
-            .with_linear_error_tolerance(0.0)
-            .without_linear_velocity_tolerance()
-            .await;
-        // basic.drive_distance_at_heading(dt, 12.0, 270.0.deg()).await;
-        // basic.turn_to_heading(dt, 180.0.deg()).await;
-        // basic.drive_distance_at_heading(dt, 11.0, 180.0.deg()).await;
-        // basic.turn_to_heading(dt, 90.0.deg()).await;
-        // basic.drive_distance_at_heading(dt, 38.0, 90.0.deg()).await;
+
+        // Holding only ends with autonomous, so report while it starts.
+        self.report.log();
+        join(
+            self.report.show(&mut self.controller),
+            basic
+                .drive_at_heading(dt, 0.0, m.heading(90.0.deg()))
+                .without_timeout()
+                .with_linear_error_tolerance(0.0)
+                .without_linear_velocity_tolerance(),
+        )
+        .await;
+        // basic.drive_distance_at_heading(dt, 12.0, m.heading(270.0.deg())).await;
+        // basic.turn_to_heading(dt, m.heading(180.0.deg())).await;
+        // basic.drive_distance_at_heading(dt, 11.0, m.heading(180.0.deg())).await;
+        // basic.turn_to_heading(dt, m.heading(90.0.deg())).await;
+        // basic.drive_distance_at_heading(dt, 38.0, m.heading(90.0.deg())).await;
     }
 }
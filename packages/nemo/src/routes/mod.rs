@@ -1,164 +1,191 @@
 use std::time::Duration;
 
 use aubie2::{
-    motion::basic::BasicExt,
-    subsystems::intake::{ElementColor, HoodPosition, IntakeStage},
+    motion::{basic::BasicExt, mirror::Mirror, report::AutonReport},
+    route::{Motion, RoutePlan, RouteRobot},
+    subsystems::intake::{IntakeMode, IntakeStage},
 };
 use evian::{
+    control::loops::{AngularPid, Pid},
+    drivetrain::model::Differential,
     motion::{Basic, Seeking},
     prelude::*,
+    tracking::wheeled::WheeledTracking,
 };
 use futures::future::join;
-use vexide::time::sleep;
 
-use crate::Nemo;
+use crate::{Mechanisms, Nemo};
 
 mod aura;
 mod skills;
 
-impl Nemo {
-    pub async fn safe(&mut self) {
-        let dt = &mut self.drivetrain;
-        let mut basic = Basic {
+impl RouteRobot for Nemo {
+    type Model = Differential;
+    type Tracking = WheeledTracking;
+    type Mechanisms = Mechanisms;
+
+    fn basic(&self) -> Basic<Pid, AngularPid> {
+        Basic {
             linear_controller: Nemo::LINEAR_PID,
             angular_controller: Nemo::ANGUALR_PID,
             linear_tolerances: Nemo::LINEAR_TOLERANCES,
             angular_tolerances: Nemo::ANGULAR_TOLERANCES,
             timeout: Some(Duration::from_secs(5)),
-        };
-        let mut seeking = Seeking {
+        }
+    }
+
+    fn seeking(&self) -> Seeking<Pid, Pid> {
+        Seeking {
             linear_controller: Nemo::LINEAR_PID,
             lateral_controller: Nemo::LATERAL_PID,
             tolerances: Tolerances::new()
                 .error(1.0)
                 .duration(Duration::from_millis(100)),
             timeout: Some(Duration::from_secs(3)),
-        };
-
-        dt.tracking.set_heading(270.0.deg());
-
-        // Descore from park zone
-        _ = self.descore.set_high();
-        sleep(Duration::from_millis(150)).await;
-        basic
-            .drive_distance_at_heading(dt, -10.0, 270.0.deg())
-            .await;
-        _ = self.descore.set_low();
-        sleep(Duration::from_millis(100)).await;
+        }
+    }
 
-        // Go to matchloader
-        _ = self.intake_front.set_voltage(-12.0);
-        basic.turn_to_heading(dt, 0.0.deg()).await;
-        seeking
-            .move_to_point(dt, (50.0, 9.0))
-            .with_linear_output_limit(0.7)
-            .await;
-        println!("{}", dt.tracking.position());
-        basic.turn_to_heading(dt, 270.0.deg()).await;
+    fn parts(
+        &mut self,
+    ) -> (
+        &mut Drivetrain<Differential, WheeledTracking>,
+        &mut Mechanisms,
+    ) {
+        (&mut self.drivetrain, &mut self.mechanisms)
+    }
 
-        // Reset
-        basic
-            .drive_distance_at_heading(dt, -12.0, 270.0.deg())
-            .with_linear_output_limit(0.5)
-            .with_timeout(Duration::from_secs(1))
-            .await;
-        _ = self.intake_hood.set_voltage(12.0);
-        _ = self.intake_score.set_voltage(12.0);
-        sleep(Duration::from_millis(750)).await; // ensure we're fully settled
-        dt.tracking.set_position((0.0, 0.0));
+    fn report(&self) -> Option<AutonReport> {
+        Some(self.report.clone())
+    }
+}
 
-        // Matchloader
-        _ = self.matchloader.set_high();
-        sleep(Duration::from_millis(350)).await;
-        _ = self.intake_front.set_voltage(12.0);
-        _ = self.intake_middle.set_voltage(12.0);
-        _ = self.intake_score.set_voltage(1.0);
-        _ = self.intake_hood.set_voltage(-12.0);
-        basic
-            .drive_distance_at_heading(dt, 100.0, 268.0.deg())
-            .with_timeout(Duration::from_secs(4))
-            .with_linear_output_limit(0.3)
+impl Nemo {
+    pub async fn safe(&mut self) {
+        let m = Mirror::new(self.selector.alliance());
+        self.drivetrain.tracking.set_heading(m.heading(270.0.deg()));
+
+        RoutePlan::<Mechanisms>::new("Safe")
+            .checkpoint("descore")
+            .actuate("extend descore", |mech| {
+                _ = mech.descore.extend();
+            })
+            .wait(Duration::from_millis(150))
+            .then(Motion::drive(-10.0, m.heading(270.0.deg())))
+            .actuate("retract descore", |mech| {
+                _ = mech.descore.retract();
+            })
+            .wait(Duration::from_millis(100))
+            .checkpoint("go to matchloader")
+            .actuate("reverse front bottom", |mech| {
+                _ = mech.intake.set_voltage(IntakeStage::FRONT_BOTTOM, -12.0);
+            })
+            .then(Motion::turn(m.heading(0.0.deg())))
+            .then(Motion::seek(m.point((50.0, 9.0))).with_linear_output_limit(0.7))
+            .then(Motion::turn(m.heading(270.0.deg())))
+            .checkpoint("reset")
+            .then(
+                Motion::drive(-12.0, m.heading(270.0.deg()))
+                    .with_linear_output_limit(0.5)
+                    .with_timeout(Duration::from_secs(1)),
+            )
+            .actuate("run top stages", |mech| {
+                _ = mech
+                    .intake
+                    .set_voltage(IntakeStage::BACK_TOP | IntakeStage::FRONT_TOP, 12.0);
+            })
+            .wait(Duration::from_millis(750)) // ensure we're fully settled
+            .set_position((0.0, 0.0))
+            .checkpoint("matchloader")
+            .actuate("extend matchloader", |mech| {
+                _ = mech.matchloader.extend();
+            })
+            .wait(Duration::from_millis(350))
+            .actuate("store", |mech| {
+                _ = mech.intake.set_mode(IntakeMode::Store);
+            })
+            .then(
+                Motion::drive(100.0, m.heading(268.0.deg()))
+                    .with_timeout(Duration::from_secs(4))
+                    .with_linear_output_limit(0.3),
+            )
+            .checkpoint("eject")
+            .then(Motion::drive(-12.0, m.heading(270.0.deg())).with_linear_output_limit(0.5))
+            .then(Motion::turn(m.heading(0.0.deg())))
+            .actuate("retract matchloader", |mech| {
+                _ = mech.matchloader.retract();
+            })
+            .actuate("eject", |mech| {
+                _ = mech
+                    .intake
+                    .set_voltage(IntakeStage::BACK_BOTTOM | IntakeStage::FRONT_BOTTOM, -12.0);
+            })
+            .wait(Duration::from_millis(100))
+            .actuate("stop back bottom", |mech| {
+                _ = mech.intake.set_voltage(IntakeStage::BACK_BOTTOM, 0.0);
+            })
+            .wait(Duration::from_millis(550))
+            .actuate("stop front bottom", |mech| {
+                _ = mech.intake.set_voltage(IntakeStage::FRONT_BOTTOM, 0.0);
+            })
+            .checkpoint("round 2")
+            .then(Motion::turn(m.heading(272.0.deg())))
+            .actuate("extend matchloader", |mech| {
+                _ = mech.matchloader.extend();
+                _ = mech.intake.set_voltage(IntakeStage::FRONT_BOTTOM, 12.0);
+            })
+            .then(
+                Motion::drive(100.0, m.heading(272.0.deg()))
+                    .with_linear_output_limit(0.4)
+                    .with_timeout(Duration::from_secs(2)),
+            )
+            .checkpoint("score")
+            .then(
+                Motion::drive(-100.0, m.heading(268.0.deg()))
+                    .with_linear_output_limit(0.5)
+                    .with_timeout(Duration::from_millis(1500)),
+            )
+            .actuate("score long", |mech| {
+                _ = mech.intake.set_mode(IntakeMode::ScoreLong);
+            })
+            .set_position((0.0, 0.0)) // odom reset
+            .wait(Duration::from_secs(2))
+            .checkpoint("snacky")
+            .actuate("retract matchloader", |mech| {
+                _ = mech.matchloader.retract();
+            })
+            .then(
+                Motion::drive(10.0, m.heading(268.0.deg()))
+                    .with_linear_tolerances(Tolerances::new().error(1.0).velocity(0.25))
+                    .with_angular_tolerances(Tolerances::new().velocity(0.05)),
+            )
+            .then(
+                Motion::drive(28.0, m.heading(90.0.deg()))
+                    .with_linear_output_limit(0.55)
+                    .with_angular_output_limit(1.0),
+            )
+            .actuate("stop intake", |mech| {
+                _ = mech.intake.set_mode(IntakeMode::Idle);
+            })
+            .then(
+                Motion::drive(19.0, m.heading(90.0.deg()))
+                    .with_linear_output_limit(0.7)
+                    .with_timeout(Duration::from_millis(1000)),
+            )
+            .run(self)
             .await;
 
-        // Eject
-        basic
-            .drive_distance_at_heading(dt, -12.0, 270.0.deg())
-            .with_linear_output_limit(0.5)
-            .await;
-        basic.turn_to_heading(dt, 0.0.deg()).await;
-        _ = self.matchloader.set_low();
+        // Hold position against pushes until autonomous ends. The hold never
+        // finishes, so report while it starts.
+        let mut basic = self.basic();
+        self.report.log();
         join(
-            async {
-                _ = self.intake_middle.set_voltage(-12.0);
-                sleep(Duration::from_millis(100)).await;
-                _ = self.intake_middle.set_voltage(0.0);
-            },
-            async {
-                _ = self.intake_front.set_voltage(-12.0);
-                sleep(Duration::from_millis(650)).await;
-            },
+            self.report.show(&mut self.controller),
+            basic
+                .drive_at_heading(&mut self.drivetrain, 0.0, m.heading(90.0.deg()))
+                .without_timeout()
+                .with_linear_error_tolerance(0.0)
+                .without_linear_velocity_tolerance(),
         )
         .await;
-
-        _ = self.intake_front.set_voltage(0.0);
-
-        // Round 2
-        basic.turn_to_heading(dt, 272.0.deg()).await;
-        _ = self.matchloader.set_high();
-        _ = self.intake_front.set_voltage(12.0);
-        basic
-            .drive_distance_at_heading(dt, 100.0, 272.0.deg())
-            .with_linear_output_limit(0.4)
-            .with_timeout(Duration::from_secs(2))
-            .await;
-
-        // Score
-        basic
-            .drive_distance_at_heading(dt, -100.0, 268.0.deg())
-            .with_linear_output_limit(0.5)
-            .with_timeout(Duration::from_millis(1500))
-            .await;
-        _ = self.intake_front.set_voltage(12.0);
-        _ = self.intake_middle.set_voltage(12.0);
-        _ = self.intake_score.set_voltage(12.0);
-        _ = self.intake_hood.set_voltage(12.0);
-        dt.tracking.set_position((0.0, 0.0)); // odom reset
-        sleep(Duration::from_secs(2)).await;
-
-        // Snacky
-        _ = self.matchloader.set_low();
-
-        basic
-            .drive_distance_at_heading(dt, 10.0, 268.0.deg())
-            .without_tolerance_duration()
-            .without_angular_error_tolerance()
-            .await;
-        basic
-            .drive_distance_at_heading(dt, 28.0, 90.0.deg())
-            .with_linear_output_limit(0.55)
-            .with_angular_output_limit(1.0)
-            .await;
-        _ = self.intake_front.set_voltage(0.0);
-        _ = self.intake_middle.set_voltage(0.0);
-        _ = self.intake_score.set_voltage(0.0);
-        _ = self.intake_hood.set_voltage(0.0);
-
-        // basic
-        //     .drive_distance_at_heading(dt, 19.0, 90.0.deg())
-        //     .with_linear_kp(2.0)
-        //     .with_timeout(Duration::from_millis(500))
-        //     .await;
-
-        basic
-            .drive_distance_at_heading(dt, 19.0, 90.0.deg())
-            .with_linear_output_limit(0.7)
-            .with_timeout(Duration::from_millis(1000))
-            .await;
-        basic
-            .drive_distance_at_heading(dt, 0.0, 90.0.deg())
-            .without_timeout()
-            .with_linear_error_tolerance(0.0)
-            .without_linear_velocity_tolerance()
-            .await;
     }
 }
@@ -1,11 +1,15 @@
-use std::time::Duration;
+use std::{pin::pin, time::Duration};
 
+use aubie2::{
+    motion::{basic::BasicExt, report::Target},
+    subsystems::intake::IntakeMode,
+};
 use evian::{
-    math::Angle,
+    math::{Angle, Vec2},
     motion::{Basic, Seeking},
     prelude::*,
 };
-use futures::future::join;
+use futures::future::{Either, join, select};
 use vexide::time::sleep;
 
 use crate::Nemo;
@@ -33,110 +37,148 @@ impl Nemo {
 
         {
             // Descore from park zone
-            _ = self.descore.set_high();
+            _ = self.mechanisms.descore.extend();
             sleep(Duration::from_millis(150)).await;
-            _ = self.intake_front.set_voltage(12.0);
-            _ = self.intake_middle.set_voltage(12.0);
-            _ = self.intake_score.set_voltage(1.0);
-            _ = self.intake_hood.set_voltage(-12.0);
+            _ = self.mechanisms.intake.set_mode(IntakeMode::Store);
+            basic
+                .drive_at_heading(dt, -10.0, 270.0.deg())
+                .with_report(&self.report, "back off descore")
+                .await;
             basic
-                .drive_distance_at_heading(dt, -10.0, 270.0.deg())
+                .turn_to(dt, 0.0.deg())
+                .with_report(&self.report, "face matchloader")
                 .await;
-            basic.turn_to_heading(dt, 0.0.deg()).await;
+            let timer = self.report.begin(
+                "go to matchloader",
+                &dt.tracking,
+                Target::Point(Vec2::new(50.0, 9.0)),
+            );
             seeking
                 .move_to_point(dt, (50.0, 9.0))
                 .with_linear_output_limit(0.7)
                 .await;
+            timer.finish(&dt.tracking, seeking.tolerances, Tolerances::new());
             println!("{}", dt.tracking.position());
-            basic.turn_to_heading(dt, 270.0.deg()).await;
+            basic
+                .turn_to(dt, 270.0.deg())
+                .with_report(&self.report, "turn to matchloader")
+                .await;
 
             // Reset
             basic
-                .drive_distance_at_heading(dt, -12.0, 270.0.deg())
+                .drive_at_heading(dt, -12.0, 270.0.deg())
                 .with_linear_output_limit(0.5)
                 .with_timeout(Duration::from_secs(1))
+                .with_report(&self.report, "square up")
                 .await;
-            _ = self.intake_hood.set_voltage(12.0);
-            _ = self.intake_score.set_voltage(12.0);
+            _ = self.mechanisms.intake.set_mode(IntakeMode::ScoreLong);
             sleep(Duration::from_millis(1000)).await; // ensure we're fully settled
             dt.tracking.set_position((0.0, 0.0));
-            _ = self.descore.set_low();
+            _ = self.mechanisms.descore.retract();
 
-            // Matchloader
-            _ = self.matchloader.set_high();
-            _ = self.intake_front.set_voltage(12.0);
-            _ = self.intake_middle.set_voltage(12.0);
-            _ = self.intake_score.set_voltage(1.0);
-            _ = self.intake_hood.set_voltage(-12.0);
-            basic
-                .drive_distance_at_heading(dt, 100.0, 270.0.deg())
-                .with_timeout(Duration::from_secs(4))
+            // Matchloader, until every block is in
+            _ = self.mechanisms.matchloader.extend();
+            _ = self.mechanisms.intake.set_mode(IntakeMode::Store);
+            let full = self.mechanisms.intake.stored_blocks() + Nemo::MATCHLOAD_BLOCKS;
+            let mut matchload = basic.drive_at_heading(dt, 100.0, 270.0.deg());
+            matchload
+                .without_timeout()
                 .with_linear_output_limit(0.4)
-                .await;
+                .with_report(&self.report, "matchload");
+            let matchload = pin!(matchload);
+            let stored = pin!(
+                self.mechanisms
+                    .intake
+                    .wait_for_stored(full, Duration::from_secs(4))
+            );
+            if let Either::Right((false, _)) = select(matchload, stored).await {
+                println!("Matchloader timed out before filling the robot");
+            }
 
             // Score
             basic
-                .drive_distance_at_heading(dt, -100.0, 270.0.deg())
+                .drive_at_heading(dt, -100.0, 270.0.deg())
                 .with_linear_output_limit(0.5)
                 .with_timeout(Duration::from_millis(1500))
+                .with_report(&self.report, "score")
                 .await;
-            _ = self.intake_front.set_voltage(12.0);
-            _ = self.intake_middle.set_voltage(12.0);
-            _ = self.intake_score.set_voltage(12.0);
-            _ = self.intake_hood.set_voltage(12.0);
+            _ = self.mechanisms.intake.set_mode(IntakeMode::ScoreLong);
             dt.tracking.set_position((0.0, 0.0)); // odom reset
             sleep(Duration::from_secs(2)).await;
 
             // go
-            _ = self.matchloader.set_low();
+            _ = self.mechanisms.matchloader.retract();
 
-            basic.drive_distance_at_heading(dt, 18.0, 270.0.deg()).await;
-            basic.turn_to_heading(dt, 0.0.deg()).await;
-            basic.drive_distance_at_heading(dt, 16.0, 0.0.deg()).await;
-            basic.turn_to_heading(dt, 90.0.deg()).await;
+            basic
+                .drive_at_heading(dt, 18.0, 270.0.deg())
+                .with_report(&self.report, "leave goal")
+                .await;
+            basic
+                .turn_to(dt, 0.0.deg())
+                .with_report(&self.report, "turn across")
+                .await;
+            basic
+                .drive_at_heading(dt, 16.0, 0.0.deg())
+                .with_report(&self.report, "cross over")
+                .await;
+            basic
+                .turn_to(dt, 90.0.deg())
+                .with_report(&self.report, "turn along wall")
+                .await;
 
-            basic.drive_distance_at_heading(dt, 90.0, 89.0.deg()).await;
-            basic.turn_to_heading(dt, 0.0.deg()).await;
-            basic.drive_distance_at_heading(dt, -12.25, 0.0.deg()).await;
-            basic.turn_to_heading(dt, 90.0.deg()).await;
+            basic
+                .drive_at_heading(dt, 90.0, 89.0.deg())
+                .with_report(&self.report, "drive along wall")
+                .await;
+            basic
+                .turn_to(dt, 0.0.deg())
+                .with_report(&self.report, "turn to goal")
+                .await;
+            basic
+                .drive_at_heading(dt, -12.25, 0.0.deg())
+                .with_report(&self.report, "line up goal")
+                .await;
+            basic
+                .turn_to(dt, 90.0.deg())
+                .with_report(&self.report, "face wall")
+                .await;
         }
 
         {
             // Reset
             basic
-                .drive_distance_at_heading(dt, -100.0, 88.0.deg())
+                .drive_at_heading(dt, -100.0, 88.0.deg())
                 .with_linear_output_limit(0.5)
                 .with_timeout(Duration::from_millis(1000))
+                .with_report(&self.report, "square up")
                 .await;
-            _ = self.intake_hood.set_voltage(12.0);
-            _ = self.intake_score.set_voltage(12.0);
+            _ = self.mechanisms.intake.set_mode(IntakeMode::ScoreLong);
             sleep(Duration::from_millis(500)).await; // ensure we're fully settled
             dt.tracking.set_position((0.0, 0.0));
-            _ = self.descore.set_low();
+            _ = self.mechanisms.descore.retract();
 
             // Matchloader
-            _ = self.matchloader.set_high();
-            _ = self.intake_front.set_voltage(12.0);
-            _ = self.intake_middle.set_voltage(12.0);
-            _ = self.intake_score.set_voltage(1.0);
-            _ = self.intake_hood.set_voltage(-12.0);
+            _ = self.mechanisms.matchloader.extend();
+            _ = self.mechanisms.intake.set_mode(IntakeMode::Store);
             basic
-                .drive_distance_at_heading(dt, 100.0, 90.0.deg())
+                .drive_at_heading(dt, 100.0, 90.0.deg())
                 .with_timeout(Duration::from_secs(4))
                 .with_linear_output_limit(0.3)
+                .with_report(&self.report, "matchload")
                 .await;
 
             // Score
             basic
-                .drive_distance_at_heading(dt, -100.0, 90.0.deg())
+                .drive_at_heading(dt, -100.0, 90.0.deg())
                 .with_linear_output_limit(0.5)
                 .with_timeout(Duration::from_millis(1500))
+                .with_report(&self.report, "score")
+                .await;
+            _ = self.mechanisms.intake.set_mode(IntakeMode::ScoreLong);
+            basic
+                .turn_to(dt, 90.0.deg())
+                .with_report(&self.report, "straighten")
                 .await;
-            _ = self.intake_front.set_voltage(12.0);
-            _ = self.intake_middle.set_voltage(12.0);
-            _ = self.intake_score.set_voltage(12.0);
-            _ = self.intake_hood.set_voltage(12.0);
-            basic.turn_to_heading(dt, 90.0.deg()).await;
             dt.tracking.set_position((0.0, 0.0)); // odom reset
             sleep(Duration::from_secs(2)).await;
         }
@@ -144,99 +186,128 @@ impl Nemo {
         // go
         dt.tracking
             .set_heading(Angle::HALF_TURN + dt.tracking.heading());
-        _ = self.matchloader.set_low();
-        basic.drive_distance_at_heading(dt, 10.0, 270.0.deg()).await;
-        basic.turn_to_heading(dt, 0.0.deg()).await;
-        basic.drive_distance_at_heading(dt, 49.0, 0.0.deg()).await;
-        basic.turn_to_heading(dt, 270.0.deg()).await;
+        _ = self.mechanisms.matchloader.retract();
+        basic
+            .drive_at_heading(dt, 10.0, 270.0.deg())
+            .with_report(&self.report, "leave goal")
+            .await;
+        basic
+            .turn_to(dt, 0.0.deg())
+            .with_report(&self.report, "turn across")
+            .await;
+        basic
+            .drive_at_heading(dt, 49.0, 0.0.deg())
+            .with_report(&self.report, "cross field")
+            .await;
+        basic
+            .turn_to(dt, 270.0.deg())
+            .with_report(&self.report, "face park zone")
+            .await;
         basic
-            .drive_distance(dt, 10.0)
+            .drive_at_heading(dt, 10.0, 270.0.deg())
             .with_timeout(Duration::from_millis(800))
+            .with_report(&self.report, "square on park zone")
             .await;
         dt.tracking.set_position((0.0, 0.0)); // odom reset
 
         {
             // Descore from park zone
-            _ = self.descore.set_high();
+            _ = self.mechanisms.descore.extend();
             sleep(Duration::from_millis(50)).await;
-            _ = self.intake_front.set_voltage(12.0);
-            _ = self.intake_middle.set_voltage(12.0);
-            _ = self.intake_score.set_voltage(1.0);
-            _ = self.intake_hood.set_voltage(-12.0);
+            _ = self.mechanisms.intake.set_mode(IntakeMode::Store);
+            basic
+                .drive_at_heading(dt, -10.0, 270.0.deg())
+                .with_report(&self.report, "back off descore")
+                .await;
             basic
-                .drive_distance_at_heading(dt, -10.0, 270.0.deg())
+                .turn_to(dt, 0.0.deg())
+                .with_report(&self.report, "face matchloader")
                 .await;
-            basic.turn_to_heading(dt, 0.0.deg()).await;
+            let timer = self.report.begin(
+                "go to matchloader",
+                &dt.tracking,
+                Target::Point(Vec2::new(50.0, 9.0)),
+            );
             seeking
                 .move_to_point(dt, (50.0, 9.0))
                 .with_linear_output_limit(0.7)
                 .await;
+            timer.finish(&dt.tracking, seeking.tolerances, Tolerances::new());
             println!("{}", dt.tracking.position());
-            basic.turn_to_heading(dt, 270.0.deg()).await;
+            basic
+                .turn_to(dt, 270.0.deg())
+                .with_report(&self.report, "turn to matchloader")
+                .await;
 
             // Reset
             basic
-                .drive_distance_at_heading(dt, -12.0, 270.0.deg())
+                .drive_at_heading(dt, -12.0, 270.0.deg())
                 .with_linear_output_limit(0.5)
                 .with_timeout(Duration::from_secs(1))
+                .with_report(&self.report, "square up")
                 .await;
-            _ = self.intake_hood.set_voltage(12.0);
-            _ = self.intake_score.set_voltage(12.0);
+            _ = self.mechanisms.intake.set_mode(IntakeMode::ScoreLong);
             sleep(Duration::from_millis(1000)).await; // ensure we're fully settled
             dt.tracking.set_position((0.0, 0.0));
-            _ = self.descore.set_low();
+            _ = self.mechanisms.descore.retract();
 
             // Matchloader
-            _ = self.matchloader.set_high();
-            _ = self.intake_front.set_voltage(12.0);
-            _ = self.intake_middle.set_voltage(12.0);
-            _ = self.intake_score.set_voltage(12.0);
-            _ = self.intake_hood.set_voltage(12.0);
+            _ = self.mechanisms.matchloader.extend();
+            _ = self.mechanisms.intake.set_mode(IntakeMode::ScoreLong);
 
             join(
                 async {
                     basic
-                        .drive_distance_at_heading(dt, 100.0, 268.0.deg())
+                        .drive_at_heading(dt, 100.0, 268.0.deg())
                         .with_timeout(Duration::from_millis(4250))
                         .with_linear_output_limit(0.3)
+                        .with_report(&self.report, "matchload")
                         .await;
                 },
                 async {
                     sleep(Duration::from_millis(2500)).await;
-                    _ = self.intake_score.set_voltage(1.0);
-                    _ = self.intake_hood.set_voltage(-12.0);
+                    _ = self.mechanisms.intake.set_mode(IntakeMode::Store);
                 },
             )
             .await;
 
             // Score
             basic
-                .drive_distance_at_heading(dt, -100.0, 268.0.deg())
+                .drive_at_heading(dt, -100.0, 268.0.deg())
                 .with_linear_output_limit(0.5)
                 .with_timeout(Duration::from_millis(1500))
+                .with_report(&self.report, "score")
                 .await;
-            _ = self.intake_front.set_voltage(12.0);
-            _ = self.intake_middle.set_voltage(12.0);
-            _ = self.intake_score.set_voltage(12.0);
-            _ = self.intake_hood.set_voltage(12.0);
+            _ = self.mechanisms.intake.set_mode(IntakeMode::ScoreLong);
             dt.tracking.set_position((0.0, 0.0)); // odom reset
             sleep(Duration::from_secs(2)).await;
 
             // park
-            _ = self.matchloader.set_low();
+            _ = self.mechanisms.matchloader.retract();
             basic
-                .drive_distance_at_heading(dt, 10.0, 268.0.deg())
+                .drive_at_heading(dt, 10.0, 268.0.deg())
                 .without_tolerance_duration()
                 .without_angular_error_tolerance()
+                .with_report(&self.report, "leave goal")
                 .await;
             basic
-                .drive_distance_at_heading(dt, 48.0, 90.0.deg())
+                .drive_at_heading(dt, 48.0, 90.0.deg())
                 .without_tolerance_duration()
                 .without_angular_error_tolerance()
+                .with_report(&self.report, "turn to park")
+                .await;
+            basic
+                .drive_at_heading(dt, 75.0, 90.0.deg())
+                .with_report(&self.report, "drive to park")
+                .await;
+            basic
+                .turn_to(dt, -20.0.deg())
+                .with_report(&self.report, "face park")
+                .await;
+            basic
+                .drive_at_heading(dt, -26.0, 0.0.deg())
+                .with_report(&self.report, "park")
                 .await;
-            basic.drive_distance_at_heading(dt, 75.0, 90.0.deg()).await;
-            basic.turn_to_heading(dt, -20.0.deg()).await;
-            basic.drive_distance_at_heading(dt, -26.0, 0.0.deg()).await;
         }
     }
 }